@@ -23,6 +23,7 @@ pub trait LD {
 impl LD for serde_json::Value {
 	fn ld_context(mut self) -> Self {
 		let o_type = self.object_type();
+		let is_actor = matches!(o_type, Ok(crate::ObjectType::Actor(_)));
 		if let Some(obj) = self.as_object_mut() {
 			let mut ctx = serde_json::Map::new();
 			ctx.insert("sensitive".to_string(), serde_json::Value::String("as:sensitive".into()));
@@ -50,14 +51,16 @@ impl LD for serde_json::Value {
 				},
 				_ => {},
 			}
-			obj.insert(
-				"@context".to_string(),
-				serde_json::Value::Array(vec![
-					serde_json::Value::String("https://www.w3.org/ns/activitystreams".into()),
-					serde_json::Value::String("https://w3id.org/security/v1".into()),
-					serde_json::Value::Object(ctx),
-				]),
-			);
+			let mut context = vec![
+				serde_json::Value::String("https://www.w3.org/ns/activitystreams".into()),
+				serde_json::Value::String("https://w3id.org/security/v1".into()),
+			];
+			if is_actor {
+				// for assertionMethod keys
+				context.push(serde_json::Value::String("https://w3id.org/security/multikey/v1".into()));
+			}
+			context.push(serde_json::Value::Object(ctx));
+			obj.insert("@context".to_string(), serde_json::Value::Array(context));
 		} else {
 			tracing::warn!("cannot add @context to json value different than object");
 		}
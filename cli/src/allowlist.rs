@@ -0,0 +1,48 @@
+use sea_orm::DbErr;
+
+#[derive(Debug, Clone, clap::Subcommand)]
+/// available actions on federation allowlist
+pub enum AllowlistCommand {
+	/// show all allowed instances, both configured and added at runtime
+	List,
+	/// start federating with an instance (and its subdomains)
+	Add {
+		/// instance domain, like `example.org`
+		domain: String,
+	},
+	/// stop federating with an instance previously added at runtime
+	Remove {
+		/// instance domain, like `example.org`
+		domain: String,
+	},
+}
+
+pub async fn allowlist(ctx: upub::Context, action: AllowlistCommand) -> Result<(), DbErr> {
+	if !ctx.cfg().federation.allowlist_only {
+		tracing::warn!("allowlist mode is disabled in config, allowed instances have no effect");
+	}
+
+	match action {
+		AllowlistCommand::List => {
+			for domain in &ctx.cfg().federation.allowlist {
+				tracing::info!("[cfg] {domain}");
+			}
+			for domain in upub::allowlist::list(ctx.db()).await? {
+				tracing::info!("[db] {domain}");
+			}
+		},
+		AllowlistCommand::Add { domain } => {
+			upub::allowlist::allow(ctx.db(), &domain).await?;
+			tracing::info!("allowed federation with {domain}");
+		},
+		AllowlistCommand::Remove { domain } => {
+			if upub::allowlist::disallow(ctx.db(), &domain).await? {
+				tracing::info!("disallowed federation with {domain}");
+			} else {
+				tracing::warn!("{domain} was not allowed at runtime (configured instances must be removed from config)");
+			}
+		},
+	}
+
+	Ok(())
+}
@@ -0,0 +1,293 @@
+use sea_orm::{sea_query::LikeExpr, ColumnTrait, Condition, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder};
+use upub::model::{self, domain_block::Severity};
+
+/// columns of mastodon `domain_blocks.csv`, as exported from admin panel
+const MASTODON_HEADERS: [&str; 6] = ["#domain", "#severity", "#reject_media", "#reject_reports", "#public_comment", "#obfuscate"];
+
+#[derive(Debug, Clone, clap::Subcommand)]
+/// available actions on instance blocks
+pub enum BlocksCommand {
+	/// show all blocked instances
+	List,
+	/// block an instance (and its subdomains), or change an existing block
+	Add {
+		/// instance domain, like `example.org`
+		domain: String,
+
+		#[arg(long, default_value = "suspend")]
+		/// one of reject_media, silence, suspend
+		severity: Severity,
+
+		#[arg(long)]
+		/// reason shown on public blocklist
+		public_reason: Option<String>,
+
+		#[arg(long)]
+		/// reason only visible to admins
+		private_reason: Option<String>,
+	},
+	/// lift block from an instance
+	Remove {
+		/// instance domain, like `example.org`
+		domain: String,
+	},
+	/// add blocks from a mastodon `domain_blocks.csv` file, replacing existing ones for same domains
+	Import {
+		/// path to csv file
+		file: std::path::PathBuf,
+
+		#[arg(long, default_value_t = false)]
+		/// don't block anything, only show known instances and local relations that would be affected
+		dry_run: bool,
+	},
+	/// write all blocks to a mastodon compatible `domain_blocks.csv` file
+	Export {
+		/// path to csv file
+		file: std::path::PathBuf,
+	},
+}
+
+pub async fn blocks(ctx: upub::Context, action: BlocksCommand) -> Result<(), Box<dyn std::error::Error>> {
+	match action {
+		BlocksCommand::List => {
+			for block in model::domain_block::Entity::find()
+				.order_by_asc(model::domain_block::Column::Domain)
+				.all(ctx.db())
+				.await?
+			{
+				tracing::info!(
+					"[{}] {} ({}) public: {:?} private: {:?}",
+					block.severity.as_str(), block.domain, block.published, block.public_reason, block.private_reason
+				);
+			}
+		},
+		BlocksCommand::Add { domain, severity, public_reason, private_reason } => {
+			upub::blocks::block(ctx.db(), &domain, severity, public_reason, private_reason).await?;
+			tracing::info!("blocked {domain} with severity {}", severity.as_str());
+		},
+		BlocksCommand::Remove { domain } => {
+			if upub::blocks::unblock(ctx.db(), &domain).await? {
+				tracing::info!("unblocked {domain}");
+			} else {
+				tracing::warn!("{domain} was not blocked");
+			}
+		},
+		BlocksCommand::Import { file, dry_run } => {
+			let imported = read_mastodon_csv(std::fs::File::open(&file)?)?;
+			tracing::info!("read {} blocks from {}", imported.len(), file.display());
+			for block in imported {
+				if dry_run {
+					impact(&ctx, &block.domain, block.severity).await?;
+				} else {
+					upub::blocks::block(
+						ctx.db(), &block.domain, block.severity, block.public_reason,
+						Some(format!("imported from {}", file.display())),
+					).await?;
+					tracing::info!("blocked {} with severity {}", block.domain, block.severity.as_str());
+				}
+			}
+		},
+		BlocksCommand::Export { file } => {
+			let mut writer = csv::Writer::from_path(&file)?;
+			writer.write_record(MASTODON_HEADERS)?;
+			let mut count = 0;
+			for block in model::domain_block::Entity::find()
+				.order_by_asc(model::domain_block::Column::Domain)
+				.all(ctx.db())
+				.await?
+			{
+				let severity = match block.severity {
+					Severity::RejectMedia => "noop",
+					Severity::Silence => "silence",
+					Severity::Suspend => "suspend",
+				};
+				// our stronger severities always imply rejecting media
+				writer.write_record([
+					block.domain.as_str(), severity, "true", "false",
+					block.public_reason.as_deref().unwrap_or_default(), "false",
+				])?;
+				count += 1;
+			}
+			writer.flush()?;
+			tracing::info!("exported {count} blocks to {}", file.display());
+		},
+	}
+
+	Ok(())
+}
+
+struct ImportedBlock {
+	domain: String,
+	severity: Severity,
+	public_reason: Option<String>,
+}
+
+fn read_mastodon_csv(input: impl std::io::Read) -> Result<Vec<ImportedBlock>, csv::Error> {
+	let mut reader = csv::ReaderBuilder::new()
+		.has_headers(false)
+		.flexible(true)
+		.from_reader(input);
+
+	// header is optional: very old exports and most shared lists are just one domain per line
+	let mut columns : Vec<String> = MASTODON_HEADERS.iter().map(|x| x.to_string()).collect();
+	let mut out = Vec::new();
+	for (i, record) in reader.records().enumerate() {
+		let record = record?;
+		if i == 0 && record.get(0).is_some_and(|x| x.starts_with('#')) {
+			columns = record.iter().map(|x| x.trim().to_string()).collect();
+			continue;
+		}
+
+		let Some(domain) = field(&columns, &record, "#domain") else { continue };
+		let reject_media = field(&columns, &record, "#reject_media").is_some_and(|x| x == "true");
+		let severity = match field(&columns, &record, "#severity").unwrap_or("suspend") {
+			"suspend" => Severity::Suspend,
+			"silence" => Severity::Silence,
+			"noop" if reject_media => Severity::RejectMedia,
+			// there are no reports to reject here, so that alone does nothing too
+			"noop" => {
+				tracing::debug!("skipping {domain}: block has no effect");
+				continue;
+			},
+			x => {
+				tracing::warn!("skipping {domain}: unknown severity '{x}'");
+				continue;
+			},
+		};
+
+		out.push(ImportedBlock {
+			domain: upub::blocks::normalize(domain),
+			severity,
+			public_reason: field(&columns, &record, "#public_comment").map(|x| x.to_string()),
+		});
+	}
+
+	Ok(out)
+}
+
+fn field<'a>(columns: &[String], record: &'a csv::StringRecord, name: &str) -> Option<&'a str> {
+	columns
+		.iter()
+		.position(|x| x == name)
+		.and_then(|i| record.get(i))
+		.map(|x| x.trim())
+		.filter(|x| !x.is_empty())
+}
+
+/// matches any subdomain of given domain, which may itself contain like wildcards
+fn subdomains(domain: &str) -> LikeExpr {
+	let escaped = domain.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+	LikeExpr::new(format!("%.{escaped}")).escape('\\')
+}
+
+/// log known instances and local relations which given block would affect
+async fn impact(ctx: &upub::Context, domain: &str, severity: Severity) -> Result<(), sea_orm::DbErr> {
+	let current = upub::blocks::severity(ctx.db(), domain).await?;
+	if current.is_some_and(|x| x >= severity) {
+		tracing::info!("[=] {domain} ({}): already blocked as {}", severity.as_str(), current.map(|x| x.as_str()).unwrap_or_default());
+		return Ok(());
+	}
+
+	let instances = model::instance::Entity::find()
+		.filter(
+			Condition::any()
+				.add(model::instance::Column::Domain.eq(domain))
+				.add(model::instance::Column::Domain.like(subdomains(domain)))
+		)
+		.all(ctx.db())
+		.await?;
+
+	if instances.is_empty() {
+		tracing::info!("[+] {domain} ({}): never seen, nothing affected", severity.as_str());
+		return Ok(());
+	}
+
+	let local = ctx.instance().internal;
+	for instance in instances {
+		let followers = model::relation::Entity::find()
+			.filter(model::relation::Column::FollowerInstance.eq(instance.internal))
+			.filter(model::relation::Column::FollowingInstance.eq(local))
+			.count(ctx.db())
+			.await?;
+		let following = model::relation::Entity::find()
+			.filter(model::relation::Column::FollowerInstance.eq(local))
+			.filter(model::relation::Column::FollowingInstance.eq(instance.internal))
+			.count(ctx.db())
+			.await?;
+		tracing::info!(
+			"[+] {} ({}): {} users, {} posts, follows {followers} local users, followed by {following} local users",
+			instance.domain, severity.as_str(), instance.users.unwrap_or_default(), instance.posts.unwrap_or_default(),
+		);
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use upub::model::domain_block::Severity;
+
+	use super::{read_mastodon_csv, subdomains};
+
+	fn read(csv: &str) -> Vec<(String, Severity, Option<String>)> {
+		read_mastodon_csv(csv.as_bytes())
+			.unwrap()
+			.into_iter()
+			.map(|x| (x.domain, x.severity, x.public_reason))
+			.collect()
+	}
+
+	#[test]
+	fn header_is_detected_and_columns_follow_it() {
+		let csv = "#severity,#domain,#public_comment\nsilence,Example.ORG,spam\n";
+		assert_eq!(read(csv), vec![("example.org".to_string(), Severity::Silence, Some("spam".to_string()))]);
+	}
+
+	#[test]
+	fn bare_domain_lists_are_suspended() {
+		let csv = "example.org\n*.evil.com\n\n";
+		assert_eq!(
+			read(csv),
+			vec![
+				("example.org".to_string(), Severity::Suspend, None),
+				("evil.com".to_string(), Severity::Suspend, None),
+			]
+		);
+	}
+
+	#[test]
+	fn severities_are_mapped() {
+		let csv = "#domain,#severity,#reject_media,#reject_reports,#public_comment,#obfuscate\n\
+			a.example,suspend,false,false,,false\n\
+			b.example,silence,true,false,,false\n\
+			c.example,noop,true,false,,false\n\
+			d.example,noop,false,true,,false\n\
+			e.example,noop,false,false,,false\n\
+			f.example,defederate,false,false,,false\n";
+		assert_eq!(
+			read(csv),
+			vec![
+				("a.example".to_string(), Severity::Suspend, None),
+				("b.example".to_string(), Severity::Silence, None),
+				("c.example".to_string(), Severity::RejectMedia, None),
+			]
+		);
+	}
+
+	#[test]
+	fn quoted_fields_keep_commas() {
+		let csv = "#domain,#severity,#reject_media,#reject_reports,#public_comment,#obfuscate\n\
+			\"example.org\",\"suspend\",false,false,\"spam, harassment\",false\n";
+		assert_eq!(read(csv), vec![("example.org".to_string(), Severity::Suspend, Some("spam, harassment".to_string()))]);
+	}
+
+	#[test]
+	fn like_wildcards_are_escaped() {
+		use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryTrait};
+		let sql = upub::model::instance::Entity::find()
+			.filter(upub::model::instance::Column::Domain.like(subdomains("ex_am%ple.com")))
+			.build(sea_orm::DbBackend::Sqlite)
+			.to_string();
+		assert!(sql.contains(r"LIKE '%.ex\_am\%ple.com' ESCAPE '\'"), "{sql}");
+	}
+}
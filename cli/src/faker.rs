@@ -24,6 +24,7 @@ pub async fn faker(ctx: upub::Context, count: i64) -> Result<(), sea_orm::DbErr>
 		fields: JsonVec::default(),
 		also_known_as: JsonVec::default(),
 		moved_to: None,
+		deleted: None,
 		icon: Some("https://cdn.alemi.dev/social/circle-square.png".to_string()),
 		image: Some("https://cdn.alemi.dev/social/someriver-xs.jpg".to_string()),
 		inbox: None,
@@ -54,8 +55,14 @@ pub async fn faker(ctx: upub::Context, count: i64) -> Result<(), sea_orm::DbErr>
 		internal: NotSet,
 		actor: Set(test_user.id.clone()),
 		login: Set("mail@example.net".to_string()),
-		password: Set(sha256::digest("very-strong-password")),
+		password: Set(upub::passwords::hash_async("very-strong-password".to_string()).await),
 		active: Set(true),
+		totp: Set(None),
+		recovery_codes: Set(None),
+		reason: Set(None),
+		totp_step: Set(None),
+		totp_failures: Set(0),
+		totp_locked_until: Set(None),
 	}).exec(db).await?;
 
 	let context = uuid::Uuid::new_v4().to_string();
@@ -5,7 +5,7 @@ pub async fn fetch(ctx: upub::Context, uri: String, save: bool, actor: Option<St
 	use apb::Base;
 
 	let mut pkey = ctx.pkey().to_string();
-	let mut from = ctx.base().to_string();
+	let mut key_id = ctx.key_id().to_string();
 	
 	if let Some(actor) = actor {
 		let actor_model = upub::model::actor::Entity::find_by_ap_id(&actor)
@@ -13,11 +13,11 @@ pub async fn fetch(ctx: upub::Context, uri: String, save: bool, actor: Option<St
 			.await?
 			.ok_or_else(|| sea_orm::DbErr::RecordNotFound(actor.clone()))?;
 
-		match actor_model.private_key {
+		match upub::keys::signing(ctx.db(), &actor_model).await? {
 			None => tracing::error!("requested actor lacks a private key, fetching with server key instead"),
-			Some(x) => {
+			Some((id, x)) => {
 				pkey = x;
-				from = actor.to_string();
+				key_id = id;
 			},
 		}
 	}
@@ -25,7 +25,7 @@ pub async fn fetch(ctx: upub::Context, uri: String, save: bool, actor: Option<St
 	let mut node = apb::Node::link(uri.to_string());
 	if let apb::Node::Link(ref uri) = node {
 		if let Ok(href) = uri.href() {
-			node = upub::Context::request(reqwest::Method::GET, &href, None, &from, &pkey, ctx.domain())
+			node = ctx.request(reqwest::Method::GET, &href, None, &key_id, &pkey, ctx.domain())
 				.await?
 				.json::<serde_json::Value>()
 				.await?
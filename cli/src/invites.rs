@@ -0,0 +1,64 @@
+use sea_orm::DbErr;
+
+#[derive(Debug, Clone, clap::Subcommand)]
+/// available actions on registration invites
+pub enum InvitesCommand {
+	/// show all invites, with their usage
+	List,
+	/// create a new invite code
+	Create {
+		/// how many users can register with this code, unlimited if not given
+		#[arg(long)]
+		max_uses: Option<i32>,
+
+		/// code stops working after these many hours, never expires if not given
+		#[arg(long)]
+		expires_hours: Option<i64>,
+	},
+	/// delete an invite code, so that nobody else can use it
+	Revoke {
+		/// invite code
+		code: String,
+	},
+}
+
+pub async fn invites(ctx: upub::Context, action: InvitesCommand) -> Result<(), DbErr> {
+	match action {
+		InvitesCommand::List => {
+			for invite in upub::invites::list(ctx.db(), None).await? {
+				tracing::info!(
+					"{} [{}/{}] by {} expires {} {}",
+					invite.code,
+					invite.uses,
+					invite.max_uses.map(|x| x.to_string()).unwrap_or("∞".to_string()),
+					invite.created_by.as_deref().unwrap_or("cli"),
+					invite.expires.map(|x| x.to_rfc3339()).unwrap_or("never".to_string()),
+					if invite.usable() { "" } else { "(unusable)" },
+				);
+			}
+		},
+		InvitesCommand::Create { max_uses, expires_hours } => {
+			let expires = match expires_hours {
+				None => None,
+				Some(h) => match upub::invites::expiry(h) {
+					Some(x) => Some(x),
+					None => {
+						tracing::error!("invalid expiration: {h} hours");
+						return Ok(());
+					},
+				},
+			};
+			let invite = upub::invites::create(ctx.db(), None, max_uses, expires).await?;
+			tracing::info!("created invite {}: {}", invite.code, upub::invites::link(&ctx, &invite.code));
+		},
+		InvitesCommand::Revoke { code } => {
+			if upub::invites::revoke(ctx.db(), &code, None).await? {
+				tracing::info!("revoked invite {code}");
+			} else {
+				tracing::warn!("no invite with code {code}");
+			}
+		},
+	}
+
+	Ok(())
+}
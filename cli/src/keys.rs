@@ -0,0 +1,89 @@
+use apb::{ActivityMut, BaseMut, ObjectMut};
+use sea_orm::{ActiveModelTrait, ActiveValue::{NotSet, Set, Unchanged}, ColumnTrait, DbErr, EntityTrait, QueryFilter, TransactionTrait};
+
+pub async fn rotate_key(ctx: upub::Context, actor: String, grace_hours: i64) -> Result<(), Box<dyn std::error::Error>> {
+	let uid = if actor.starts_with("http") { actor } else { ctx.uid(&actor) };
+	let user = upub::model::actor::Entity::find_by_ap_id(&uid)
+		.one(ctx.db())
+		.await?
+		.ok_or_else(|| DbErr::RecordNotFound(uid.clone()))?;
+
+	if user.private_key.is_none() {
+		return Err(format!("{uid} is not a local actor, can't rotate its keys").into());
+	}
+
+	let key = openssl::rsa::Rsa::generate(2048)?;
+	let private_key = std::str::from_utf8(&key.private_key_to_pem()?)?.to_string();
+	let public_key = std::str::from_utf8(&key.public_key_to_pem()?)?.to_string();
+	let now = chrono::Utc::now();
+	let key_id = format!("{uid}#key-{}", now.timestamp());
+	let (ed_public_key, ed_private_key) = upub::keys::ed25519()?;
+
+	let tx = ctx.db().begin().await?;
+
+	// keep previous keys valid for a while: remotes may still be processing our signed requests
+	upub::model::key::Entity::update_many()
+		.col_expr(upub::model::key::Column::Expires, sea_orm::sea_query::Expr::value(now + chrono::Duration::hours(grace_hours)))
+		.filter(upub::model::key::Column::Actor.eq(&uid))
+		.filter(upub::model::key::Column::Expires.is_null())
+		.exec(&tx)
+		.await?;
+
+	upub::model::key::Entity::insert_many([
+		upub::keys::local(key_id.clone(), uid.clone(), upub::keys::RSA_SHA256, public_key.clone(), private_key.clone()),
+		upub::keys::local(format!("{uid}#ed25519-{}", now.timestamp()), uid.clone(), upub::keys::ED25519, ed_public_key, ed_private_key),
+	])
+		.exec(&tx)
+		.await?;
+
+	let user = upub::model::actor::ActiveModel {
+		internal: Unchanged(user.internal),
+		public_key: Set(public_key),
+		private_key: Set(Some(private_key)),
+		updated: Set(now),
+		..Default::default()
+	}
+		.update(&tx)
+		.await?;
+
+	tx.commit().await?;
+
+	tracing::info!("rotated key of {uid}, now signing with {key_id}");
+
+	if uid == ctx.base() {
+		tracing::warn!("application key rotated: restart all upub processes to start signing with it");
+		return Ok(());
+	}
+
+	// let everyone know about our new keys: the plain actor document would still advertise just the
+	// legacy main key, so publish all currently valid ones
+	let followers = user.followers.clone().unwrap_or_else(|| upub::url!(ctx, "/actors/{}/followers", user.preferred_username));
+	let document = upub::keys::publish(ctx.db(), ctx.ap(user)).await?;
+	let aid = ctx.aid(&upub::Context::new_id());
+	let payload = apb::new()
+		.set_id(Some(aid.clone()))
+		.set_activity_type(Some(apb::ActivityType::Update))
+		.set_actor(apb::Node::link(uid.clone()))
+		.set_object(apb::Node::object(document))
+		.set_to(apb::Node::links(vec![apb::target::PUBLIC.to_string()]))
+		.set_cc(apb::Node::links(vec![followers]))
+		.set_published(Some(now));
+	let job = upub::model::job::ActiveModel {
+		internal: NotSet,
+		activity: Set(aid),
+		job_type: Set(upub::model::job::JobType::Outbound),
+		actor: Set(uid),
+		target: Set(None),
+		payload: Set(Some(payload)),
+		attempt: Set(0),
+		published: Set(now),
+		not_before: Set(now),
+		error: Set(None),
+		locked_until: Set(None),
+		domain: Set(None),
+	};
+	upub::model::job::Entity::insert(job).exec(ctx.db()).await?;
+	ctx.wake_workers();
+
+	Ok(())
+}
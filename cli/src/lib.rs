@@ -28,6 +28,27 @@ pub use thread::*;
 mod cloak;
 pub use cloak::*;
 
+mod keys;
+pub use keys::*;
+
+mod allowlist;
+pub use allowlist::*;
+
+mod blocks;
+pub use blocks::*;
+
+mod password;
+pub use password::*;
+
+mod invites;
+pub use invites::*;
+
+mod tokens;
+pub use tokens::*;
+
+mod user;
+pub use user::*;
+
 #[derive(Debug, Clone, clap::Subcommand)]
 pub enum CliCommand {
 	/// generate fake user, note and activity
@@ -83,15 +104,11 @@ pub enum CliCommand {
 		limit: Option<u64>,
 	},
 
-	/// register a new local user
+	/// register a new local user, password is asked interactively unless given on stdin
 	Register {
 		/// username for new user, must be unique locally and cannot be changed
 		username: String,
 
-		/// password for new user
-		// TODO get this with getpass rather than argv!!!!
-		password: String,
-
 		/// display name for new user
 		#[arg(long = "name")]
 		display_name: Option<String>,
@@ -107,6 +124,10 @@ pub enum CliCommand {
 		/// url for banner image of new user
 		#[arg(long = "banner")]
 		banner_url: Option<String>,
+
+		/// read password from first line of stdin rather than asking for it
+		#[arg(long, default_value_t = false)]
+		password_stdin: bool,
 	},
 
 	/// break all user relations so that instance can be shut down
@@ -144,6 +165,52 @@ pub enum CliCommand {
 		re_cloak: bool,
 	},
 
+	/// manage instances to federate with when running in allowlist mode
+	Allowlist {
+		#[clap(subcommand)]
+		/// action to take on allowlist
+		action: AllowlistCommand,
+	},
+
+	/// manage moderation blocks against whole instances
+	Blocks {
+		#[clap(subcommand)]
+		/// action to take on blocks
+		action: BlocksCommand,
+	},
+
+	/// manage local users and their registrations
+	User {
+		#[clap(subcommand)]
+		/// action to take on users
+		action: UserCommand,
+	},
+
+	/// manage invite codes, which allow registering even when registrations are closed
+	Invites {
+		#[clap(subcommand)]
+		/// action to take on invites
+		action: InvitesCommand,
+	},
+
+	/// manage api tokens, used by bots and scripts instead of logging in
+	Tokens {
+		#[clap(subcommand)]
+		/// action to take on api tokens
+		action: TokensCommand,
+	},
+
+	/// generate new rsa and ed25519 keys for a local actor and broadcast them with an Update, also
+	/// useful to give ed25519 keys to actors created before they were supported
+	RotateKey {
+		/// username or full id of local actor
+		actor: String,
+
+		/// keep accepting signatures with previous keys for this many hours
+		#[arg(long, default_value_t = 24)]
+		grace_hours: i64,
+	},
+
 	/// restore activities links, only needed for very old installs
 	FixActivities {
 		/// restore like activity links
@@ -169,8 +236,8 @@ pub async fn run(ctx: upub::Context, command: CliCommand) -> Result<(), Box<dyn
 			Ok(count(ctx, likes, shares, replies).await?),
 		CliCommand::Update { days, limit } =>
 			Ok(update_users(ctx, days, limit).await?),
-		CliCommand::Register { username, password, display_name, summary, avatar_url, banner_url } =>
-			Ok(register(ctx, username, password, display_name, summary, avatar_url, banner_url).await?),
+		CliCommand::Register { username, display_name, summary, avatar_url, banner_url, password_stdin } =>
+			register(ctx, username, display_name, summary, avatar_url, banner_url, password_stdin).await,
 		CliCommand::Nuke { for_real, delete_objects } =>
 			Ok(nuke(ctx, for_real, delete_objects).await?),
 		CliCommand::Thread { } =>
@@ -179,5 +246,17 @@ pub async fn run(ctx: upub::Context, command: CliCommand) -> Result<(), Box<dyn
 			Ok(cloak(ctx, contents, objects, actors, re_cloak).await?),
 		CliCommand::FixActivities { likes, announces } =>
 			Ok(fix_activities(ctx, likes, announces).await?),
+		CliCommand::Allowlist { action } =>
+			Ok(allowlist(ctx, action).await?),
+		CliCommand::Blocks { action } =>
+			blocks(ctx, action).await,
+		CliCommand::User { action } =>
+			user(ctx, action).await,
+		CliCommand::Invites { action } =>
+			Ok(invites(ctx, action).await?),
+		CliCommand::Tokens { action } =>
+			tokens(ctx, action).await,
+		CliCommand::RotateKey { actor, grace_hours } =>
+			rotate_key(ctx, actor, grace_hours).await,
 	}
 }
@@ -82,6 +82,8 @@ pub async fn nuke(ctx: upub::Context, for_real: bool, delete_posts: bool) -> Res
 			attempt: Set(0),
 			payload: Set(Some(undo_activity)),
 			error: Set(None),
+			locked_until: Set(None),
+			domain: Set(None),
 		};
 
 		tracing::info!("undoing {}", activity.id);
@@ -123,6 +125,8 @@ pub async fn nuke(ctx: upub::Context, for_real: bool, delete_posts: bool) -> Res
 				attempt: Set(0),
 				payload: Set(Some(undo_activity)),
 				error: Set(None),
+				locked_until: Set(None),
+				domain: Set(None),
 			};
 
 			tracing::info!("deleting {}", object.id);
@@ -0,0 +1,79 @@
+use sea_orm::{sea_query::Expr, ColumnTrait, EntityTrait, QueryFilter, TransactionTrait};
+
+/// replace password of given user (or of all users still on legacy hashes), logging out all their
+/// sessions. new password is asked interactively, read from stdin or, if temporary, generated at
+/// random and printed on stdout
+pub async fn reset_password(
+	ctx: upub::Context,
+	username: Option<String>,
+	legacy: bool,
+	temporary: bool,
+	password_stdin: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+	let mut credentials = upub::model::credential::Entity::find();
+	if let Some(ref username) = username {
+		credentials = credentials.filter(upub::model::credential::Column::Actor.eq(ctx.uid(username)));
+	} else if !legacy {
+		tracing::error!("specify either an username or --legacy");
+		return Ok(());
+	}
+
+	// nobody is around to pick a new password for accounts still on legacy hashes
+	let chosen = if temporary || legacy { None } else { Some(read_new_password(password_stdin)?) };
+
+	let tx = ctx.db().begin().await?;
+	let mut count = 0;
+
+	for credential in credentials.all(&tx).await? {
+		if legacy && !upub::passwords::is_legacy(&credential.password) { continue }
+
+		let password = chosen.clone().unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+		let hash = upub::passwords::hash_async(password.clone()).await;
+
+		upub::model::credential::Entity::update_many()
+			.col_expr(upub::model::credential::Column::Password, Expr::value(hash))
+			.filter(upub::model::credential::Column::Internal.eq(credential.internal))
+			.exec(&tx)
+			.await?;
+
+		upub::model::session::Entity::delete_many()
+			.filter(upub::model::session::Column::Actor.eq(&credential.actor))
+			.exec(&tx)
+			.await?;
+
+		tracing::info!("reset password of {} ({}), all sessions have been revoked", credential.actor, credential.login);
+		if chosen.is_none() {
+			// not through tracing: logs get collected and kept around, temporary passwords shouldn't be
+			println!("{}\t{password}", credential.login);
+		}
+		count += 1;
+	}
+
+	tx.commit().await?;
+
+	if count == 0 {
+		tracing::warn!("no matching local users, nothing was reset");
+	}
+
+	Ok(())
+}
+
+/// reads new password from first line of stdin, for scripts, or asks for it on the terminal, twice,
+/// so that it never ends up in shell history
+pub fn read_new_password(stdin: bool) -> Result<String, Box<dyn std::error::Error>> {
+	let password = if stdin {
+		let mut line = String::new();
+		std::io::stdin().read_line(&mut line)?;
+		line.trim_end_matches(['\r', '\n']).to_string()
+	} else {
+		let password = rpassword::prompt_password("new password: ")?;
+		if !password.is_empty() && rpassword::prompt_password("repeat password: ")? != password {
+			return Err("passwords don't match".into());
+		}
+		password
+	};
+	if password.is_empty() {
+		return Err("password can't be empty".into());
+	}
+	Ok(password)
+}
@@ -1,14 +1,18 @@
+use sea_orm::TransactionTrait;
 use upub::traits::Administrable;
 
 pub async fn register(
 	ctx: upub::Context,
 	username: String,
-	password: String,
 	display_name: Option<String>,
 	summary: Option<String>,
 	avatar_url: Option<String>,
 	banner_url: Option<String>,
-) -> Result<(), sea_orm::DbErr> {
+	password_stdin: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+	let password = crate::read_new_password(password_stdin)?;
+
+	let tx = ctx.db().begin().await?;
 	ctx.register_user(
 		username.clone(),
 		password,
@@ -16,7 +20,9 @@ pub async fn register(
 		summary,
 		avatar_url,
 		banner_url,
+		&tx,
 	).await?;
+	tx.commit().await?;
 
 	tracing::info!("registered new user: {username}");
 	
@@ -85,6 +85,8 @@ pub async fn relay(ctx: upub::Context, action: RelayCommand) -> Result<(), Reque
 				published: Set(chrono::Utc::now()),
 				not_before: Set(chrono::Utc::now()),
 				error: Set(None),
+				locked_until: Set(None),
+				domain: Set(None),
 			};
 			tracing::info!("following relay {actor}");
 			upub::model::job::Entity::insert(job).exec(ctx.db()).await?;
@@ -121,6 +123,8 @@ pub async fn relay(ctx: upub::Context, action: RelayCommand) -> Result<(), Reque
 				published: Set(chrono::Utc::now()),
 				not_before: Set(chrono::Utc::now()),
 				error: Set(None),
+				locked_until: Set(None),
+				domain: Set(None),
 			};
 			tracing::info!("accepting relay {actor}");
 			upub::model::job::Entity::insert(job).exec(ctx.db()).await?;
@@ -158,6 +162,8 @@ pub async fn relay(ctx: upub::Context, action: RelayCommand) -> Result<(), Reque
 				published: Set(chrono::Utc::now()),
 				not_before: Set(chrono::Utc::now()),
 				error: Set(None),
+				locked_until: Set(None),
+				domain: Set(None),
 			};
 			tracing::info!("unfollowing relay {actor}");
 			upub::model::job::Entity::insert(job).exec(ctx.db()).await?;
@@ -194,6 +200,8 @@ pub async fn relay(ctx: upub::Context, action: RelayCommand) -> Result<(), Reque
 				published: Set(chrono::Utc::now()),
 				not_before: Set(chrono::Utc::now()),
 				error: Set(None),
+				locked_until: Set(None),
+				domain: Set(None),
 			};
 			tracing::info!("unfollowing relay {actor}");
 			upub::model::job::Entity::insert(job).exec(ctx.db()).await?;
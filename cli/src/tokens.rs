@@ -0,0 +1,78 @@
+#[derive(Debug, Clone, clap::Subcommand)]
+/// available actions on api tokens of local users
+pub enum TokensCommand {
+	/// show api tokens of an user
+	List {
+		/// username of token owner
+		username: String,
+	},
+	/// create a new api token, printing its secret on stdout
+	Create {
+		/// username of token owner
+		username: String,
+
+		/// name to recognize this token later, such as the bot using it
+		name: String,
+
+		/// space separated scopes granted to this token (read, write, follow, admin)
+		#[arg(long, default_value = "read")]
+		scopes: String,
+
+		/// token stops working after these many days, never expires if not given
+		#[arg(long)]
+		expires_days: Option<i64>,
+	},
+	/// delete an api token, so that it can't be used anymore
+	Revoke {
+		/// username of token owner
+		username: String,
+
+		/// token id, as shown by list
+		id: i64,
+	},
+}
+
+pub async fn tokens(ctx: upub::Context, action: TokensCommand) -> Result<(), Box<dyn std::error::Error>> {
+	match action {
+		TokensCommand::List { username } => {
+			for token in upub::tokens::list(ctx.db(), &ctx.uid(&username)).await? {
+				tracing::info!(
+					"[{}] {} ({}) last seen {} expires {}",
+					token.internal,
+					token.name.unwrap_or_default(),
+					token.scopes.unwrap_or_default(),
+					token.last_seen.map(|x| x.to_rfc3339()).unwrap_or("never".to_string()),
+					token.expires.to_rfc3339(),
+				);
+			}
+		},
+		TokensCommand::Create { username, name, scopes, expires_days } => {
+			let scopes = scopes.parse::<upub::oauth::Scopes>()?;
+			if scopes.is_empty() {
+				return Err("at least one scope is required".into());
+			}
+			let expires = match expires_days {
+				None => None,
+				Some(d) => Some(upub::tokens::expiry(d)
+					.ok_or_else(|| format!("expiration must be between 1 and {} days", upub::tokens::NEVER_EXPIRES_DAYS))?),
+			};
+			match upub::tokens::create(ctx.db(), &ctx.uid(&username), name, scopes, expires).await? {
+				Some(token) => {
+					tracing::info!("created api token [{}] for {username}", token.internal);
+					// not through tracing: logs get collected and kept around, secrets shouldn't be
+					println!("{}", token.secret);
+				},
+				None => tracing::error!("no local user {username}"),
+			}
+		},
+		TokensCommand::Revoke { username, id } => {
+			if upub::tokens::revoke(ctx.db(), &ctx.uid(&username), id).await? {
+				tracing::info!("revoked api token {id} of {username}");
+			} else {
+				tracing::warn!("{username} has no api token {id}");
+			}
+		},
+	}
+
+	Ok(())
+}
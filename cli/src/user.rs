@@ -0,0 +1,72 @@
+#[derive(Debug, Clone, clap::Subcommand)]
+/// available actions on local users
+pub enum UserCommand {
+	/// show registrations waiting for approval, with the reason given at signup
+	Pending,
+	/// activate a pending registration, so that user can login
+	Approve {
+		/// username of pending user
+		username: String,
+	},
+	/// refuse a pending registration, deleting the user
+	Reject {
+		/// username of pending user
+		username: String,
+	},
+	/// set a new password and log out everywhere, password is asked interactively unless given on
+	/// stdin or temporary
+	ResetPassword {
+		/// username of user to reset
+		#[arg(required_unless_present = "legacy")]
+		username: Option<String>,
+
+		/// replace password with a random temporary one, printed on stdout
+		#[arg(long, default_value_t = false)]
+		temporary: bool,
+
+		/// read new password from first line of stdin rather than asking for it
+		#[arg(long, default_value_t = false, conflicts_with = "temporary")]
+		password_stdin: bool,
+
+		/// reset all users whose password is still stored with old unsalted sha256 hashes, which
+		/// get upgraded on login: useful for accounts nobody logs into anymore. implies --temporary
+		#[arg(long, default_value_t = false, conflicts_with = "password_stdin")]
+		legacy: bool,
+	},
+}
+
+pub async fn user(ctx: upub::Context, action: UserCommand) -> Result<(), Box<dyn std::error::Error>> {
+	match action {
+		UserCommand::Pending => {
+			if !ctx.cfg().security.require_user_approval {
+				tracing::warn!("registrations don't require approval in config, new users are activated immediately");
+			}
+			for pending in upub::registrations::pending(ctx.db()).await? {
+				tracing::info!(
+					"{} (registered {}): {}",
+					pending.username,
+					pending.registered.to_rfc3339(),
+					pending.reason.as_deref().unwrap_or("no reason given"),
+				);
+			}
+		},
+		UserCommand::Approve { username } => {
+			if upub::registrations::approve(ctx.db(), &ctx.uid(&username)).await? {
+				tracing::info!("approved registration of {username}");
+			} else {
+				tracing::warn!("no pending registration for {username}");
+			}
+		},
+		UserCommand::Reject { username } => {
+			if upub::registrations::reject(ctx.db(), &ctx.uid(&username)).await? {
+				tracing::info!("rejected registration of {username}");
+			} else {
+				tracing::warn!("no pending registration for {username}");
+			}
+		},
+		UserCommand::ResetPassword { username, temporary, password_stdin, legacy } =>
+			crate::reset_password(ctx, username, legacy, temporary, password_stdin).await?,
+	}
+
+	Ok(())
+}
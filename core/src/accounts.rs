@@ -0,0 +1,102 @@
+use sea_orm::{sea_query::Expr, ActiveValue::{NotSet, Set}, ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QuerySelect, SelectColumns};
+
+use crate::{ext::JsonVec, model, traits::Addresser};
+
+/// deletes a local account: a Delete for the actor is delivered to everyone who may know about
+/// it, then credentials, sessions, config and relations are dropped and the profile is wiped.
+/// actor row is kept as tombstone (with its keys, so that deliveries can still be signed) and
+/// its username can't be registered again. returns the id of the Delete activity, or None if
+/// there's no such local actor
+pub async fn delete(ctx: &crate::Context, uid: &str, tx: &impl ConnectionTrait) -> Result<Option<String>, DbErr> {
+	let Some(actor) = model::actor::Entity::find_by_ap_id(uid)
+		.filter(model::actor::Column::Deleted.is_null())
+		.one(tx)
+		.await?
+	else {
+		return Ok(None);
+	};
+
+	if !ctx.is_local(&actor.id) {
+		return Ok(None);
+	}
+
+	let aid = ctx.aid(&crate::Context::new_id());
+	let mut to = vec![apb::target::PUBLIC.to_string()];
+	if let Some(ref followers) = actor.followers {
+		to.push(followers.clone());
+	}
+
+	let activity = model::activity::Entity::insert(model::activity::ActiveModel {
+		internal: NotSet,
+		id: Set(aid.clone()),
+		activity_type: Set(apb::ActivityType::Delete),
+		actor: Set(actor.id.clone()),
+		object: Set(Some(actor.id.clone())),
+		target: Set(None),
+		content: Set(None),
+		to: Set(JsonVec(to.clone())),
+		bto: Set(JsonVec::default()),
+		cc: Set(JsonVec::default()),
+		bcc: Set(JsonVec::default()),
+		published: Set(chrono::Utc::now()),
+	})
+		.exec_with_returning(tx)
+		.await?;
+
+	ctx.address(Some(&activity), None, tx).await?;
+
+	// followers are reached expanding the followers collection, but also whoever we follow knows
+	// about us and should drop our stuff
+	let mut following = crate::Query::related(Some(actor.internal), None, false)
+		.select_only()
+		.select_column(model::actor::Column::Id)
+		.into_tuple::<String>()
+		.all(tx)
+		.await?;
+	to.append(&mut following);
+
+	ctx.deliver(to, &aid, &actor.id, tx).await?;
+
+	model::credential::Entity::delete_many()
+		.filter(model::credential::Column::Actor.eq(&actor.id))
+		.exec(tx)
+		.await?;
+
+	model::session::Entity::delete_many()
+		.filter(model::session::Column::Actor.eq(&actor.id))
+		.exec(tx)
+		.await?;
+
+	model::config::Entity::delete_many()
+		.filter(model::config::Column::Actor.eq(&actor.id))
+		.exec(tx)
+		.await?;
+
+	model::relation::Entity::delete_many()
+		.filter(
+			Condition::any()
+				.add(model::relation::Column::Follower.eq(actor.internal))
+				.add(model::relation::Column::Following.eq(actor.internal))
+		)
+		.exec(tx)
+		.await?;
+
+	let now = chrono::Utc::now();
+	model::actor::Entity::update_many()
+		.col_expr(model::actor::Column::Name, Expr::value(Option::<String>::None))
+		.col_expr(model::actor::Column::Summary, Expr::value(Option::<String>::None))
+		.col_expr(model::actor::Column::Image, Expr::value(Option::<String>::None))
+		.col_expr(model::actor::Column::Icon, Expr::value(Option::<String>::None))
+		.col_expr(model::actor::Column::Fields, Expr::value(JsonVec::<model::actor::Field>::default()))
+		.col_expr(model::actor::Column::FollowersCount, Expr::value(0))
+		.col_expr(model::actor::Column::FollowingCount, Expr::value(0))
+		.col_expr(model::actor::Column::Updated, Expr::value(now))
+		.col_expr(model::actor::Column::Deleted, Expr::value(Some(now)))
+		.filter(model::actor::Column::Internal.eq(actor.internal))
+		.exec(tx)
+		.await?;
+
+	tracing::info!("deleted account {}, delivering {aid}", actor.id);
+
+	Ok(Some(aid))
+}
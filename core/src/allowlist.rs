@@ -0,0 +1,72 @@
+use std::{collections::BTreeSet, sync::{LazyLock, RwLock}};
+
+use sea_orm::{sea_query::OnConflict, ActiveValue::{NotSet, Set}, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QuerySelect, SelectColumns};
+
+use crate::{ext::matches_domain, model, Context};
+
+/// how long runtime additions may take to be noticed by other processes
+const REFRESH: std::time::Duration = std::time::Duration::from_secs(60);
+
+type Cached = Option<(std::time::Instant, BTreeSet<String>)>;
+
+static CACHE: LazyLock<RwLock<Cached>> = LazyLock::new(Default::default);
+
+/// whether we may federate with given domain: always true unless running in allowlist mode
+pub async fn is_allowed(ctx: &Context, domain: &str) -> Result<bool, DbErr> {
+	let cfg = &ctx.cfg().federation;
+	if !cfg.allowlist_only || domain == ctx.domain() || matches_domain(domain, &cfg.allowlist) {
+		return Ok(true);
+	}
+
+	if let Some((ref when, ref domains)) = *CACHE.read().expect("allowlist cache lock poisoned") {
+		if when.elapsed() < REFRESH {
+			return Ok(matches_domain(domain, domains));
+		}
+	}
+
+	let domains = list(ctx.db()).await?;
+	let allowed = matches_domain(domain, &domains);
+	*CACHE.write().expect("allowlist cache lock poisoned") = Some((std::time::Instant::now(), domains));
+	Ok(allowed)
+}
+
+/// domains allowed at runtime, not including configured ones
+pub async fn list(db: &impl ConnectionTrait) -> Result<BTreeSet<String>, DbErr> {
+	Ok(
+		model::allowed_domain::Entity::find()
+			.select_only()
+			.select_column(model::allowed_domain::Column::Domain)
+			.into_tuple::<String>()
+			.all(db)
+			.await?
+			.into_iter()
+			.collect()
+	)
+}
+
+pub async fn allow(db: &impl ConnectionTrait, domain: &str) -> Result<(), DbErr> {
+	model::allowed_domain::Entity::insert(model::allowed_domain::ActiveModel {
+		internal: NotSet,
+		domain: Set(domain.to_lowercase()),
+		published: Set(chrono::Utc::now()),
+	})
+		.on_conflict(OnConflict::column(model::allowed_domain::Column::Domain).do_nothing().to_owned())
+		.exec_without_returning(db)
+		.await?;
+	invalidate();
+	Ok(())
+}
+
+/// returns false if given domain wasn't allowed at runtime
+pub async fn disallow(db: &impl ConnectionTrait, domain: &str) -> Result<bool, DbErr> {
+	let res = model::allowed_domain::Entity::delete_many()
+		.filter(model::allowed_domain::Column::Domain.eq(domain.to_lowercase()))
+		.exec(db)
+		.await?;
+	invalidate();
+	Ok(res.rows_affected > 0)
+}
+
+fn invalidate() {
+	*CACHE.write().expect("allowlist cache lock poisoned") = None;
+}
@@ -0,0 +1,135 @@
+use std::{collections::HashMap, sync::{LazyLock, RwLock}};
+
+use sea_orm::{sea_query::OnConflict, ActiveValue::{NotSet, Set}, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QuerySelect, SelectColumns};
+
+use crate::model::{self, domain_block::Severity};
+
+/// how long changes made by other processes may take to be noticed
+const REFRESH: std::time::Duration = std::time::Duration::from_secs(60);
+
+type Cached = Option<(std::time::Instant, HashMap<String, Severity>)>;
+
+// blocked domain -> severity. lookups walk up the requested domain labels, so each check costs a
+// handful of hash lookups no matter how many instances are blocked
+static CACHE: LazyLock<RwLock<Cached>> = LazyLock::new(Default::default);
+
+/// lowercase, without wildcard prefix or trailing dot: blocks always cover subdomains
+pub fn normalize(domain: &str) -> String {
+	let domain = domain.trim().trim_end_matches('.').to_lowercase();
+	domain.strip_prefix("*.").map(|x| x.to_string()).unwrap_or(domain)
+}
+
+// a weaker block on a subdomain must not hide a stronger one on its parent, so check every label
+fn lookup(blocks: &HashMap<String, Severity>, domain: &str) -> Option<Severity> {
+	let mut current = Some(domain);
+	let mut found = None;
+	while let Some(label) = current {
+		found = found.max(blocks.get(label).copied());
+		current = label.split_once('.').map(|(_, parent)| parent);
+	}
+	found
+}
+
+/// strongest block applying to given domain (or any of its parents), if any
+pub async fn severity(db: &impl ConnectionTrait, domain: &str) -> Result<Option<Severity>, DbErr> {
+	let domain = normalize(domain);
+
+	if let Some((ref when, ref blocks)) = *CACHE.read().expect("domain blocks cache lock poisoned") {
+		if when.elapsed() < REFRESH {
+			return Ok(lookup(blocks, &domain));
+		}
+	}
+
+	let blocks : HashMap<String, Severity> = model::domain_block::Entity::find()
+		.select_only()
+		.select_column(model::domain_block::Column::Domain)
+		.select_column(model::domain_block::Column::Severity)
+		.into_tuple::<(String, Severity)>()
+		.all(db)
+		.await?
+		.into_iter()
+		.collect();
+	let found = lookup(&blocks, &domain);
+	*CACHE.write().expect("domain blocks cache lock poisoned") = Some((std::time::Instant::now(), blocks));
+	Ok(found)
+}
+
+/// whether given domain is blocked with at least given severity
+pub async fn is_blocked(db: &impl ConnectionTrait, domain: &str, severity: Severity) -> Result<bool, DbErr> {
+	Ok(self::severity(db, domain).await?.is_some_and(|x| x >= severity))
+}
+
+/// create a block, or replace an existing one for same domain
+pub async fn block(
+	db: &impl ConnectionTrait,
+	domain: &str,
+	severity: Severity,
+	public_reason: Option<String>,
+	private_reason: Option<String>,
+) -> Result<(), DbErr> {
+	model::domain_block::Entity::insert(model::domain_block::ActiveModel {
+		internal: NotSet,
+		domain: Set(normalize(domain)),
+		severity: Set(severity),
+		public_reason: Set(public_reason),
+		private_reason: Set(private_reason),
+		published: Set(chrono::Utc::now()),
+	})
+		.on_conflict(
+			OnConflict::column(model::domain_block::Column::Domain)
+				.update_columns([
+					model::domain_block::Column::Severity,
+					model::domain_block::Column::PublicReason,
+					model::domain_block::Column::PrivateReason,
+				])
+				.to_owned()
+		)
+		.exec_without_returning(db)
+		.await?;
+	invalidate();
+	Ok(())
+}
+
+/// returns false if given domain wasn't blocked
+pub async fn unblock(db: &impl ConnectionTrait, domain: &str) -> Result<bool, DbErr> {
+	let res = model::domain_block::Entity::delete_many()
+		.filter(model::domain_block::Column::Domain.eq(normalize(domain)))
+		.exec(db)
+		.await?;
+	invalidate();
+	Ok(res.rows_affected > 0)
+}
+
+fn invalidate() {
+	*CACHE.write().expect("domain blocks cache lock poisoned") = None;
+}
+
+#[cfg(test)]
+mod test {
+	use std::collections::HashMap;
+
+	use super::{lookup, Severity};
+
+	#[test]
+	fn parent_suspend_wins_over_child_reject_media() {
+		let blocks = HashMap::from([
+			("evil.com".to_string(), Severity::Suspend),
+			("media.evil.com".to_string(), Severity::RejectMedia),
+		]);
+		assert_eq!(lookup(&blocks, "media.evil.com"), Some(Severity::Suspend));
+		assert_eq!(lookup(&blocks, "cdn.media.evil.com"), Some(Severity::Suspend));
+		assert_eq!(lookup(&blocks, "evil.com"), Some(Severity::Suspend));
+	}
+
+	#[test]
+	fn child_block_stronger_than_parent() {
+		let blocks = HashMap::from([
+			("example.com".to_string(), Severity::RejectMedia),
+			("spam.example.com".to_string(), Severity::Suspend),
+		]);
+		assert_eq!(lookup(&blocks, "spam.example.com"), Some(Severity::Suspend));
+		assert_eq!(lookup(&blocks, "www.example.com"), Some(Severity::RejectMedia));
+		assert_eq!(lookup(&blocks, "notexample.com"), None);
+		assert_eq!(lookup(&blocks, "example.org"), None);
+	}
+}
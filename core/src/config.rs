@@ -19,6 +19,18 @@ pub struct Config {
 	#[serde(default)]
 	pub reject: RejectConfig,
 
+	#[serde(default)]
+	pub policy: PolicyConfig,
+
+	#[serde(default)]
+	pub federation: FederationConfig,
+
+	#[serde(default)]
+	pub worker: WorkerConfig,
+
+	#[serde(default)]
+	pub metrics: MetricsConfig,
+
 	// TODO should i move app keys here?
 }
 
@@ -84,6 +96,14 @@ pub struct SecurityConfig {
 	/// newly registered users require manual activation
 	pub require_user_approval: bool,
 
+	#[serde(default)]
+	/// let any local user create invite codes, not just admins
+	pub allow_user_invites: bool,
+
+	#[serde(default)]
+	/// local usernames allowed to use administrative endpoints
+	pub admins: Vec<String>,
+
 	#[serde(default)]
 	/// allow anonymous users access to fetch debugger (explore screen)
 	pub allow_public_debugger: bool,
@@ -100,6 +120,18 @@ pub struct SecurityConfig {
 	/// secret for media proxy, set this to something random
 	pub proxy_secret: String,
 
+	#[serde_inline_default(1024 * 1024)]
+	/// max size, in bytes, of activities POSTed to inboxes (bodies get buffered to check digests)
+	pub max_inbox_body_bytes: usize,
+
+	#[serde_inline_default(300)]
+	/// max difference, in seconds, between our clock and signed request dates
+	pub signature_clock_skew_seconds: i64,
+
+	#[serde_inline_default(100_000)]
+	/// how many recently seen signatures to remember, to refuse replayed requests
+	pub signature_replay_cache_size: usize,
+
 	#[serde_inline_default(true)]
 	/// allow expired tokens to be refreshed
 	pub allow_login_refresh: bool,
@@ -108,6 +140,10 @@ pub struct SecurityConfig {
 	/// how long do login sessions last
 	pub session_duration_hours: i64,
 
+	#[serde_inline_default(30)]
+	/// how long after expiring can oauth sessions still be refreshed
+	pub refresh_duration_days: i64,
+
 	#[serde_inline_default(2)]
 	/// how many times we allow an object to redirect
 	pub max_id_redirects: u32, // TODO not sure it fits here
@@ -190,6 +226,168 @@ pub struct RejectConfig {
 	pub requests: Vec<String>,
 }
 
+#[serde_inline_default::serde_inline_default]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, serde_default::DefaultFromSerde)]
+pub struct FederationConfig {
+	#[serde(default)]
+	/// only federate with allowed instances: refuse their requests, don't fetch from or deliver to them
+	pub allowlist_only: bool,
+
+	#[serde(default)]
+	/// instances (and their subdomains) to federate with in allowlist mode, more can be added at
+	/// runtime with `upub cli allowlist add` or admin api
+	pub allowlist: Vec<String>,
+
+	#[serde(default)]
+	/// list blocked instances, with their public reason, on /blocklist
+	pub public_blocklist: bool,
+}
+
+#[serde_inline_default::serde_inline_default]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, serde_default::DefaultFromSerde)]
+pub struct PolicyConfig {
+	#[serde(default)]
+	/// discard incoming activities containing any of these words, case insensitive
+	pub reject_keywords: Vec<String>,
+
+	#[serde(default)]
+	/// mark media coming from these instances (and their subdomains) as sensitive
+	pub force_sensitive: Vec<String>,
+
+	#[serde(default)]
+	/// drop media attachments coming from or going to these instances (and their subdomains)
+	pub strip_media: Vec<String>,
+
+	#[serde(default)]
+	/// discard incoming activities from actors created less than these hours ago, 0 to disable
+	pub min_actor_age_hours: i64,
+
+	#[serde(default)]
+	/// discard incoming posts mentioning more than these many actors, 0 to disable
+	pub max_mentions: usize,
+
+	#[serde(default)]
+	/// don't notify mentions from new remote actors nobody here follows, hold them until approved
+	pub quarantine_first_contact: bool,
+
+	#[serde_inline_default(7)]
+	/// remote actors created less than these days ago are considered new by quarantine
+	pub quarantine_actor_age_days: i64,
+
+	#[serde_inline_default(5)]
+	/// remote actors with less followers than these are considered new by quarantine
+	pub quarantine_min_followers: i32,
+}
+
+#[serde_inline_default::serde_inline_default]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, serde_default::DefaultFromSerde)]
+pub struct MetricsConfig {
+	#[serde(default)]
+	/// expose prometheus metrics on /metrics
+	pub enabled: bool,
+
+	#[serde_inline_default("127.0.0.1:9100".to_string())]
+	/// where to serve /metrics, make sure to not make it reachable publicly! leave empty to serve it
+	/// alongside api routes instead, and restrict access to it in your reverse proxy
+	pub bind: String,
+}
+
+#[serde_inline_default::serde_inline_default]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, serde_default::DefaultFromSerde)]
+pub struct WorkerConfig {
+	#[serde_inline_default(300)]
+	/// how long, in seconds, a claimed job stays locked before other workers may pick it up again
+	/// (only on postgres, where jobs are leased instead of deleted while processing)
+	pub job_lease_seconds: u64,
+
+	#[serde_inline_default(60)]
+	/// how often, in seconds, workers extend the lease of jobs they're still processing
+	pub job_heartbeat_seconds: u64,
+
+	#[serde_inline_default(2)]
+	/// how many deliveries towards the same instance each worker may run at once, so that a big
+	/// backlog for one slow instance doesn't take over all worker tasks
+	pub max_concurrent_per_domain: usize,
+
+	#[serde_inline_default(300)]
+	/// when a remote answers 429 without telling us how long to wait, retry after these seconds
+	pub default_retry_after_seconds: u64,
+
+	#[serde_inline_default(10)]
+	/// seconds between each follow enqueued when users import their following list from elsewhere
+	pub import_interval_seconds: u64,
+
+	#[serde(default)]
+	/// per-instance overrides for outbound delivery limits, keyed by domain
+	pub rate_limits: std::collections::BTreeMap<String, DomainRateLimit>,
+
+	#[serde(default)]
+	/// recurring maintenance tasks, each is ran by only one worker at a time
+	pub maintenance: MaintenanceConfig,
+}
+
+#[serde_inline_default::serde_inline_default]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, serde_default::DefaultFromSerde)]
+pub struct DomainRateLimit {
+	#[serde(default)]
+	/// concurrent deliveries towards this instance, overrides `max_concurrent_per_domain`
+	pub max_concurrent: Option<usize>,
+
+	#[serde(default)]
+	/// minimum time, in milliseconds, between two deliveries towards this instance
+	pub min_interval_ms: u64,
+}
+
+#[serde_inline_default::serde_inline_default]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, serde_default::DefaultFromSerde)]
+pub struct MaintenanceConfig {
+	#[serde_inline_default(60)]
+	/// how often, in seconds, workers check if some maintenance task is due
+	pub check_interval_seconds: u64,
+
+	#[serde(default)]
+	/// refresh stale remote actors every these hours (like `cli update`), 0 to disable
+	pub update_actors_hours: u64,
+
+	#[serde_inline_default(10)]
+	/// remote actors not updated since these many days are considered stale
+	pub update_actors_stale_days: i64,
+
+	#[serde_inline_default(100)]
+	/// max remote actors to refresh on each run
+	pub update_actors_limit: u64,
+
+	#[serde(default)]
+	/// recount likes, shares and replies every these hours (like `cli count`), 0 to disable
+	pub count_hours: u64,
+
+	#[serde(default)]
+	/// fix broken threads every these hours (like `cli thread`), 0 to disable
+	pub thread_hours: u64,
+
+	#[serde_inline_default(24)]
+	/// remove expired login sessions every these hours, 0 to disable
+	pub expired_sessions_hours: u64,
+}
+
+impl WorkerConfig {
+	pub fn max_concurrent(&self, domain: &str) -> usize {
+		self.rate_limits
+			.get(domain)
+			.and_then(|x| x.max_concurrent)
+			.unwrap_or(self.max_concurrent_per_domain)
+	}
+
+	pub fn min_interval(&self, domain: &str) -> std::time::Duration {
+		std::time::Duration::from_millis(
+			self.rate_limits
+				.get(domain)
+				.map(|x| x.min_interval_ms)
+				.unwrap_or_default()
+		)
+	}
+}
+
 impl Config {
 	pub fn load(path: Option<&std::path::PathBuf>) -> Self {
 		let Some(cfg_path) = path else { return Config::default() };
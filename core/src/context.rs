@@ -17,6 +17,8 @@ struct ContextInner {
 	actor: model::actor::Model,
 	instance: model::instance::Model,
 	pkey: String,
+	key_id: String,
+	policies: crate::policy::Pipeline,
 	waker: Option<Box<dyn WakerToken>>,
 	#[allow(unused)] relay: Relays,
 }
@@ -53,9 +55,12 @@ impl Context {
 		let base_url = format!("{}{}", protocol, domain);
 
 		let (actor, instance) = super::init::application(domain.clone(), base_url.clone(), &db).await?;
+		super::init::multikeys(&db).await?;
 
 		// TODO maybe we could provide a more descriptive error...
-		let pkey = actor.private_key.as_deref().ok_or_else(|| DbErr::RecordNotFound("application private key".into()))?.to_string();
+		let (key_id, pkey) = crate::keys::signing(&db, &actor)
+			.await?
+			.ok_or_else(|| DbErr::RecordNotFound("application private key".into()))?;
 
 		let relay_sinks = crate::Query::related(None, Some(actor.internal), false)
 			.select_only()
@@ -76,8 +81,10 @@ impl Context {
 			sinks: BTreeSet::from_iter(relay_sinks),
 		};
 
+		let policies = crate::policy::Pipeline::from_config(&config.policy);
+
 		Ok(Context(Arc::new(ContextInner {
-			base_url, db, domain, protocol, actor, instance, config, pkey, relay, waker,
+			base_url, db, domain, protocol, actor, instance, config, pkey, key_id, policies, relay, waker,
 		})))
 	}
 
@@ -94,6 +101,16 @@ impl Context {
 		&self.0.pkey
 	}
 
+	/// id of key matching [Self::pkey], to sign requests as application actor
+	pub fn key_id(&self) -> &str {
+		&self.0.key_id
+	}
+
+	/// federation policies every inbound and outbound activity must go through
+	pub fn policies(&self) -> &crate::policy::Pipeline {
+		&self.0.policies
+	}
+
 	pub fn db(&self) -> &DatabaseConnection {
 		&self.0.db
 	}
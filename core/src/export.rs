@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use apb::CollectionMut;
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, SelectColumns};
+
+use crate::{model, selector::{RichActivity, RichObject}};
+
+/// everything a local user may take away with them, see routes for how it's packed
+pub struct Archive {
+	pub actor: serde_json::Value,
+	pub outbox: serde_json::Value,
+	pub likes: serde_json::Value,
+	/// mastodon compatible csv, can be imported elsewhere as is
+	pub following: String,
+	pub followers: String,
+	/// names of uploaded files attached to own posts, under `files.path`
+	pub files: Vec<String>,
+}
+
+pub async fn archive(ctx: &crate::Context, uid: &str, db: &impl ConnectionTrait) -> Result<Option<Archive>, DbErr> {
+	let Some(actor) = model::actor::Entity::find_by_ap_id(uid)
+		.filter(model::actor::Column::Deleted.is_null())
+		.one(db)
+		.await?
+	else {
+		return Ok(None);
+	};
+
+	let objects = model::object::Entity::find()
+		.filter(model::object::Column::AttributedTo.eq(uid))
+		.all(db)
+		.await?;
+
+	let mut attachments : HashMap<i64, Vec<model::attachment::Model>> = HashMap::new();
+	for attachment in model::attachment::Entity::find()
+		.filter(model::attachment::Column::Object.is_in(objects.iter().map(|x| x.internal)))
+		.all(db)
+		.await?
+	{
+		attachments.entry(attachment.object).or_default().push(attachment);
+	}
+
+	let files_base = crate::url!(ctx, "/file/");
+	let files = attachments
+		.values()
+		.flatten()
+		.filter_map(|x| x.url.strip_prefix(&files_base))
+		// urls are stored as given, never trust them as paths
+		.filter(|x| is_upload(x))
+		.map(|x| x.to_string())
+		.collect();
+
+	let mut objects : HashMap<String, model::object::Model> = objects
+		.into_iter()
+		.map(|x| (x.id.clone(), x))
+		.collect();
+
+	let activities : Vec<serde_json::Value> = model::activity::Entity::find()
+		.filter(model::activity::Column::Actor.eq(uid))
+		.order_by_asc(model::activity::Column::Published)
+		.all(db)
+		.await?
+		.into_iter()
+		.map(|activity| {
+			let object = match activity.activity_type {
+				apb::ActivityType::Create => activity.object.as_deref().and_then(|x| objects.remove(x)),
+				_ => None,
+			};
+			RichActivity {
+				discovered: activity.published,
+				object: RichObject {
+					attachments: object.as_ref().and_then(|x| attachments.remove(&x.internal)),
+					object,
+					liked: None,
+					hashtags: None,
+					mentions: None,
+				},
+				activity: Some(activity),
+			}
+		})
+		.map(|x| ctx.ap(x))
+		.collect();
+
+	let liked : Vec<serde_json::Value> = model::like::Entity::find()
+		.filter(model::like::Column::Actor.eq(actor.internal))
+		.order_by_asc(model::like::Column::Published)
+		.find_also_related(model::object::Entity)
+		.all(db)
+		.await?
+		.into_iter()
+		.filter_map(|(_, object)| object)
+		.map(|x| serde_json::Value::String(x.id))
+		.collect();
+
+	let following = csv(
+		crate::Query::related(Some(actor.internal), None, false)
+			.select_only()
+			.select_column(model::actor::Column::PreferredUsername)
+			.select_column(model::actor::Column::Domain)
+			.into_tuple::<(String, String)>()
+			.all(db)
+			.await?
+	);
+
+	let followers = csv(
+		crate::Query::related(None, Some(actor.internal), false)
+			.select_only()
+			.select_column(model::actor::Column::PreferredUsername)
+			.select_column(model::actor::Column::Domain)
+			.into_tuple::<(String, String)>()
+			.all(db)
+			.await?
+	);
+
+	Ok(Some(Archive {
+		outbox: apb::new()
+			.set_collection_type(Some(apb::CollectionType::OrderedCollection))
+			.set_total_items(Some(activities.len() as u64))
+			.set_ordered_items(apb::Node::array(activities)),
+		likes: apb::new()
+			.set_collection_type(Some(apb::CollectionType::OrderedCollection))
+			.set_total_items(Some(liked.len() as u64))
+			.set_ordered_items(apb::Node::array(liked)),
+		actor: crate::keys::publish(db, ctx.ap(actor)).await?,
+		following,
+		followers,
+		files,
+	}))
+}
+
+/// uploaded files are named after the sha256 hex digest of their content
+pub fn is_upload(name: &str) -> bool {
+	name.len() == 64 && name.bytes().all(|x| x.is_ascii_hexdigit())
+}
+
+// same header mastodon uses, so that these can be imported there as is
+fn csv(accounts: Vec<(String, String)>) -> String {
+	let mut out = "Account address,Show boosts,Notify on new posts,Languages\n".to_string();
+	for (username, domain) in accounts {
+		out.push_str(&format!("{username}@{domain},true,false,\n"));
+	}
+	out
+}
@@ -145,6 +145,32 @@ pub fn strip_proto(url: &str) -> &str {
 		.unwrap_or(url)
 }
 
+/// true if domain is any of given ones, or a subdomain of them
+pub fn matches_domain<'a>(domain: &str, list: impl IntoIterator<Item = &'a String>) -> bool {
+	list.into_iter().any(|x| domain == x || domain.ends_with(&format!(".{x}")))
+}
+
+/// how many distinct domains may show up as metric labels, before the rest gets lumped as "other"
+pub const METRICS_MAX_DOMAINS: usize = 200;
+
+static METRICS_DOMAINS: std::sync::LazyLock<std::sync::RwLock<std::collections::HashSet<String>>> = std::sync::LazyLock::new(Default::default);
+
+/// label value for given domain in metrics: every label combination is a separate time series,
+/// so only the first METRICS_MAX_DOMAINS domains seen get their own
+pub fn metrics_domain(domain: &str) -> String {
+	if let Ok(seen) = METRICS_DOMAINS.read() {
+		if seen.contains(domain) { return domain.to_string() }
+		if seen.len() >= METRICS_MAX_DOMAINS { return "other".to_string() }
+	}
+	match METRICS_DOMAINS.write() {
+		Ok(mut seen) if seen.len() < METRICS_MAX_DOMAINS || seen.contains(domain) => {
+			seen.insert(domain.to_string());
+			domain.to_string()
+		},
+		_ => "other".to_string(),
+	}
+}
+
 pub fn is_blacklisted(id: &str, blacklist: &[String]) -> bool {
 	let stripped = strip_proto(id);
 	blacklist.iter().any(|x| stripped.starts_with(x))
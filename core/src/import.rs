@@ -0,0 +1,122 @@
+use apb::{ActivityMut, BaseMut, ObjectMut};
+use sea_orm::{ActiveValue::{NotSet, Set}, EntityTrait};
+
+use crate::{ext::AnyQuery, traits::Fetcher};
+
+/// extracts `user@domain` handles from a mastodon accounts csv, such as `following_accounts.csv`.
+/// header is optional, only first column is considered
+// TODO once users can block other actors, parse `blocked_accounts.csv` too and enqueue Blocks
+pub fn accounts(csv: &str) -> Vec<(String, String)> {
+	csv
+		.lines()
+		.filter_map(|line| line.split(',').next())
+		.map(|x| x.trim().trim_start_matches('@'))
+		.filter_map(|x| x.split_once('@'))
+		.filter(|(user, domain)| !user.is_empty() && !domain.is_empty())
+		.map(|(user, domain)| (user.to_string(), domain.to_string()))
+		.collect()
+}
+
+/// resolves each account and enqueues a Follow for it, spaced by `worker.import_interval_seconds`
+/// so that remote instances don't get hammered. accounts which can't be resolved or are already
+/// followed are skipped. returns how many follows got enqueued
+pub async fn follows(ctx: &crate::Context, uid: &str, accounts: Vec<(String, String)>) -> Result<usize, sea_orm::DbErr> {
+	let Some(internal) = crate::model::actor::Entity::ap_to_internal(uid, ctx.db()).await? else {
+		return Ok(0);
+	};
+
+	let interval = chrono::Duration::seconds(ctx.cfg().worker.import_interval_seconds as i64);
+	let mut when = chrono::Utc::now();
+	let mut count = 0;
+
+	for (user, domain) in accounts {
+		let target = match ctx.webfinger(&user, &domain).await {
+			Ok(Some(x)) => x,
+			Ok(None) => {
+				tracing::warn!("skipping import of {user}@{domain}: could not resolve");
+				continue;
+			},
+			Err(e) => {
+				tracing::warn!("skipping import of {user}@{domain}: {e}");
+				continue;
+			},
+		};
+
+		if target == uid {
+			continue;
+		}
+
+		if let Some(target_internal) = crate::model::actor::Entity::ap_to_internal(&target, ctx.db()).await? {
+			// pending requests count too, no need to ask twice
+			if crate::Query::related(Some(internal), Some(target_internal), true).any(ctx.db()).await? {
+				continue;
+			}
+		}
+
+		let aid = ctx.aid(&crate::Context::new_id());
+		let payload = apb::new()
+			.set_id(Some(aid.clone()))
+			.set_activity_type(Some(apb::ActivityType::Follow))
+			.set_actor(apb::Node::link(uid.to_string()))
+			.set_object(apb::Node::link(target.clone()))
+			.set_to(apb::Node::links(vec![target]))
+			.set_published(Some(when));
+
+		crate::model::job::Entity::insert(crate::model::job::ActiveModel {
+			internal: NotSet,
+			activity: Set(aid),
+			job_type: Set(crate::model::job::JobType::Outbound),
+			actor: Set(uid.to_string()),
+			target: Set(None),
+			payload: Set(Some(payload)),
+			attempt: Set(0),
+			published: Set(when),
+			not_before: Set(when),
+			error: Set(None),
+			locked_until: Set(None),
+			domain: Set(None),
+		})
+			.exec(ctx.db())
+			.await?;
+
+		when += interval;
+		count += 1;
+	}
+
+	Ok(count)
+}
+
+#[cfg(test)]
+mod test {
+	use super::accounts;
+
+	#[test]
+	fn mastodon_export_with_header() {
+		let csv = "Account address,Show boosts,Notify on new posts,Languages\nalice@example.org,true,false,\nbob@social.example,false,true,en\n";
+		assert_eq!(
+			accounts(csv),
+			vec![
+				("alice".to_string(), "example.org".to_string()),
+				("bob".to_string(), "social.example".to_string()),
+			]
+		);
+	}
+
+	#[test]
+	fn bare_handles_without_header() {
+		let csv = "@alice@example.org\r\n  bob@social.example  \n";
+		assert_eq!(
+			accounts(csv),
+			vec![
+				("alice".to_string(), "example.org".to_string()),
+				("bob".to_string(), "social.example".to_string()),
+			]
+		);
+	}
+
+	#[test]
+	fn malformed_lines_are_skipped() {
+		let csv = "\nnot-an-account,true\n@example.org\nalice@\n,alice@example.org\ncarol@example.org";
+		assert_eq!(accounts(csv), vec![("carol".to_string(), "example.org".to_string())]);
+	}
+}
@@ -1,5 +1,5 @@
 use openssl::rsa::Rsa;
-use sea_orm::{ActiveValue::{NotSet, Set}, DatabaseConnection, EntityTrait};
+use sea_orm::{ActiveValue::{NotSet, Set}, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect, SelectColumns};
 
 use crate::{ext::JsonVec, model};
 
@@ -28,6 +28,7 @@ pub async fn application(
 				let rsa = Rsa::generate(2048)?;
 				let privk = std::str::from_utf8(&rsa.private_key_to_pem()?)?.to_string();
 				let pubk = std::str::from_utf8(&rsa.public_key_to_pem()?)?.to_string();
+				let (ed_pubk, ed_privk) = crate::keys::ed25519()?;
 				let system = model::actor::ActiveModel {
 					internal: NotSet,
 					id: Set(base_url.clone()),
@@ -36,9 +37,10 @@ pub async fn application(
 					actor_type: Set(apb::ActorType::Application),
 					also_known_as: Set(JsonVec::default()),
 					moved_to: Set(None),
+					deleted: Set(None),
 					fields: Set(JsonVec::default()), // TODO we could put some useful things here actually
-					private_key: Set(Some(privk)),
-					public_key: Set(pubk),
+					private_key: Set(Some(privk.clone())),
+					public_key: Set(pubk.clone()),
 					following: Set(None),
 					following_count: Set(0),
 					followers: Set(None),
@@ -55,6 +57,12 @@ pub async fn application(
 					updated: Set(chrono::Utc::now()),
 				};
 				model::actor::Entity::insert(system).exec(db).await?;
+				model::key::Entity::insert_many([
+					crate::keys::local(crate::keys::main_key_id(&base_url), base_url.clone(), crate::keys::RSA_SHA256, pubk, privk),
+					crate::keys::local(crate::keys::ed25519_key_id(&base_url), base_url.clone(), crate::keys::ED25519, ed_pubk, ed_privk),
+				])
+					.exec(db)
+					.await?;
 				// sqlite doesn't resurn last inserted id so we're better off just querying again, it's just one time
 				model::actor::Entity::find().one(db).await?.expect("could not find app actor just inserted")
 			}
@@ -74,6 +82,7 @@ pub async fn application(
 					icon: Set(None),
 					users: Set(Some(0)),
 					posts: Set(Some(0)),
+					signature_scheme: Set(None),
 					published: Set(chrono::Utc::now()),
 					updated: Set(chrono::Utc::now()),
 				};
@@ -84,3 +93,48 @@ pub async fn application(
 		}
 	))
 }
+
+/// local actors created before ed25519 keys existed can't sign object proofs: give each one lacking
+/// a valid ed25519 key a new one. returns how many got generated
+pub async fn multikeys(db: &DatabaseConnection) -> Result<usize, InitError> {
+	let with_key = model::key::Entity::find()
+		.filter(model::key::Column::Algorithm.eq(crate::keys::ED25519))
+		.filter(model::key::Column::PrivateKey.is_not_null())
+		.filter(model::key::Column::Expires.is_null())
+		.select_only()
+		.select_column(model::key::Column::Actor)
+		.into_tuple::<String>()
+		.all(db)
+		.await?
+		.into_iter()
+		.collect::<std::collections::HashSet<String>>();
+
+	let locals = model::actor::Entity::find()
+		.filter(model::actor::Column::PrivateKey.is_not_null())
+		.filter(model::actor::Column::Deleted.is_null())
+		.select_only()
+		.select_column(model::actor::Column::Id)
+		.into_tuple::<String>()
+		.all(db)
+		.await?;
+
+	let mut count = 0;
+	for actor in locals.into_iter().filter(|x| !with_key.contains(x)) {
+		// first key id may already be taken by an expired key, if this actor rotated its keys before
+		let mut key_id = crate::keys::ed25519_key_id(&actor);
+		if model::key::Entity::find_by_ap_id(&key_id).one(db).await?.is_some() {
+			key_id = format!("{actor}#ed25519-{}", chrono::Utc::now().timestamp());
+		}
+		let (public_key, private_key) = crate::keys::ed25519()?;
+		model::key::Entity::insert(crate::keys::local(key_id, actor, crate::keys::ED25519, public_key, private_key))
+			.exec(db)
+			.await?;
+		count += 1;
+	}
+
+	if count > 0 {
+		tracing::info!("generated ed25519 keys for {count} local actors");
+	}
+
+	Ok(count)
+}
@@ -0,0 +1,171 @@
+//! FEP-8b32 object integrity proofs, using the `eddsa-jcs-2022` cryptosuite
+//! see https://www.w3.org/TR/vc-di-eddsa/#eddsa-jcs-2022
+
+use sha2::Digest;
+
+pub const CRYPTOSUITE: &str = "eddsa-jcs-2022";
+pub const CONTEXT: &str = "https://w3id.org/security/data-integrity/v2";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProofError {
+	#[error("document has no proof")]
+	Missing,
+
+	#[error("malformed proof: {0}")]
+	Malformed(&'static str),
+
+	#[error("unsupported proof: {0}")]
+	Unsupported(String),
+
+	#[error("could not canonicalize document: {0}")]
+	Canonicalization(#[from] serde_json::Error),
+
+	#[error("openssl error: {0:?}")]
+	OpenSSL(#[from] openssl::error::ErrorStack),
+}
+
+/// verification method referenced by given document proof, if any
+pub fn verification_method(document: &serde_json::Value) -> Option<&str> {
+	document.get("proof")?.get("verificationMethod")?.as_str()
+}
+
+fn hash_data(proof_options: &serde_json::Value, document: &serde_json::Value) -> Result<Vec<u8>, ProofError> {
+	let mut out = sha2::Sha256::digest(serde_jcs::to_string(proof_options)?.as_bytes()).to_vec();
+	out.extend(sha2::Sha256::digest(serde_jcs::to_string(document)?.as_bytes()));
+	Ok(out)
+}
+
+/// attach a proof to given document. it must already have its final @context, which gets
+/// extended with data integrity terms if necessary
+pub fn sign(mut document: serde_json::Value, key_id: &str, private_key: &str) -> Result<serde_json::Value, ProofError> {
+	let Some(obj) = document.as_object_mut() else {
+		return Err(ProofError::Malformed("can only sign objects"));
+	};
+	obj.remove("proof");
+	match obj.get_mut("@context") {
+		Some(serde_json::Value::Array(ctx)) => if !ctx.iter().any(|x| x.as_str() == Some(CONTEXT)) {
+			ctx.push(serde_json::Value::String(CONTEXT.to_string()));
+		},
+		Some(x @ serde_json::Value::String(_)) => if x.as_str() != Some(CONTEXT) {
+			*x = serde_json::Value::Array(vec![x.clone(), serde_json::Value::String(CONTEXT.to_string())]);
+		},
+		_ => { obj.insert("@context".to_string(), serde_json::Value::String(CONTEXT.to_string())); },
+	}
+
+	let mut proof = serde_json::json!({
+		"@context": obj.get("@context").cloned(),
+		"type": "DataIntegrityProof",
+		"cryptosuite": CRYPTOSUITE,
+		"verificationMethod": key_id,
+		"proofPurpose": "assertionMethod",
+		"created": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+	});
+
+	let data = hash_data(&proof, &document)?;
+	let key = openssl::pkey::PKey::private_key_from_pem(private_key.as_bytes())?;
+	let mut signer = openssl::sign::Signer::new_without_digest(&key)?;
+	let signature = signer.sign_oneshot_to_vec(&data)?;
+
+	if let Some(proof) = proof.as_object_mut() {
+		proof.remove("@context"); // it's the same as the document's
+		proof.insert("proofValue".to_string(), serde_json::Value::String(format!("z{}", bs58::encode(signature).into_string())));
+	}
+	if let Some(obj) = document.as_object_mut() {
+		obj.insert("proof".to_string(), proof);
+	}
+
+	Ok(document)
+}
+
+/// check proof attached to given document against given ed25519 public key pem
+pub fn verify(document: &serde_json::Value, public_key: &str) -> Result<bool, ProofError> {
+	let mut document = document.clone();
+	let Some(obj) = document.as_object_mut() else {
+		return Err(ProofError::Malformed("can only verify objects"));
+	};
+	let mut proof = match obj.remove("proof") {
+		None => return Err(ProofError::Missing),
+		Some(serde_json::Value::Object(x)) => x,
+		// TODO proof sets and chains are allowed, but nobody sends them yet
+		Some(_) => return Err(ProofError::Unsupported("multiple proofs".to_string())),
+	};
+
+	if proof.get("type").and_then(|x| x.as_str()) != Some("DataIntegrityProof") {
+		return Err(ProofError::Unsupported(format!("{:?}", proof.get("type"))));
+	}
+	match proof.get("cryptosuite").and_then(|x| x.as_str()) {
+		Some(CRYPTOSUITE) => {},
+		x => return Err(ProofError::Unsupported(format!("{x:?}"))),
+	}
+	if proof.get("proofPurpose").and_then(|x| x.as_str()) != Some("assertionMethod") {
+		return Err(ProofError::Malformed("proof purpose is not assertionMethod"));
+	}
+
+	let Some(serde_json::Value::String(value)) = proof.remove("proofValue") else {
+		return Err(ProofError::Malformed("missing proofValue"));
+	};
+	let signature = value
+		.strip_prefix('z')
+		.and_then(|x| bs58::decode(x).into_vec().ok())
+		.ok_or(ProofError::Malformed("proofValue is not base58btc multibase"))?;
+
+	// proofs may omit their @context: in that case it's implicitly the document's one. if present it
+	// must be the same, otherwise the signed terms could mean something else than what we received
+	match (proof.get("@context"), obj.get("@context")) {
+		(Some(signed), received) => if Some(signed) != received {
+			return Ok(false);
+		},
+		(None, Some(ctx)) => { proof.insert("@context".to_string(), ctx.clone()); },
+		(None, None) => {},
+	}
+
+	let data = hash_data(&serde_json::Value::Object(proof), &document)?;
+	let key = openssl::pkey::PKey::public_key_from_pem(public_key.as_bytes())?;
+	let mut verifier = openssl::sign::Verifier::new_without_digest(&key)?;
+	Ok(verifier.verify_oneshot(&signature, &data)?)
+}
+
+#[cfg(test)]
+mod test {
+	use super::{sign, verify};
+
+	fn signed() -> (serde_json::Value, String) {
+		let (public_key, private_key) = crate::keys::ed25519().expect("ed25519 keys can be generated");
+		let document = serde_json::json!({
+			"@context": "https://www.w3.org/ns/activitystreams",
+			"id": "https://example.org/objects/1",
+			"type": "Note",
+			"content": "hello",
+		});
+		let document = sign(document, "https://example.org/actors/alice#ed25519-key", &private_key)
+			.expect("document can be signed");
+		(document, public_key)
+	}
+
+	#[test]
+	fn sign_verify_round_trip() {
+		let (document, public_key) = signed();
+		assert!(verify(&document, &public_key).expect("proof is well formed"));
+	}
+
+	#[test]
+	fn tampered_document_is_refused() {
+		let (mut document, public_key) = signed();
+		document["content"] = serde_json::Value::String("goodbye".to_string());
+		assert!(!verify(&document, &public_key).expect("proof is well formed"));
+	}
+
+	#[test]
+	fn different_proof_context_is_refused() {
+		let (mut document, public_key) = signed();
+		document["@context"] = serde_json::json!(["https://www.w3.org/ns/activitystreams", "https://example.org/evil"]);
+		assert!(!verify(&document, &public_key).expect("proof is well formed"));
+
+		let (mut document, public_key) = signed();
+		let received = document["@context"].clone();
+		document["proof"]["@context"] = serde_json::json!("https://example.org/evil");
+		assert!(!verify(&document, &public_key).expect("proof is well formed"));
+		document["proof"]["@context"] = received;
+		assert!(verify(&document, &public_key).expect("proof is well formed"));
+	}
+}
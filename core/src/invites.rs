@@ -0,0 +1,95 @@
+use sea_orm::{sea_query::Expr, ActiveValue::{NotSet, Set}, ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder};
+
+use crate::{model, Context};
+
+/// short enough to type, long enough to not be guessed
+fn code() -> String {
+	uuid::Uuid::new_v4().simple().to_string()[..16].to_string()
+}
+
+/// where the frontend lets people register with this invite
+pub fn link(ctx: &Context, code: &str) -> String {
+	crate::url!(ctx, "/web/register?invite={code}")
+}
+
+/// when an invite created now and lasting given hours expires, None if that overflows
+pub fn expiry(hours: i64) -> Option<chrono::DateTime<chrono::Utc>> {
+	chrono::Duration::try_hours(hours)
+		.and_then(|d| chrono::Utc::now().checked_add_signed(d))
+}
+
+pub async fn create(
+	db: &impl ConnectionTrait,
+	created_by: Option<String>,
+	max_uses: Option<i32>,
+	expires: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<model::invite::Model, DbErr> {
+	let invite = model::invite::ActiveModel {
+		internal: NotSet,
+		code: Set(code()),
+		created_by: Set(created_by),
+		max_uses: Set(max_uses),
+		uses: Set(0),
+		expires: Set(expires),
+		published: Set(chrono::Utc::now()),
+	};
+	model::invite::Entity::insert(invite)
+		.exec_with_returning(db)
+		.await
+}
+
+/// all invites, or just the ones created by given actor
+pub async fn list(db: &impl ConnectionTrait, created_by: Option<&str>) -> Result<Vec<model::invite::Model>, DbErr> {
+	let mut select = model::invite::Entity::find()
+		.order_by_desc(model::invite::Column::Published);
+	if let Some(uid) = created_by {
+		select = select.filter(model::invite::Column::CreatedBy.eq(uid));
+	}
+	select.all(db).await
+}
+
+/// atomically consumes one use of given invite, returns None if it doesn't exist, expired or
+/// ran out of uses
+pub async fn redeem(db: &impl ConnectionTrait, code: &str) -> Result<Option<model::invite::Model>, DbErr> {
+	let res = model::invite::Entity::update_many()
+		.col_expr(model::invite::Column::Uses, Expr::col(model::invite::Column::Uses).add(1))
+		.filter(model::invite::Column::Code.eq(code))
+		.filter(
+			Condition::any()
+				.add(model::invite::Column::MaxUses.is_null())
+				.add(Expr::col(model::invite::Column::Uses).lt(Expr::col(model::invite::Column::MaxUses)))
+		)
+		.filter(
+			Condition::any()
+				.add(model::invite::Column::Expires.is_null())
+				.add(model::invite::Column::Expires.gt(chrono::Utc::now()))
+		)
+		.exec(db)
+		.await?;
+	if res.rows_affected != 1 {
+		return Ok(None);
+	}
+	model::invite::Entity::find()
+		.filter(model::invite::Column::Code.eq(code))
+		.one(db)
+		.await
+}
+
+/// only invites from cli or from admins skip registration approval, otherwise any user could let
+/// anyone in
+pub fn vouches(ctx: &Context, invite: &model::invite::Model) -> bool {
+	match invite.created_by {
+		None => true,
+		Some(ref uid) => ctx.cfg().security.admins.iter().any(|x| ctx.uid(x) == *uid),
+	}
+}
+
+/// returns false if no such invite exists (or, if given, it wasn't created by that actor)
+pub async fn revoke(db: &impl ConnectionTrait, code: &str, created_by: Option<&str>) -> Result<bool, DbErr> {
+	let mut delete = model::invite::Entity::delete_many()
+		.filter(model::invite::Column::Code.eq(code));
+	if let Some(uid) = created_by {
+		delete = delete.filter(model::invite::Column::CreatedBy.eq(uid));
+	}
+	Ok(delete.exec(db).await?.rows_affected > 0)
+}
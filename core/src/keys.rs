@@ -0,0 +1,233 @@
+use apb::{Actor, Base, BaseMut, PublicKey, PublicKeyMut};
+use sea_orm::{sea_query::Expr, ActiveValue::{NotSet, Set}, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter};
+
+use crate::model;
+
+pub const RSA_SHA256: &str = "rsa-sha256";
+pub const ED25519: &str = "ed25519";
+
+/// multicodec prefix for ed25519 public keys, see https://www.w3.org/TR/cid-1.0/#Multikey
+const ED25519_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+/// key id used before actors could have multiple keys, still used as fallback
+pub fn main_key_id(actor: &str) -> String {
+	format!("{actor}#main-key")
+}
+
+/// key id for first ed25519 key of an actor
+pub fn ed25519_key_id(actor: &str) -> String {
+	format!("{actor}#ed25519-key")
+}
+
+/// generate a new ed25519 keypair, returns (public, private) pem
+pub fn ed25519() -> Result<(String, String), openssl::error::ErrorStack> {
+	let key = openssl::pkey::PKey::generate_ed25519()?;
+	Ok((
+		String::from_utf8_lossy(&key.public_key_to_pem()?).to_string(),
+		String::from_utf8_lossy(&key.private_key_to_pem_pkcs8()?).to_string(),
+	))
+}
+
+/// encode an ed25519 public key pem as `publicKeyMultibase`
+pub fn multikey(pem: &str) -> Option<String> {
+	let key = openssl::pkey::PKey::public_key_from_pem(pem.as_bytes()).ok()?;
+	let mut bytes = ED25519_MULTICODEC.to_vec();
+	bytes.extend(key.raw_public_key().ok()?);
+	Some(format!("z{}", bs58::encode(bytes).into_string()))
+}
+
+/// decode a `publicKeyMultibase` ed25519 key into pem
+pub fn from_multikey(multibase: &str) -> Option<String> {
+	// only base58btc is widely used, don't bother with other multibase encodings
+	let bytes = bs58::decode(multibase.strip_prefix('z')?).into_vec().ok()?;
+	let raw = bytes.strip_prefix(&ED25519_MULTICODEC)?;
+	let key = openssl::pkey::PKey::public_key_from_raw_bytes(raw, openssl::pkey::Id::ED25519).ok()?;
+	Some(String::from_utf8_lossy(&key.public_key_to_pem().ok()?).to_string())
+}
+
+/// new key for a local actor, valid until rotated
+pub fn local(key_id: String, actor: String, algorithm: &str, public_key: String, private_key: String) -> model::key::ActiveModel {
+	model::key::ActiveModel {
+		internal: NotSet,
+		id: Set(key_id),
+		actor: Set(actor),
+		algorithm: Set(algorithm.to_string()),
+		public_key: Set(public_key),
+		private_key: Set(Some(private_key)),
+		published: Set(chrono::Utc::now()),
+		expires: Set(None),
+	}
+}
+
+/// key id and private key that given local actor should currently sign http requests with
+pub async fn signing(db: &impl ConnectionTrait, actor: &model::actor::Model) -> Result<Option<(String, String)>, DbErr> {
+	if let Some(key) = current(db, &actor.id, RSA_SHA256).await? {
+		return Ok(Some(key));
+	}
+	Ok(actor.private_key.clone().map(|x| (main_key_id(&actor.id), x)))
+}
+
+/// key id and private key that given local actor should currently sign object proofs with
+pub async fn assertion(db: &impl ConnectionTrait, actor: &str) -> Result<Option<(String, String)>, DbErr> {
+	current(db, actor, ED25519).await
+}
+
+async fn current(db: &impl ConnectionTrait, actor: &str, algorithm: &str) -> Result<Option<(String, String)>, DbErr> {
+	Ok(
+		model::key::Entity::active(actor)
+			.filter(model::key::Column::Algorithm.eq(algorithm))
+			.filter(model::key::Column::PrivateKey.is_not_null())
+			.one(db)
+			.await?
+			.and_then(|key| Some((key.id, key.private_key?)))
+	)
+}
+
+/// known key with given id, only if it's still valid
+pub async fn find(db: &impl ConnectionTrait, key_id: &str) -> Result<Option<model::key::Model>, DbErr> {
+	Ok(
+		model::key::Entity::find_by_ap_id(key_id)
+			.one(db)
+			.await?
+			.filter(|x| x.expires.is_none_or(|e| e > chrono::Utc::now()))
+	)
+}
+
+/// replace publicKey and assertionMethod of given actor document with all its currently valid
+/// keys, newest first
+pub async fn publish(db: &impl ConnectionTrait, actor: serde_json::Value) -> Result<serde_json::Value, DbErr> {
+	let Ok(id) = actor.id() else { return Ok(actor) };
+	let mut keys = Vec::new();
+	let mut multikeys = Vec::new();
+	for key in model::key::Entity::active(&id).all(db).await? {
+		match key.algorithm.as_str() {
+			ED25519 => if let Some(multibase) = multikey(&key.public_key) {
+				multikeys.push(serde_json::json!({
+					"id": key.id,
+					"type": "Multikey",
+					"controller": key.actor,
+					"publicKeyMultibase": multibase,
+				}));
+			},
+			_ => keys.push(
+				apb::new()
+					.set_id(Some(key.id))
+					.set_owner(Some(key.actor))
+					.set_public_key_pem(key.public_key)
+			),
+		}
+	}
+
+	let mut actor = actor;
+
+	// most software expects a single key object, only use an array if really necessary
+	match keys.len() {
+		0 => {},
+		1 => actor = apb::ActorMut::set_public_key(actor, apb::Node::object(keys.remove(0))),
+		_ => actor = apb::ActorMut::set_public_key(actor, apb::Node::array(keys)),
+	}
+
+	if !multikeys.is_empty() {
+		if let Some(obj) = actor.as_object_mut() {
+			obj.insert("assertionMethod".to_string(), serde_json::Value::Array(multikeys));
+		}
+	}
+
+	Ok(actor)
+}
+
+/// remember all keys published in a remote actor document. only keys belonging to the document
+/// itself and served from its same origin are accepted, and keys already known as someone else's
+/// (or local ones) are never touched. previously learned keys of this actor which aren't published
+/// anymore get expired
+pub async fn learn(db: &impl ConnectionTrait, actor: &serde_json::Value) -> Result<(), DbErr> {
+	let Ok(owner) = actor.id() else { return Ok(()) };
+	let origin = crate::Context::server(&owner);
+	let mut found = Vec::new();
+
+	for key in actor.public_key().flat() {
+		let Ok(key) = key.into_inner() else { continue };
+		let Ok(key_id) = key.id() else { continue };
+		// some software omits owner, but then key must at least be a fragment of the actor itself
+		let owned = match key.owner() {
+			Ok(x) => x == owner,
+			Err(_) => key_id.starts_with(&format!("{owner}#")),
+		};
+		if !owned || crate::Context::server(&key_id) != origin {
+			tracing::warn!("ignoring key {key_id} published by {owner} but not owned by it");
+			continue;
+		}
+		let pem = key.public_key_pem();
+		if pem.is_empty() { continue }
+		found.push((key_id, RSA_SHA256, pem));
+	}
+
+	let methods = match actor.get("assertionMethod") {
+		Some(serde_json::Value::Array(x)) => x.clone(),
+		Some(x @ serde_json::Value::Object(_)) => vec![x.clone()],
+		_ => Vec::new(),
+	};
+	for method in methods {
+		if method.get("type").and_then(|x| x.as_str()) != Some("Multikey") { continue }
+		let Some(key_id) = method.get("id").and_then(|x| x.as_str()) else { continue };
+		// a key controlled by someone else can't be used to prove this actor's objects
+		if method.get("controller").and_then(|x| x.as_str()) != Some(owner.as_str()) || crate::Context::server(key_id) != origin {
+			tracing::warn!("ignoring multikey {key_id} published by {owner} but not controlled by it");
+			continue;
+		}
+		let Some(pem) = method.get("publicKeyMultibase").and_then(|x| x.as_str()).and_then(from_multikey) else { continue };
+		found.push((key_id.to_string(), ED25519, pem));
+	}
+
+	let now = chrono::Utc::now();
+	let mut published = Vec::new();
+
+	for (key_id, algorithm, pem) in found {
+		match model::key::Entity::find_by_ap_id(&key_id).one(db).await? {
+			None => {
+				model::key::Entity::insert(model::key::ActiveModel {
+					internal: NotSet,
+					id: Set(key_id.clone()),
+					actor: Set(owner.clone()),
+					algorithm: Set(algorithm.to_string()),
+					public_key: Set(pem),
+					private_key: Set(None),
+					published: Set(now),
+					expires: Set(None),
+				})
+					.exec_without_returning(db)
+					.await?;
+			},
+			Some(known) if known.actor != owner || known.private_key.is_some() => {
+				tracing::warn!("ignoring key {key_id} published by {owner}: already known as a key of {}", known.actor);
+				continue;
+			},
+			// same id reused for a different kind of key, don't let one replace the other
+			Some(known) if known.algorithm != algorithm => {
+				tracing::warn!("ignoring {algorithm} key {key_id} published by {owner}: already known as {}", known.algorithm);
+				continue;
+			},
+			Some(known) => {
+				model::key::Entity::update_many()
+					.col_expr(model::key::Column::PublicKey, Expr::value(pem))
+					.col_expr(model::key::Column::Expires, Expr::value(None::<chrono::DateTime<chrono::Utc>>))
+					.filter(model::key::Column::Internal.eq(known.internal))
+					.exec(db)
+					.await?;
+			},
+		}
+		published.push(key_id);
+	}
+
+	// keys dropped from the document have been rotated away or revoked
+	model::key::Entity::update_many()
+		.col_expr(model::key::Column::Expires, Expr::value(now))
+		.filter(model::key::Column::Actor.eq(&owner))
+		.filter(model::key::Column::PrivateKey.is_null())
+		.filter(model::key::Column::Expires.is_null())
+		.filter(model::key::Column::Id.is_not_in(published))
+		.exec(db)
+		.await?;
+
+	Ok(())
+}
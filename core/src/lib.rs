@@ -16,5 +16,22 @@ pub use selector::Query;
 pub use traits::normalize::AP;
 
 pub mod downtime;
+pub mod maintenance;
+pub mod signatures;
+pub mod keys;
+pub mod integrity;
+pub mod policy;
+pub mod allowlist;
+pub mod blocks;
+pub mod quarantine;
+pub mod passwords;
+pub mod oauth;
+pub mod totp;
+pub mod invites;
+pub mod registrations;
+pub mod accounts;
+pub mod export;
+pub mod import;
+pub mod tokens;
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,172 @@
+use futures::TryStreamExt;
+use sea_orm::{ActiveModelTrait, ActiveValue::{Set, Unchanged}, ColumnTrait, EntityTrait, IntoActiveModel, ModelTrait, QueryFilter, QueryOrder};
+
+use crate::traits::{fetch::RequestError, Fetcher};
+
+/// refetch remote actors not updated in given days, oldest first. actors whose instance answers
+/// 404 or 410 get removed, unreachable instances get marked as down
+pub async fn update_actors(ctx: &crate::Context, days: i64, limit: Option<u64>) -> Result<(), sea_orm::DbErr> {
+	let mut count = 0;
+	let mut stream = crate::model::actor::Entity::find()
+		.filter(crate::model::actor::Column::Updated.lt(chrono::Utc::now() - chrono::Duration::days(days)))
+		.order_by_asc(crate::model::actor::Column::Updated)
+		.stream(ctx.db())
+		.await?;
+
+
+	while let Some(user) = stream.try_next().await? {
+		if ctx.is_local(&user.id) { continue }
+		if let Some(limit) = limit {
+			if count >= limit { break }
+		}
+		let server = crate::Context::server(&user.id);
+		if crate::downtime::get(ctx.db(), &server).await?.is_some() { continue }
+		match ctx.pull(&user.id).await.and_then(|x| x.actor()) {
+			Err(RequestError::Fetch(status, msg)) => {
+				if status.as_u16() == 410 {
+					tracing::info!("user {} has been deleted", user.id);
+					user.delete(ctx.db()).await?;
+				}
+				else if status.as_u16() == 404 {
+					tracing::info!("user {} does not exist anymore", user.id);
+					user.delete(ctx.db()).await?;
+				}
+				else {
+					crate::downtime::set(ctx.db(), &server).await?;
+					tracing::warn!("could not fetch user {}: failed with status {status} -- {msg}", user.id);
+				}
+			},
+			Err(e) => {
+				crate::downtime::set(ctx.db(), &server).await?;
+				tracing::warn!("could not fetch user {}: {e}", user.id)
+			},
+			Ok(doc) => match ctx.resolve_user(doc, ctx.db()).await {
+				Err(e) => {
+					crate::downtime::set(ctx.db(), &server).await?;
+					tracing::warn!("failed deserializing user '{}': {e}", user.id)
+				},
+				Ok(mut u) => {
+					tracing::info!("updating user {}", user.id);
+					u.internal = Unchanged(user.internal);
+					u.updated = Set(chrono::Utc::now());
+					u.update(ctx.db()).await?;
+					count += 1;
+				},
+			},
+		}
+	}
+
+	tracing::info!("updated {count} users");
+
+	Ok(())
+}
+
+/// recalculate likes, shares and replies counters from scratch
+pub async fn count(ctx: &crate::Context, likes: bool, shares: bool, replies: bool) -> Result<(), sea_orm::DbErr> {
+	let db = ctx.db();
+
+	if likes {
+		tracing::info!("counting likes...");
+		let mut store = std::collections::HashMap::new();
+		{
+			let mut stream = crate::model::like::Entity::find().stream(db).await?;
+			while let Some(like) = stream.try_next().await? {
+				store.insert(like.object, store.get(&like.object).unwrap_or(&0) + 1);
+			}
+		}
+
+		for (k, v) in store {
+			let m = crate::model::object::ActiveModel {
+				internal: Unchanged(k),
+				likes: Set(v),
+				..Default::default()
+			};
+			if let Err(e) = m.update(db).await {
+				tracing::warn!("record not updated ({k}): {e}");
+			}
+		}
+	}
+
+	if shares {
+		tracing::info!("counting shares...");
+		let mut store = std::collections::HashMap::new();
+		{
+			let mut stream = crate::model::announce::Entity::find().stream(db).await?;
+			while let Some(share) = stream.try_next().await? {
+				store.insert(share.object, store.get(&share.object).unwrap_or(&0) + 1);
+			}
+		}
+
+		for (k, v) in store {
+			let m = crate::model::object::ActiveModel {
+				internal: Unchanged(k),
+				announces: Set(v),
+				..Default::default()
+			};
+			if let Err(e) = m.update(db).await {
+				tracing::warn!("record not updated ({k}): {e}");
+			}
+		}
+	}
+
+	if replies {
+		tracing::info!("counting replies...");
+		let mut store = std::collections::HashMap::new();
+		{
+			let mut stream = crate::model::object::Entity::find().stream(db).await?;
+			while let Some(object) = stream.try_next().await? {
+				if let Some(reply) = object.in_reply_to {
+					let before = store.get(&reply).unwrap_or(&0);
+					store.insert(reply, before + 1);
+				}
+			}
+		}
+
+		for (k, v) in store {
+			let m = crate::model::object::ActiveModel {
+				id: Unchanged(k.clone()),
+				replies: Set(v),
+				..Default::default()
+			};
+			// TODO will update work with non-primary-key field??
+			if let Err(e) = m.update(db).await {
+				tracing::warn!("record not updated ({k}): {e}");
+			}
+		}
+	}
+
+	tracing::info!("done running fix tasks");
+	Ok(())
+}
+
+/// fill missing contexts: top level posts are their own context, replies inherit their parent's
+pub async fn thread(ctx: &crate::Context) -> Result<(), RequestError> {
+	let db = ctx.db();
+
+	tracing::info!("fixing contexts...");
+	let mut stream = crate::model::object::Entity::find()
+		.filter(crate::model::object::Column::Context.is_null())
+		.stream(db)
+		.await?;
+
+	while let Some(mut object) = stream.try_next().await? {
+		match object.in_reply_to {
+			None => object.context = Some(object.id.clone()),
+			Some(ref in_reply_to) => {
+				let reply = ctx.fetch_object(in_reply_to, ctx.db()).await?;
+				if let Some(context) = reply.context {
+					object.context = Some(context);
+				} else {
+					continue;
+				}
+			},
+		}
+		tracing::info!("updating context of {}", object.id);
+		crate::model::object::Entity::update(object.into_active_model())
+			.exec(ctx.db())
+			.await?;
+	}
+
+	tracing::info!("done fixing contexts");
+	Ok(())
+}
@@ -62,6 +62,8 @@ pub struct Model {
 	pub updated: ChronoDateTimeUtc,
 	pub also_known_as: JsonVec<String>,
 	pub moved_to: Option<String>,
+	/// local actors which deleted their account are kept as tombstones, so username is never reused
+	pub deleted: Option<ChronoDateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -86,6 +88,8 @@ pub enum Relation {
 	Instances,
 	#[sea_orm(has_many = "super::dislike::Entity")]
 	Dislikes,
+	#[sea_orm(has_many = "super::key::Entity")]
+	Keys,
 	#[sea_orm(has_many = "super::like::Entity")]
 	Likes,
 	#[sea_orm(has_many = "super::mention::Entity")]
@@ -142,6 +146,12 @@ impl Related<super::dislike::Entity> for Entity {
 	}
 }
 
+impl Related<super::key::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::Keys.def()
+	}
+}
+
 impl Related<super::like::Entity> for Entity {
 	fn to() -> RelationDef {
 		Relation::Likes.def()
@@ -10,6 +10,17 @@ pub struct Model {
 	pub login: String,
 	pub password: String,
 	pub active: bool,
+	/// base32 totp secret, if two factor authentication is enabled
+	pub totp: Option<String>,
+	/// newline separated hashes of unused recovery codes
+	pub recovery_codes: Option<String>,
+	/// why user wants to join, shown to admins approving registrations
+	pub reason: Option<String>,
+	/// last totp time step accepted, codes can't be used twice
+	pub totp_step: Option<i64>,
+	/// wrong codes in a row, too many lock out second factor for a while
+	pub totp_failures: i32,
+	pub totp_locked_until: Option<ChronoDateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
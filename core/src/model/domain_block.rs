@@ -0,0 +1,61 @@
+use sea_orm::entity::prelude::*;
+
+/// stronger severities imply all weaker ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, EnumIter, DeriveActiveEnum, serde::Serialize, serde::Deserialize)]
+#[sea_orm(rs_type = "i16", db_type = "SmallInteger")]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+	/// don't proxy media from this instance
+	RejectMedia = 1,
+	/// content from this instance is never public, only visible to explicit addressees
+	Silence = 2,
+	/// don't federate at all: refuse requests and activities, don't fetch from or deliver to it
+	Suspend = 3,
+}
+
+impl Severity {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Severity::RejectMedia => "reject_media",
+			Severity::Silence => "silence",
+			Severity::Suspend => "suspend",
+		}
+	}
+}
+
+impl std::str::FromStr for Severity {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"reject_media" | "reject-media" => Ok(Severity::RejectMedia),
+			"silence" => Ok(Severity::Silence),
+			"suspend" => Ok(Severity::Suspend),
+			_ => Err(format!("invalid severity '{s}', expected one of: reject_media, silence, suspend")),
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "domain_blocks")]
+pub struct Model {
+	#[sea_orm(primary_key)]
+	pub internal: i64,
+	#[sea_orm(unique)]
+	pub domain: String,
+	pub severity: Severity,
+	pub public_reason: Option<String>,
+	pub private_reason: Option<String>,
+	pub published: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Entity {
+	pub fn find_by_domain(domain: &str) -> Select<Entity> {
+		Entity::find().filter(Column::Domain.eq(domain))
+	}
+}
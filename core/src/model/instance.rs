@@ -15,6 +15,8 @@ pub struct Model {
 	pub down_since: Option<ChronoDateTimeUtc>,
 	pub users: Option<i64>,
 	pub posts: Option<i64>,
+	/// http signature format this instance accepts, if we had to learn it
+	pub signature_scheme: Option<crate::signatures::SignatureScheme>,
 	pub published: ChronoDateTimeUtc,
 	pub updated: ChronoDateTimeUtc,
 }
@@ -0,0 +1,44 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "invites")]
+pub struct Model {
+	#[sea_orm(primary_key)]
+	pub internal: i64,
+	#[sea_orm(unique)]
+	pub code: String,
+	/// local actor who created this invite, missing if created from cli
+	pub created_by: Option<String>,
+	/// unlimited if missing
+	pub max_uses: Option<i32>,
+	pub uses: i32,
+	pub expires: Option<ChronoDateTimeUtc>,
+	pub published: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+	#[sea_orm(
+		belongs_to = "super::actor::Entity",
+		from = "Column::CreatedBy",
+		to = "super::actor::Column::Id",
+		on_update = "Cascade",
+		on_delete = "Cascade"
+	)]
+	Actors,
+}
+
+impl Related<super::actor::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::Actors.def()
+	}
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+	pub fn usable(&self) -> bool {
+		self.max_uses.is_none_or(|max| self.uses < max)
+			&& self.expires.is_none_or(|e| e > chrono::Utc::now())
+	}
+}
@@ -8,6 +8,16 @@ pub enum JobType {
 	Delivery = 3,
 }
 
+impl JobType {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			JobType::Inbound => "inbound",
+			JobType::Outbound => "outbound",
+			JobType::Delivery => "delivery",
+		}
+	}
+}
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "jobs")]
 pub struct Model {
@@ -22,6 +32,8 @@ pub struct Model {
 	pub not_before: ChronoDateTimeUtc,
 	pub attempt: i16,
 	pub error: Option<String>,
+	pub locked_until: Option<ChronoDateTimeUtc>,
+	pub domain: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -54,6 +66,17 @@ impl Model {
 			published: sea_orm::ActiveValue::Set(self.published),
 			attempt: sea_orm::ActiveValue::Set(self.attempt + 1),
 			error: sea_orm::ActiveValue::Set(error),
+			locked_until: sea_orm::ActiveValue::Set(None),
+			domain: sea_orm::ActiveValue::Set(self.domain),
 		}
 	}
+
+	/// put job back in queue to be retried later, without counting this as a failed attempt
+	pub fn reschedule(self, not_before: ChronoDateTimeUtc) -> ActiveModel {
+		let attempt = self.attempt;
+		let mut active = self.repeat(None);
+		active.attempt = sea_orm::ActiveValue::Set(attempt);
+		active.not_before = sea_orm::ActiveValue::Set(not_before);
+		active
+	}
 }
@@ -0,0 +1,54 @@
+use sea_orm::{entity::prelude::*, Condition, QueryOrder};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "keys")]
+pub struct Model {
+	#[sea_orm(primary_key)]
+	pub internal: i64,
+	#[sea_orm(unique)]
+	pub id: String,
+	pub actor: String,
+	pub algorithm: String,
+	pub public_key: String,
+	pub private_key: Option<String>,
+	pub published: ChronoDateTimeUtc,
+	pub expires: Option<ChronoDateTimeUtc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+	#[sea_orm(
+		belongs_to = "super::actor::Entity",
+		from = "Column::Actor",
+		to = "super::actor::Column::Id",
+		on_update = "Cascade",
+		on_delete = "Cascade"
+	)]
+	Actors,
+}
+
+impl Related<super::actor::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::Actors.def()
+	}
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Entity {
+	pub fn find_by_ap_id(id: &str) -> Select<Entity> {
+		Entity::find().filter(Column::Id.eq(id))
+	}
+
+	/// keys which are still valid, newest first
+	pub fn active(actor: &str) -> Select<Entity> {
+		Entity::find()
+			.filter(Column::Actor.eq(actor))
+			.filter(
+				Condition::any()
+					.add(Column::Expires.is_null())
+					.add(Column::Expires.gt(chrono::Utc::now()))
+			)
+			.order_by_desc(Column::Published)
+	}
+}
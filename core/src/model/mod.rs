@@ -5,6 +5,10 @@ pub mod activity;
 pub mod config;
 pub mod credential;
 pub mod session;
+pub mod oauth_app;
+pub mod oauth_code;
+pub mod invite;
+pub mod key;
 
 pub mod instance;
 pub mod job;
@@ -21,3 +25,7 @@ pub mod mention;
 pub mod attachment;
 
 pub mod downtime;
+pub mod maintenance;
+pub mod allowed_domain;
+pub mod domain_block;
+pub mod quarantine_approval;
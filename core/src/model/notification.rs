@@ -9,6 +9,7 @@ pub struct Model {
 	pub actor: i64,
 	pub seen: bool,
 	pub published: ChronoDateTimeUtc,
+	pub quarantined: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
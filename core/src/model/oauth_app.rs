@@ -0,0 +1,51 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "oauth_apps")]
+pub struct Model {
+	#[sea_orm(primary_key)]
+	pub internal: i64,
+	#[sea_orm(unique)]
+	pub client_id: String,
+	pub client_secret: String,
+	pub name: String,
+	pub website: Option<String>,
+	/// newline separated
+	pub redirect_uris: String,
+	pub scopes: String,
+	pub published: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+	#[sea_orm(has_many = "super::oauth_code::Entity")]
+	OauthCodes,
+	#[sea_orm(has_many = "super::session::Entity")]
+	Sessions,
+}
+
+impl Related<super::oauth_code::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::OauthCodes.def()
+	}
+}
+
+impl Related<super::session::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::Sessions.def()
+	}
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Entity {
+	pub fn find_by_client_id(client_id: &str) -> Select<Entity> {
+		Entity::find().filter(Column::ClientId.eq(client_id))
+	}
+}
+
+impl Model {
+	pub fn redirect_uris(&self) -> impl Iterator<Item = &str> {
+		self.redirect_uris.lines().map(str::trim).filter(|x| !x.is_empty())
+	}
+}
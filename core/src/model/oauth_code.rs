@@ -0,0 +1,45 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "oauth_codes")]
+pub struct Model {
+	#[sea_orm(primary_key)]
+	pub internal: i64,
+	#[sea_orm(unique)]
+	pub code: String,
+	pub app: i64,
+	pub actor: String,
+	pub redirect_uri: String,
+	pub scopes: String,
+	/// PKCE S256 challenge, if client provided one
+	pub challenge: Option<String>,
+	pub expires: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+	#[sea_orm(
+		belongs_to = "super::oauth_app::Entity",
+		from = "Column::App",
+		to = "super::oauth_app::Column::Internal",
+		on_update = "Cascade",
+		on_delete = "Cascade"
+	)]
+	OauthApps,
+	#[sea_orm(
+		belongs_to = "super::actor::Entity",
+		from = "Column::Actor",
+		to = "super::actor::Column::Id",
+		on_update = "Cascade",
+		on_delete = "Cascade"
+	)]
+	Actors,
+}
+
+impl Related<super::oauth_app::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::OauthApps.def()
+	}
+}
+
+impl ActiveModelBehavior for ActiveModel {}
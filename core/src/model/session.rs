@@ -6,8 +6,23 @@ pub struct Model {
 	#[sea_orm(primary_key)]
 	pub internal: i64,
 	pub actor: String,
+	/// for api tokens and oauth sessions, only its sha256 digest
 	pub secret: String,
 	pub expires: ChronoDateTimeUtc,
+	/// space separated oauth scopes, full access if missing (password logins)
+	pub scopes: Option<String>,
+	/// oauth app which requested this session, if any
+	pub app: Option<i64>,
+	/// oauth refresh token, also just its sha256 digest
+	pub refresh: Option<String>,
+	/// missing only for sessions opened before it was tracked
+	pub actor_internal: Option<i64>,
+	pub created: ChronoDateTimeUtc,
+	/// updated at most every few minutes, not on every request
+	pub last_seen: Option<ChronoDateTimeUtc>,
+	pub user_agent: Option<String>,
+	/// set only for api tokens, which users create by hand for bots and scripts
+	pub name: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -20,6 +35,14 @@ pub enum Relation {
 		on_delete = "Cascade"
 	)]
 	Actors,
+	#[sea_orm(
+		belongs_to = "super::oauth_app::Entity",
+		from = "Column::App",
+		to = "super::oauth_app::Column::Internal",
+		on_update = "Cascade",
+		on_delete = "Cascade"
+	)]
+	OauthApps,
 }
 
 impl Related<super::actor::Entity> for Entity {
@@ -28,4 +51,17 @@ impl Related<super::actor::Entity> for Entity {
 	}
 }
 
+impl Related<super::oauth_app::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::OauthApps.def()
+	}
+}
+
 impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+	/// opened by logging in with a password, rather than by an oauth app or as an api token
+	pub fn is_login(&self) -> bool {
+		self.app.is_none() && self.name.is_none()
+	}
+}
@@ -0,0 +1,180 @@
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use sha2::Digest;
+
+/// authorization codes are meant to be exchanged right after the redirect
+pub const CODE_LIFETIME_MINUTES: i64 = 10;
+
+#[derive(Debug, thiserror::Error)]
+#[error("unknown oauth scope: {0}")]
+pub struct InvalidScope(pub String);
+
+/// what a session is allowed to do: sessions created with a password login have all scopes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Scopes {
+	pub read: bool,
+	pub write: bool,
+	pub follow: bool,
+	pub admin: bool,
+}
+
+impl Scopes {
+	pub const SUPPORTED: [&str; 4] = ["read", "write", "follow", "admin"];
+
+	pub fn all() -> Self {
+		Scopes { read: true, write: true, follow: true, admin: true }
+	}
+
+	pub fn is_empty(&self) -> bool {
+		!(self.read || self.write || self.follow || self.admin)
+	}
+
+	/// true if every scope in `other` is also granted by self
+	pub fn contains(&self, other: &Scopes) -> bool {
+		(self.read || !other.read)
+		&& (self.write || !other.write)
+		&& (self.follow || !other.follow)
+		&& (self.admin || !other.admin)
+	}
+
+	/// scopes granted in both sets
+	pub fn intersect(&self, other: &Scopes) -> Self {
+		Scopes {
+			read: self.read && other.read,
+			write: self.write && other.write,
+			follow: self.follow && other.follow,
+			admin: self.admin && other.admin,
+		}
+	}
+
+	/// coarse check done on every authenticated request, before reaching any route: reading needs
+	/// `read`, admin routes need `admin`, everything else needs `write`. posting to an outbox is
+	/// also allowed with just `follow`, but then outbox must check activity type with
+	/// [Scopes::allows_activity]
+	pub fn allows_request(&self, method: &str, path: &str) -> bool {
+		if path.starts_with("/admin") {
+			return self.admin;
+		}
+		match method {
+			"GET" | "HEAD" | "OPTIONS" => self.read,
+			"POST" if path.ends_with("/outbox") => self.write || self.follow,
+			_ => self.write,
+		}
+	}
+
+	/// `follow` only allows to manage relations, `write` allows any activity. Undo is checked
+	/// separately, see [Scopes::allows_undo]
+	pub fn allows_activity(&self, activity_type: &str) -> bool {
+		self.write || (
+			self.follow && matches!(activity_type, "Follow" | "Block" | "Accept" | "Reject")
+		)
+	}
+
+	/// with just `follow`, only follows and blocks can be undone, not posts, likes or shares
+	pub fn allows_undo(&self, undone: apb::ActivityType) -> bool {
+		self.write || (
+			self.follow && matches!(undone, apb::ActivityType::Follow | apb::ActivityType::Ignore(apb::IgnoreType::Block))
+		)
+	}
+}
+
+impl std::str::FromStr for Scopes {
+	type Err = InvalidScope;
+
+	/// space separated, granular mastodon-like scopes (`read:statuses`, `admin:write`) are
+	/// accepted but widened to the top level scope they belong to
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut scopes = Scopes::default();
+		for scope in s.split([' ', '+', ',']).filter(|x| !x.is_empty()) {
+			match scope.split(':').next().unwrap_or_default() {
+				"read" | "profile" => scopes.read = true,
+				"write" | "push" => scopes.write = true,
+				"follow" => scopes.follow = true,
+				"admin" => scopes.admin = true,
+				_ => return Err(InvalidScope(scope.to_string())),
+			}
+		}
+		Ok(scopes)
+	}
+}
+
+impl std::fmt::Display for Scopes {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let granted = [self.read, self.write, self.follow, self.admin];
+		let names: Vec<&str> = Self::SUPPORTED
+			.into_iter()
+			.zip(granted)
+			.filter_map(|(name, ok)| if ok { Some(name) } else { None })
+			.collect();
+		write!(f, "{}", names.join(" "))
+	}
+}
+
+/// PKCE with S256 method: challenge is the unpadded base64url sha256 of verifier
+pub fn verify_challenge(verifier: &str, challenge: &str) -> bool {
+	let hash = sha2::Sha256::digest(verifier.as_bytes());
+	BASE64_URL_SAFE_NO_PAD.encode(hash) == challenge
+}
+
+/// compares client secrets in constant time, so that timing doesn't tell how much of it was right
+pub fn secret_matches(given: &str, expected: &str) -> bool {
+	given.len() == expected.len() && openssl::memcmp::eq(given.as_bytes(), expected.as_bytes())
+}
+
+/// only redirect to uris the app registered, matched exactly
+pub fn valid_redirect(app: &crate::model::oauth_app::Model, redirect_uri: &str) -> bool {
+	app.redirect_uris().any(|x| x == redirect_uri)
+}
+
+#[cfg(test)]
+mod test {
+	use super::{secret_matches, verify_challenge, Scopes};
+
+	#[test]
+	fn scopes_parse_and_widen() {
+		let scopes: Scopes = "read write:statuses follow".parse().unwrap();
+		assert_eq!(scopes, Scopes { read: true, write: true, follow: true, admin: false });
+		assert_eq!("read+admin:read".parse::<Scopes>().unwrap(), Scopes { read: true, admin: true, ..Default::default() });
+		assert!("".parse::<Scopes>().unwrap().is_empty());
+		assert!("read delete".parse::<Scopes>().is_err());
+		assert_eq!(scopes.to_string(), "read write follow");
+		assert_eq!(scopes.to_string().parse::<Scopes>().unwrap(), scopes);
+	}
+
+	#[test]
+	fn scopes_gate_requests() {
+		let read = Scopes { read: true, ..Default::default() };
+		assert!(read.allows_request("GET", "/actors/alice/inbox"));
+		assert!(!read.allows_request("POST", "/actors/alice/outbox"));
+		assert!(!read.allows_request("GET", "/admin/blocks"));
+
+		let follow = Scopes { follow: true, ..Default::default() };
+		assert!(follow.allows_request("POST", "/actors/alice/outbox"));
+		assert!(!follow.allows_request("POST", "/actors/alice/tokens"));
+		assert!(follow.allows_activity("Follow"));
+		assert!(!follow.allows_activity("Create"));
+		assert!(!follow.allows_activity("Undo"));
+		assert!(follow.allows_undo(apb::ActivityType::Follow));
+		assert!(follow.allows_undo(apb::ActivityType::Ignore(apb::IgnoreType::Block)));
+		assert!(!follow.allows_undo(apb::ActivityType::Like));
+		assert!(!follow.allows_undo(apb::ActivityType::Create));
+		assert!(Scopes { write: true, ..Default::default() }.allows_undo(apb::ActivityType::Like));
+
+		assert!(Scopes::all().allows_request("DELETE", "/admin/blocks/example.com"));
+		assert!(!Scopes { write: true, ..Default::default() }.allows_request("POST", "/admin/blocks"));
+	}
+
+	#[test]
+	fn pkce_s256_matches_rfc7636_example() {
+		// RFC 7636, appendix B
+		assert!(verify_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk", "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"));
+		assert!(!verify_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXl", "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"));
+	}
+
+	#[test]
+	fn client_secrets_compare_exactly() {
+		assert!(secret_matches("s3cr3t", "s3cr3t"));
+		assert!(!secret_matches("s3cr3T", "s3cr3t"));
+		assert!(!secret_matches("s3cr3", "s3cr3t"));
+		assert!(!secret_matches("", "s3cr3t"));
+	}
+}
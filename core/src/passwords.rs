@@ -0,0 +1,82 @@
+use argon2::{password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString}, Argon2};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+	Invalid,
+	Valid,
+	/// valid, but stored with an old scheme: should be re-hashed now that we know the password
+	ValidLegacy,
+}
+
+/// argon2id hash of given password, in PHC string format
+pub fn hash(password: &str) -> String {
+	let salt = SaltString::generate(&mut OsRng);
+	Argon2::default()
+		.hash_password(password.as_bytes(), &salt)
+		.expect("argon2 with default params can hash any password")
+		.to_string()
+}
+
+/// passwords used to be stored as plain unsalted sha256 hex digests
+pub fn is_legacy(stored: &str) -> bool {
+	!stored.starts_with('$')
+}
+
+pub fn verify(password: &str, stored: &str) -> Verification {
+	if is_legacy(stored) {
+		return if sha256::digest(password) == stored { Verification::ValidLegacy } else { Verification::Invalid };
+	}
+
+	let Ok(parsed) = PasswordHash::new(stored) else {
+		tracing::error!("stored password hash is malformed, refusing login");
+		return Verification::Invalid;
+	};
+
+	match Argon2::default().verify_password(password.as_bytes(), &parsed) {
+		Ok(()) => Verification::Valid,
+		Err(_) => Verification::Invalid,
+	}
+}
+
+/// argon2 is slow on purpose: run it on a blocking thread, so it doesn't stall the async runtime
+pub async fn hash_async(password: String) -> String {
+	tokio::task::spawn_blocking(move || hash(&password))
+		.await
+		.expect("argon2 with default params can hash any password")
+}
+
+/// see [hash_async]
+pub async fn verify_async(password: String, stored: String) -> Verification {
+	tokio::task::spawn_blocking(move || verify(&password, &stored))
+		.await
+		.unwrap_or(Verification::Invalid)
+}
+
+#[cfg(test)]
+mod test {
+	use super::{hash, is_legacy, verify, Verification};
+
+	#[test]
+	fn argon2_hashes_verify() {
+		let stored = hash("very-strong-password");
+		assert!(stored.starts_with("$argon2id$"));
+		assert!(!is_legacy(&stored));
+		assert_eq!(verify("very-strong-password", &stored), Verification::Valid);
+		assert_eq!(verify("very-wrong-password", &stored), Verification::Invalid);
+		// salted: same password never hashes the same
+		assert_ne!(stored, hash("very-strong-password"));
+	}
+
+	#[test]
+	fn legacy_sha256_hashes_verify_as_legacy() {
+		let stored = sha256::digest("very-strong-password");
+		assert!(is_legacy(&stored));
+		assert_eq!(verify("very-strong-password", &stored), Verification::ValidLegacy);
+		assert_eq!(verify("very-wrong-password", &stored), Verification::Invalid);
+	}
+
+	#[test]
+	fn malformed_hashes_never_verify() {
+		assert_eq!(verify("", "$argon2id$garbage"), Verification::Invalid);
+	}
+}
@@ -0,0 +1,300 @@
+//! MRF-style federation policies: every inbound activity goes through them before becoming a job,
+//! and every outbound activity before being delivered. policies can reject activities or rewrite
+//! them, and every decision gets logged
+
+use apb::{Activity, Base, Object};
+use sea_orm::DbErr;
+
+use crate::{config::PolicyConfig, ext::matches_domain, model, Context};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+	/// activity received from a remote instance
+	Inbound,
+	/// activity about to be delivered to a remote instance
+	Outbound,
+}
+
+impl Direction {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Direction::Inbound => "inbound",
+			Direction::Outbound => "outbound",
+		}
+	}
+}
+
+pub enum Decision {
+	/// let activity through untouched
+	Pass(serde_json::Value),
+	/// let activity through, but modified, with a reason
+	Rewrite(serde_json::Value, String),
+	/// drop activity, with a reason
+	Reject(String),
+}
+
+#[async_trait::async_trait]
+pub trait Policy: Sync + Send {
+	fn name(&self) -> &'static str;
+
+	/// `domain` is the remote instance: activity origin when inbound, delivery target when outbound
+	async fn filter(&self, ctx: &Context, direction: Direction, domain: &str, activity: serde_json::Value) -> Result<Decision, DbErr>;
+}
+
+#[derive(Default)]
+pub struct Pipeline(Vec<Box<dyn Policy>>);
+
+impl Pipeline {
+	pub fn from_config(cfg: &PolicyConfig) -> Self {
+		let mut pipeline = Pipeline::default();
+		if !cfg.reject_keywords.is_empty() {
+			pipeline.push(KeywordReject(cfg.reject_keywords.iter().map(|x| x.to_lowercase()).collect()));
+		}
+		if !cfg.force_sensitive.is_empty() {
+			pipeline.push(ForceSensitive(cfg.force_sensitive.clone()));
+		}
+		if !cfg.strip_media.is_empty() {
+			pipeline.push(StripMedia(cfg.strip_media.clone()));
+		}
+		if cfg.min_actor_age_hours > 0 {
+			pipeline.push(ActorAge(chrono::Duration::hours(cfg.min_actor_age_hours)));
+		}
+		if cfg.max_mentions > 0 {
+			pipeline.push(MentionLimit(cfg.max_mentions));
+		}
+		pipeline
+	}
+
+	pub fn push(&mut self, policy: impl Policy + 'static) {
+		self.0.push(Box::new(policy));
+	}
+
+	/// run activity through all policies, in order. returns None if any rejected it
+	pub async fn apply(&self, ctx: &Context, direction: Direction, domain: &str, mut activity: serde_json::Value) -> Result<Option<serde_json::Value>, DbErr> {
+		let id = activity.id().unwrap_or_default();
+		for policy in &self.0 {
+			activity = match policy.filter(ctx, direction, domain, activity).await? {
+				Decision::Pass(x) => {
+					tracing::debug!("policy {} passed {} activity {id} ({domain})", policy.name(), direction.as_str());
+					metrics::counter!("upub_policy_decisions_total", "policy" => policy.name(), "direction" => direction.as_str(), "decision" => "pass").increment(1);
+					x
+				},
+				Decision::Rewrite(x, reason) => {
+					tracing::info!("policy {} rewrote {} activity {id} ({domain}): {reason}", policy.name(), direction.as_str());
+					metrics::counter!("upub_policy_decisions_total", "policy" => policy.name(), "direction" => direction.as_str(), "decision" => "rewrite").increment(1);
+					x
+				},
+				Decision::Reject(reason) => {
+					tracing::info!("policy {} rejected {} activity {id} ({domain}): {reason}", policy.name(), direction.as_str());
+					metrics::counter!("upub_policy_decisions_total", "policy" => policy.name(), "direction" => direction.as_str(), "decision" => "reject").increment(1);
+					return Ok(None);
+				},
+			};
+		}
+		Ok(Some(activity))
+	}
+}
+
+// object embedded in activity, which is what most policies care about
+fn embedded(activity: &mut serde_json::Value) -> Option<&mut serde_json::Map<String, serde_json::Value>> {
+	activity.get_mut("object")?.as_object_mut()
+}
+
+/// reject inbound activities containing any of given keywords, case insensitive
+pub struct KeywordReject(pub Vec<String>);
+
+#[async_trait::async_trait]
+impl Policy for KeywordReject {
+	fn name(&self) -> &'static str { "keyword-reject" }
+
+	async fn filter(&self, _ctx: &Context, direction: Direction, domain: &str, activity: serde_json::Value) -> Result<Decision, DbErr> {
+		Ok(self.decide(direction, domain, activity))
+	}
+}
+
+impl KeywordReject {
+	fn decide(&self, direction: Direction, _domain: &str, activity: serde_json::Value) -> Decision {
+		if direction != Direction::Inbound { return Decision::Pass(activity) }
+		let object = activity.object().into_inner().ok();
+		let texts = [
+			activity.content().ok(),
+			activity.summary().ok(),
+			object.as_ref().and_then(|x| x.name().ok()),
+			object.as_ref().and_then(|x| x.summary().ok()),
+			object.as_ref().and_then(|x| x.content().ok()),
+		];
+		for text in texts.into_iter().flatten() {
+			let text = text.to_lowercase();
+			if let Some(keyword) = self.0.iter().find(|k| text.contains(k.as_str())) {
+				return Decision::Reject(format!("contains '{keyword}'"));
+			}
+		}
+		Decision::Pass(activity)
+	}
+}
+
+/// mark inbound media coming from given domains as sensitive
+pub struct ForceSensitive(pub Vec<String>);
+
+#[async_trait::async_trait]
+impl Policy for ForceSensitive {
+	fn name(&self) -> &'static str { "force-sensitive" }
+
+	async fn filter(&self, _ctx: &Context, direction: Direction, domain: &str, activity: serde_json::Value) -> Result<Decision, DbErr> {
+		Ok(self.decide(direction, domain, activity))
+	}
+}
+
+impl ForceSensitive {
+	fn decide(&self, direction: Direction, domain: &str, mut activity: serde_json::Value) -> Decision {
+		if direction != Direction::Inbound || !matches_domain(domain, &self.0) {
+			return Decision::Pass(activity);
+		}
+		let Some(object) = embedded(&mut activity) else { return Decision::Pass(activity) };
+		let has_media = object.get("attachment").is_some_and(|x| !x.is_null() && x != &serde_json::json!([]));
+		if !has_media || object.get("sensitive").and_then(|x| x.as_bool()).unwrap_or(false) {
+			return Decision::Pass(activity);
+		}
+		object.insert("sensitive".to_string(), serde_json::Value::Bool(true));
+		Decision::Rewrite(activity, "marked media sensitive".to_string())
+	}
+}
+
+/// drop media attachments from and to given domains
+pub struct StripMedia(pub Vec<String>);
+
+#[async_trait::async_trait]
+impl Policy for StripMedia {
+	fn name(&self) -> &'static str { "strip-media" }
+
+	async fn filter(&self, _ctx: &Context, direction: Direction, domain: &str, activity: serde_json::Value) -> Result<Decision, DbErr> {
+		Ok(self.decide(direction, domain, activity))
+	}
+}
+
+impl StripMedia {
+	fn decide(&self, _direction: Direction, domain: &str, mut activity: serde_json::Value) -> Decision {
+		if !matches_domain(domain, &self.0) {
+			return Decision::Pass(activity);
+		}
+		let Some(object) = embedded(&mut activity) else { return Decision::Pass(activity) };
+		if object.remove("attachment").is_none() {
+			return Decision::Pass(activity);
+		}
+		Decision::Rewrite(activity, "removed attachments".to_string())
+	}
+}
+
+/// reject inbound activities from actors created too recently, a common trait of spam waves
+pub struct ActorAge(pub chrono::Duration);
+
+#[async_trait::async_trait]
+impl Policy for ActorAge {
+	fn name(&self) -> &'static str { "actor-age" }
+
+	async fn filter(&self, ctx: &Context, direction: Direction, _domain: &str, activity: serde_json::Value) -> Result<Decision, DbErr> {
+		if direction != Direction::Inbound { return Ok(Decision::Pass(activity)) }
+		let Ok(actor) = activity.actor().id() else { return Ok(Decision::Pass(activity)) };
+		// TODO actors we never saw pass through, but they're usually fetched while verifying signatures
+		let Some(actor) = model::actor::Entity::find_by_ap_id(&actor).one(ctx.db()).await? else {
+			return Ok(Decision::Pass(activity));
+		};
+		if actor.published > chrono::Utc::now() - self.0 {
+			return Ok(Decision::Reject(format!("actor {} was created at {}", actor.id, actor.published)));
+		}
+		Ok(Decision::Pass(activity))
+	}
+}
+
+/// reject inbound objects mentioning too many people at once
+pub struct MentionLimit(pub usize);
+
+#[async_trait::async_trait]
+impl Policy for MentionLimit {
+	fn name(&self) -> &'static str { "mention-limit" }
+
+	async fn filter(&self, _ctx: &Context, direction: Direction, domain: &str, activity: serde_json::Value) -> Result<Decision, DbErr> {
+		Ok(self.decide(direction, domain, activity))
+	}
+}
+
+impl MentionLimit {
+	fn decide(&self, direction: Direction, _domain: &str, activity: serde_json::Value) -> Decision {
+		if direction != Direction::Inbound { return Decision::Pass(activity) }
+		let mentions = activity
+			.get("object")
+			.and_then(|x| x.get("tag"))
+			.and_then(|x| x.as_array())
+			.map(|tags| tags.iter().filter(|t| t.get("type").and_then(|x| x.as_str()) == Some("Mention")).count())
+			.unwrap_or_default();
+		if mentions > self.0 {
+			return Decision::Reject(format!("{mentions} mentions"));
+		}
+		Decision::Pass(activity)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Decision, Direction, ForceSensitive, KeywordReject, MentionLimit, StripMedia};
+
+	fn note(object: serde_json::Value) -> serde_json::Value {
+		serde_json::json!({
+			"id": "https://remote.example/activities/1",
+			"type": "Create",
+			"actor": "https://remote.example/actors/spammer",
+			"object": object,
+		})
+	}
+
+	fn is_pass(d: &Decision) -> bool { matches!(d, Decision::Pass(_)) }
+	fn is_reject(d: &Decision) -> bool { matches!(d, Decision::Reject(_)) }
+
+	#[test]
+	fn keyword_reject_is_case_insensitive_and_inbound_only() {
+		let policy = KeywordReject(vec!["buy now".to_string()]);
+		let spam = note(serde_json::json!({ "type": "Note", "content": "<p>BUY NOW cheap pills</p>" }));
+		assert!(is_reject(&policy.decide(Direction::Inbound, "remote.example", spam.clone())));
+		assert!(is_pass(&policy.decide(Direction::Outbound, "remote.example", spam)));
+		let ham = note(serde_json::json!({ "type": "Note", "content": "hello world" }));
+		assert!(is_pass(&policy.decide(Direction::Inbound, "remote.example", ham)));
+	}
+
+	#[test]
+	fn force_sensitive_marks_media_from_matching_domains() {
+		let policy = ForceSensitive(vec!["remote.example".to_string()]);
+		let media = note(serde_json::json!({ "type": "Note", "attachment": [{ "type": "Image", "url": "https://remote.example/a.png" }] }));
+		match policy.decide(Direction::Inbound, "sub.remote.example", media.clone()) {
+			Decision::Rewrite(x, _) => assert_eq!(x["object"]["sensitive"], serde_json::Value::Bool(true)),
+			_ => panic!("media from subdomain should be marked sensitive"),
+		}
+		assert!(is_pass(&policy.decide(Direction::Inbound, "other.example", media)));
+		let text = note(serde_json::json!({ "type": "Note", "attachment": [] }));
+		assert!(is_pass(&policy.decide(Direction::Inbound, "remote.example", text)));
+	}
+
+	#[test]
+	fn strip_media_removes_attachments_both_ways() {
+		let policy = StripMedia(vec!["remote.example".to_string()]);
+		let media = note(serde_json::json!({ "type": "Note", "attachment": [{ "type": "Image" }] }));
+		for direction in [Direction::Inbound, Direction::Outbound] {
+			match policy.decide(direction, "remote.example", media.clone()) {
+				Decision::Rewrite(x, _) => assert!(x["object"].get("attachment").is_none()),
+				_ => panic!("attachments should be stripped"),
+			}
+		}
+		assert!(is_pass(&policy.decide(Direction::Inbound, "notremote.example", media)));
+	}
+
+	#[test]
+	fn mention_limit_counts_only_mentions() {
+		let policy = MentionLimit(2);
+		let tags = |n: usize| (0..n)
+			.map(|i| serde_json::json!({ "type": "Mention", "href": format!("https://local.example/actors/{i}") }))
+			.chain([serde_json::json!({ "type": "Hashtag", "name": "#spam" })])
+			.collect::<Vec<_>>();
+		assert!(is_pass(&policy.decide(Direction::Inbound, "remote.example", note(serde_json::json!({ "type": "Note", "tag": tags(2) })))));
+		assert!(is_reject(&policy.decide(Direction::Inbound, "remote.example", note(serde_json::json!({ "type": "Note", "tag": tags(3) })))));
+		assert!(is_pass(&policy.decide(Direction::Outbound, "remote.example", note(serde_json::json!({ "type": "Note", "tag": tags(3) })))));
+	}
+}
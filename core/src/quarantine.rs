@@ -0,0 +1,159 @@
+//! first contact quarantine: mentions from new remote actors nobody here knows don't notify right
+//! away, but are held until the mentioned user approves their sender
+
+use sea_orm::{sea_query::OnConflict, ActiveValue::{NotSet, Set}, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QuerySelect, QueryTrait, SelectColumns};
+
+use crate::{config::PolicyConfig, ext::AnyQuery, model, Context};
+
+/// what decides whether mentions from a remote sender get held, see [should_hold]
+#[derive(Debug, Clone, Default)]
+pub struct FirstContact {
+	pub published: chrono::DateTime<chrono::Utc>,
+	pub followers: i32,
+	/// anyone here following them vouches for them
+	pub followed_here: bool,
+	/// they follow whoever they're mentioning, so they're not really strangers
+	pub follows_recipient: bool,
+	/// mentioned user already accepted them
+	pub approved: bool,
+}
+
+impl FirstContact {
+	/// only new actors (recently created or with few followers) nobody here knows are held
+	pub fn hold(&self, cfg: &PolicyConfig, now: chrono::DateTime<chrono::Utc>) -> bool {
+		if !cfg.quarantine_first_contact {
+			return false;
+		}
+		let is_new = self.published > now - chrono::Duration::days(cfg.quarantine_actor_age_days)
+			|| self.followers < cfg.quarantine_min_followers;
+		is_new && !self.followed_here && !self.follows_recipient && !self.approved
+	}
+}
+
+/// whether a mention from given sender to given local user should be held back
+pub async fn should_hold(ctx: &Context, sender: &model::actor::Model, recipient: i64, tx: &impl ConnectionTrait) -> Result<bool, DbErr> {
+	if ctx.is_local(&sender.id) {
+		return Ok(false);
+	}
+
+	let cfg = &ctx.cfg().policy;
+	let now = chrono::Utc::now();
+	let mut contact = FirstContact {
+		published: sender.published,
+		followers: sender.followers_count,
+		..Default::default()
+	};
+	// not even a stranger would be held, no need to look for relations
+	if !contact.hold(cfg, now) {
+		return Ok(false);
+	}
+
+	contact.followed_here = model::relation::Entity::find()
+		.filter(model::relation::Column::Following.eq(sender.internal))
+		.filter(model::relation::Column::FollowerInstance.eq(ctx.instance().internal))
+		.filter(model::relation::Column::Accept.is_not_null())
+		.any(tx)
+		.await?;
+
+	contact.follows_recipient = model::relation::Entity::find()
+		.filter(model::relation::Column::Follower.eq(sender.internal))
+		.filter(model::relation::Column::Following.eq(recipient))
+		.any(tx)
+		.await?;
+
+	contact.approved = model::quarantine_approval::Entity::find()
+		.filter(model::quarantine_approval::Column::Actor.eq(recipient))
+		.filter(model::quarantine_approval::Column::Sender.eq(sender.internal))
+		.any(tx)
+		.await?;
+
+	Ok(contact.hold(cfg, now))
+}
+
+fn held(recipient: i64, sender: &str) -> sea_orm::Condition {
+	sea_orm::Condition::all()
+		.add(model::notification::Column::Actor.eq(recipient))
+		.add(model::notification::Column::Quarantined.eq(true))
+		.add(
+			model::notification::Column::Activity.in_subquery(
+				model::activity::Entity::find()
+					.select_only()
+					.select_column(model::activity::Column::Internal)
+					.filter(model::activity::Column::Actor.eq(sender))
+					.into_query()
+			)
+		)
+}
+
+/// whether given local user has notifications from given sender waiting in quarantine
+pub async fn is_held(db: &impl ConnectionTrait, recipient: i64, sender: &str) -> Result<bool, DbErr> {
+	model::notification::Entity::find()
+		.filter(held(recipient, sender))
+		.any(db)
+		.await
+}
+
+/// accept first contact from sender: release held notifications and don't hold future ones.
+/// returns how many notifications were released
+pub async fn approve(db: &impl ConnectionTrait, recipient: i64, sender: &model::actor::Model) -> Result<u64, DbErr> {
+	model::quarantine_approval::Entity::insert(model::quarantine_approval::ActiveModel {
+		internal: NotSet,
+		actor: Set(recipient),
+		sender: Set(sender.internal),
+		published: Set(chrono::Utc::now()),
+	})
+		.on_conflict(
+			OnConflict::columns([model::quarantine_approval::Column::Actor, model::quarantine_approval::Column::Sender])
+				.do_nothing()
+				.to_owned()
+		)
+		.exec_without_returning(db)
+		.await?;
+
+	let res = model::notification::Entity::update_many()
+		.col_expr(model::notification::Column::Quarantined, sea_orm::sea_query::Expr::value(false))
+		.filter(held(recipient, &sender.id))
+		.exec(db)
+		.await?;
+
+	Ok(res.rows_affected)
+}
+
+#[cfg(test)]
+mod test {
+	use super::FirstContact;
+	use crate::config::PolicyConfig;
+
+	fn cfg() -> PolicyConfig {
+		PolicyConfig { quarantine_first_contact: true, ..Default::default() }
+	}
+
+	fn stranger(now: chrono::DateTime<chrono::Utc>) -> FirstContact {
+		FirstContact { published: now - chrono::Duration::days(1), followers: 0, ..Default::default() }
+	}
+
+	#[test]
+	fn new_strangers_are_held() {
+		let now = chrono::Utc::now();
+		assert!(stranger(now).hold(&cfg(), now));
+		// old accounts without followers still look like throwaways
+		assert!(FirstContact { published: now - chrono::Duration::days(365), followers: 0, ..Default::default() }.hold(&cfg(), now));
+		// and so do popular accounts created yesterday
+		assert!(FirstContact { followers: 1000, ..stranger(now) }.hold(&cfg(), now));
+	}
+
+	#[test]
+	fn established_or_known_senders_are_released() {
+		let now = chrono::Utc::now();
+		assert!(!FirstContact { published: now - chrono::Duration::days(365), followers: 100, ..Default::default() }.hold(&cfg(), now));
+		assert!(!FirstContact { followed_here: true, ..stranger(now) }.hold(&cfg(), now));
+		assert!(!FirstContact { follows_recipient: true, ..stranger(now) }.hold(&cfg(), now));
+		assert!(!FirstContact { approved: true, ..stranger(now) }.hold(&cfg(), now));
+	}
+
+	#[test]
+	fn nothing_is_held_unless_enabled() {
+		let now = chrono::Utc::now();
+		assert!(!stranger(now).hold(&PolicyConfig::default(), now));
+	}
+}
@@ -0,0 +1,63 @@
+use sea_orm::{sea_query::Expr, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder};
+
+use crate::model;
+
+/// a registration waiting for an admin, when `security.require_user_approval` is set
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Pending {
+	pub id: String,
+	pub username: String,
+	pub reason: Option<String>,
+	pub registered: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn pending(db: &impl ConnectionTrait) -> Result<Vec<Pending>, DbErr> {
+	Ok(
+		model::credential::Entity::find()
+			.find_also_related(model::actor::Entity)
+			.filter(model::credential::Column::Active.eq(false))
+			.order_by_asc(model::actor::Column::Published)
+			.all(db)
+			.await?
+			.into_iter()
+			.filter_map(|(credential, actor)| Some(Pending {
+				registered: actor?.published,
+				id: credential.actor,
+				username: credential.login,
+				reason: credential.reason,
+			}))
+			.collect()
+	)
+}
+
+/// returns false if there's no pending registration for this actor
+pub async fn approve(db: &impl ConnectionTrait, uid: &str) -> Result<bool, DbErr> {
+	let res = model::credential::Entity::update_many()
+		.col_expr(model::credential::Column::Active, Expr::value(true))
+		.filter(model::credential::Column::Actor.eq(uid))
+		.filter(model::credential::Column::Active.eq(false))
+		.exec(db)
+		.await?;
+	Ok(res.rows_affected > 0)
+}
+
+/// deletes the actor, and with it credentials and everything else, so that username is free
+/// again. returns false if there's no pending registration for this actor
+pub async fn reject(db: &impl ConnectionTrait, uid: &str) -> Result<bool, DbErr> {
+	let pending = model::credential::Entity::find()
+		.filter(model::credential::Column::Actor.eq(uid))
+		.filter(model::credential::Column::Active.eq(false))
+		.one(db)
+		.await?;
+
+	if pending.is_none() {
+		return Ok(false);
+	}
+
+	model::actor::Entity::delete_many()
+		.filter(model::actor::Column::Id.eq(uid))
+		.exec(db)
+		.await?;
+
+	Ok(true)
+}
@@ -150,12 +150,29 @@ impl Query {
 			model::notification::Entity::find()
 				.join(sea_orm::JoinType::InnerJoin, model::notification::Relation::Activities.def())
 				.order_by_desc(model::notification::Column::Published)
-				.filter(model::notification::Column::Actor.eq(user));
+				.filter(model::notification::Column::Actor.eq(user))
+				.filter(model::notification::Column::Quarantined.eq(false));
 
 		if !show_seen {
 			select = select.filter(model::notification::Column::Seen.eq(false));
 		}
 
+		Self::notification_columns(select)
+	}
+
+	/// notifications held back by first contact quarantine, see [crate::quarantine]
+	pub fn quarantined_notifications(user: i64) -> Select<model::notification::Entity> {
+		let select =
+			model::notification::Entity::find()
+				.join(sea_orm::JoinType::InnerJoin, model::notification::Relation::Activities.def())
+				.order_by_desc(model::notification::Column::Published)
+				.filter(model::notification::Column::Actor.eq(user))
+				.filter(model::notification::Column::Quarantined.eq(true));
+
+		Self::notification_columns(select)
+	}
+
+	fn notification_columns(mut select: Select<model::notification::Entity>) -> Select<model::notification::Entity> {
 		select = select.select_only()
 			.select_column_as(
 				model::notification::Column::Seen,
@@ -180,6 +197,21 @@ impl Query {
 				actor: Set(actor),
 				seen: Set(false),
 				published: Set(chrono::Utc::now()),
+				quarantined: Set(false),
+			}
+		)
+	}
+
+	/// like [Self::notify], but held back until sender gets approved
+	pub fn notify_quarantined(activity: i64, actor: i64) -> Insert<model::notification::ActiveModel> {
+		model::notification::Entity::insert(
+			model::notification::ActiveModel {
+				internal: NotSet,
+				activity: Set(activity),
+				actor: Set(actor),
+				seen: Set(false),
+				published: Set(chrono::Utc::now()),
+				quarantined: Set(true),
 			}
 		)
 	}
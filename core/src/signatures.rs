@@ -0,0 +1,51 @@
+use sea_orm::{sea_query::Expr, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QuerySelect, SelectColumns};
+
+use crate::model;
+
+/// http signature formats, see [httpsign::HttpSignature] and [httpsign::MessageSignature]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sea_orm::EnumIter, sea_orm::DeriveActiveEnum)]
+#[sea_orm(rs_type = "i16", db_type = "SmallInteger")]
+pub enum SignatureScheme {
+	/// draft-cavage-http-signatures, what most of fedi speaks
+	Cavage = 1,
+	/// RFC 9421 http message signatures
+	Rfc9421 = 2,
+}
+
+impl SignatureScheme {
+	pub fn other(self) -> Self {
+		match self {
+			SignatureScheme::Cavage => SignatureScheme::Rfc9421,
+			SignatureScheme::Rfc9421 => SignatureScheme::Cavage,
+		}
+	}
+}
+
+/// signature format that last worked with given instance, if we had to learn it
+pub async fn get(db: &impl ConnectionTrait, domain: &str) -> Result<Option<SignatureScheme>, DbErr> {
+	Ok(
+		model::instance::Entity::find()
+			.filter(model::instance::Column::Domain.eq(domain))
+			.select_only()
+			.select_column(model::instance::Column::SignatureScheme)
+			.into_tuple::<Option<SignatureScheme>>()
+			.one(db)
+			.await?
+			.flatten()
+	)
+}
+
+/// remember which signature format given instance accepts. instances we haven't stored yet are
+/// skipped: they will just default to draft-cavage again
+pub async fn set(db: &impl ConnectionTrait, domain: &str, scheme: SignatureScheme) -> Result<(), DbErr> {
+	let res = model::instance::Entity::update_many()
+		.col_expr(model::instance::Column::SignatureScheme, Expr::value(scheme))
+		.filter(model::instance::Column::Domain.eq(domain))
+		.filter(model::instance::Column::SignatureScheme.ne(scheme).or(model::instance::Column::SignatureScheme.is_null()))
+		.exec(db)
+		.await?;
+	if res.rows_affected > 0 {
+		tracing::debug!("instance {domain} accepts {scheme:?} http signatures");
+	}
+	Ok(())
+}
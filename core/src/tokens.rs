@@ -0,0 +1,106 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use sea_orm::{ActiveValue::{NotSet, Set}, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder};
+
+use crate::{model, oauth::Scopes};
+
+/// api tokens without an explicit expiry still need one in the sessions table
+pub const NEVER_EXPIRES_DAYS: i64 = 100 * 365;
+
+/// only this is stored for api tokens and oauth sessions: leaking the sessions table shouldn't
+/// leak working tokens too. secrets are long and random, a plain digest is enough
+pub fn hash(secret: &str) -> String {
+	sha256::digest(secret)
+}
+
+/// when a token created now and lasting given days expires, None if out of range
+pub fn expiry(days: i64) -> Option<chrono::DateTime<chrono::Utc>> {
+	if !(1..=NEVER_EXPIRES_DAYS).contains(&days) {
+		return None;
+	}
+	chrono::Utc::now().checked_add_signed(chrono::Duration::try_days(days)?)
+}
+
+fn secret() -> String {
+	let mut bytes = [0u8; 48];
+	OsRng.fill_bytes(&mut bytes);
+	bs58::encode(bytes).into_string()
+}
+
+/// api tokens are just named sessions, with given scopes and possibly no expiry, which can be
+/// used as Bearer tokens by bots and scripts. returns the new session, with its secret in clear:
+/// it can't be recovered afterwards
+pub async fn create(
+	db: &impl ConnectionTrait,
+	uid: &str,
+	name: String,
+	scopes: Scopes,
+	expires: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<Option<model::session::Model>, DbErr> {
+	let Some(internal) = model::actor::Entity::ap_to_internal(uid, db).await? else {
+		return Ok(None);
+	};
+	let now = chrono::Utc::now();
+	let secret = secret();
+	let token = model::session::ActiveModel {
+		internal: NotSet,
+		actor: Set(uid.to_string()),
+		secret: Set(hash(&secret)),
+		expires: Set(expires.unwrap_or(now + chrono::Duration::days(NEVER_EXPIRES_DAYS))),
+		scopes: Set(Some(scopes.to_string())),
+		app: Set(None),
+		refresh: Set(None),
+		actor_internal: Set(Some(internal)),
+		created: Set(now),
+		last_seen: Set(None),
+		user_agent: Set(None),
+		name: Set(Some(name)),
+	};
+	let mut token = model::session::Entity::insert(token)
+		.exec_with_returning(db)
+		.await?;
+	token.secret = secret;
+	Ok(Some(token))
+}
+
+pub async fn list(db: &impl ConnectionTrait, uid: &str) -> Result<Vec<model::session::Model>, DbErr> {
+	model::session::Entity::find()
+		.filter(model::session::Column::Actor.eq(uid))
+		.filter(model::session::Column::Name.is_not_null())
+		.order_by_desc(model::session::Column::Created)
+		.all(db)
+		.await
+}
+
+/// returns false if given actor has no such token
+pub async fn revoke(db: &impl ConnectionTrait, uid: &str, id: i64) -> Result<bool, DbErr> {
+	let res = model::session::Entity::delete_many()
+		.filter(model::session::Column::Internal.eq(id))
+		.filter(model::session::Column::Actor.eq(uid))
+		.filter(model::session::Column::Name.is_not_null())
+		.exec(db)
+		.await?;
+	Ok(res.rows_affected > 0)
+}
+
+#[cfg(test)]
+mod test {
+	use super::{expiry, hash, secret, NEVER_EXPIRES_DAYS};
+
+	#[test]
+	fn expiry_is_bounded() {
+		assert!(expiry(1).is_some());
+		assert!(expiry(NEVER_EXPIRES_DAYS).is_some());
+		assert!(expiry(0).is_none());
+		assert!(expiry(-1).is_none());
+		assert!(expiry(NEVER_EXPIRES_DAYS + 1).is_none());
+		assert!(expiry(i64::MAX).is_none());
+	}
+
+	#[test]
+	fn secrets_are_not_stored_in_clear() {
+		let token = secret();
+		assert_ne!(hash(&token), token);
+		assert_eq!(hash(&token), hash(&token));
+		assert_ne!(hash(&token), hash(&secret()));
+	}
+}
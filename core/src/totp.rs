@@ -0,0 +1,169 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use hmac::Mac;
+
+type HmacSha1 = hmac::Hmac<sha1::Sha1>;
+
+/// RFC6238 defaults, which is what every authenticator app expects
+pub const STEP_SECONDS: i64 = 30;
+pub const DIGITS: u32 = 6;
+/// accept codes from one step before and after, to tolerate clock drift
+pub const SKEW_STEPS: i64 = 1;
+
+pub const RECOVERY_CODES: usize = 10;
+
+/// wrong codes in a row before second factor gets locked for a while
+pub const MAX_FAILURES: i32 = 5;
+pub const LOCKOUT_MINUTES: i64 = 15;
+
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+fn base32_encode(data: &[u8]) -> String {
+	let mut out = String::new();
+	let mut buffer: u32 = 0;
+	let mut bits = 0;
+	for byte in data {
+		buffer = (buffer << 8) | *byte as u32;
+		bits += 8;
+		while bits >= 5 {
+			bits -= 5;
+			out.push(BASE32[((buffer >> bits) & 0x1f) as usize] as char);
+		}
+	}
+	if bits > 0 {
+		out.push(BASE32[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+	}
+	out
+}
+
+fn base32_decode(data: &str) -> Option<Vec<u8>> {
+	let mut out = Vec::new();
+	let mut buffer: u32 = 0;
+	let mut bits = 0;
+	for c in data.trim_end_matches('=').bytes() {
+		let value = BASE32.iter().position(|x| *x == c.to_ascii_uppercase())? as u32;
+		buffer = (buffer << 5) | value;
+		bits += 5;
+		if bits >= 8 {
+			bits -= 8;
+			out.push((buffer >> bits) as u8);
+		}
+	}
+	Some(out)
+}
+
+/// new random base32 secret, 160 bits as suggested by RFC4226
+pub fn secret() -> String {
+	let mut bytes = [0u8; 20];
+	OsRng.fill_bytes(&mut bytes);
+	base32_encode(&bytes)
+}
+
+/// otpauth uri, usually shown as qr code to enroll authenticator apps
+pub fn uri(issuer: &str, account: &str, secret: &str) -> String {
+	format!("otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={DIGITS}&period={STEP_SECONDS}")
+}
+
+fn code_at(key: &[u8], step: i64) -> u32 {
+	let mut mac = HmacSha1::new_from_slice(key).expect("hmac accepts keys of any length");
+	mac.update(&step.to_be_bytes());
+	let hash = mac.finalize().into_bytes();
+	let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+	let truncated = u32::from_be_bytes([hash[offset] & 0x7f, hash[offset + 1], hash[offset + 2], hash[offset + 3]]);
+	truncated % 10u32.pow(DIGITS)
+}
+
+/// returns time step of given code if it's valid and newer than `last_step`, so that each code can
+/// be used only once: store it and pass it back next time
+pub fn verify(secret: &str, code: &str, last_step: Option<i64>) -> Option<i64> {
+	verify_at(secret, code, chrono::Utc::now().timestamp(), last_step)
+}
+
+fn verify_at(secret: &str, code: &str, now: i64, last_step: Option<i64>) -> Option<i64> {
+	let key = base32_decode(secret)?;
+	let code = code.trim().parse::<u32>().ok()?;
+	let step = now / STEP_SECONDS;
+	(-SKEW_STEPS ..= SKEW_STEPS)
+		.map(|delta| step + delta)
+		.filter(|x| last_step.is_none_or(|last| *x > last))
+		.find(|x| code_at(&key, *x) == code)
+}
+
+/// single use codes to get back in when the authenticator is lost, only their hashes are stored
+pub fn recovery_codes() -> Vec<String> {
+	(0..RECOVERY_CODES)
+		.map(|_| {
+			let mut bytes = [0u8; 5];
+			OsRng.fill_bytes(&mut bytes);
+			base32_encode(&bytes).to_lowercase()
+		})
+		.collect()
+}
+
+pub fn hash_recovery_code(code: &str) -> String {
+	sha256::digest(code.trim().to_lowercase())
+}
+
+/// checks a recovery code against stored newline separated hashes, returning remaining ones if
+/// it matched
+pub fn redeem_recovery_code(stored: &str, code: &str) -> Option<String> {
+	let hash = hash_recovery_code(code);
+	let mut found = false;
+	let remaining: Vec<&str> = stored
+		.lines()
+		.filter(|x| {
+			if !found && *x == hash {
+				found = true;
+				return false;
+			}
+			true
+		})
+		.collect();
+	if found { Some(remaining.join("\n")) } else { None }
+}
+
+#[cfg(test)]
+mod test {
+	use super::{base32_encode, code_at, redeem_recovery_code, hash_recovery_code, verify_at, STEP_SECONDS};
+
+	// RFC 6238 appendix B, sha1 variant: reference codes have 8 digits, we keep the last 6
+	const RFC_SECRET: &[u8] = b"12345678901234567890";
+	const RFC_VECTORS: [(i64, u32); 6] = [
+		(59, 287082),
+		(1111111109, 81804),
+		(1111111111, 50471),
+		(1234567890, 5924),
+		(2000000000, 279037),
+		(20000000000, 353130),
+	];
+
+	#[test]
+	fn codes_match_rfc6238_vectors() {
+		for (time, code) in RFC_VECTORS {
+			assert_eq!(code_at(RFC_SECRET, time / STEP_SECONDS), code, "at time {time}");
+		}
+	}
+
+	#[test]
+	fn codes_verify_once_within_skew() {
+		let secret = base32_encode(RFC_SECRET);
+		let step = 1111111109 / STEP_SECONDS;
+		assert_eq!(verify_at(&secret, "081804", 1111111109, None), Some(step));
+		// previous and next windows are tolerated, older ones aren't
+		assert_eq!(verify_at(&secret, "081804", 1111111109 + STEP_SECONDS, None), Some(step));
+		assert_eq!(verify_at(&secret, "081804", 1111111109 + 3 * STEP_SECONDS, None), None);
+		// same code can't be replayed once its step got used
+		assert_eq!(verify_at(&secret, "081804", 1111111109, Some(step)), None);
+		assert_eq!(verify_at(&secret, "000000", 1111111109, None), None);
+		assert_eq!(verify_at(&secret, "not a code", 1111111109, None), None);
+	}
+
+	#[test]
+	fn recovery_codes_redeem_once() {
+		let stored = ["aaaaaaaa", "bbbbbbbb", "cccccccc"].map(hash_recovery_code).join("\n");
+		let remaining = redeem_recovery_code(&stored, " BBBBBBBB ").expect("valid recovery code");
+		assert_eq!(remaining.lines().count(), 2);
+		assert!(!remaining.contains(&hash_recovery_code("bbbbbbbb")));
+		assert!(redeem_recovery_code(&remaining, "bbbbbbbb").is_none());
+		assert!(redeem_recovery_code(&stored, "dddddddd").is_none());
+	}
+}
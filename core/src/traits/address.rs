@@ -30,12 +30,14 @@ impl Addresser for crate::Context {
 						payload: Set(None),
 						// TODO we should resolve each user by id and check its inbox because we can't assume
 						// it's /actors/{id}/inbox for every software, but oh well it's waaaaay easier now
+						domain: Set(Some(crate::Context::server(&inbox))),
 						target: Set(Some(inbox)),
 						activity: Set(aid.to_string()),
 						published: Set(chrono::Utc::now()),
 						not_before: Set(chrono::Utc::now()),
 						attempt: Set(0),
 						error: Set(None),
+						locked_until: Set(None),
 					}
 				),
 				Ok(_) => tracing::error!("resolved target but missing inbox: '{target}', skipping delivery"),
@@ -198,7 +200,9 @@ async fn expand_addressing(targets: Vec<String>, audience: Option<String>, tx: &
 }
 
 async fn expand_addressing_with_blacklist(id: &str, blacklist: &[String], mut targets: Vec<String>, audience: Option<String>, tx: &impl ConnectionTrait) -> Result<Vec<String>, DbErr> {
-	if crate::ext::is_blacklisted(id, blacklist) {
+	if crate::ext::is_blacklisted(id, blacklist)
+		|| crate::blocks::is_blocked(tx, &crate::Context::server(id), crate::model::domain_block::Severity::Silence).await?
+	{
 		targets.retain(|x| !apb::target::is_public(x));
 	}
 	expand_addressing(targets, audience, tx).await
@@ -1,9 +1,10 @@
-use sea_orm::{ActiveValue::{NotSet, Set}, DbErr, EntityTrait};
+use sea_orm::{ActiveValue::{NotSet, Set}, ConnectionTrait, DbErr, EntityTrait};
 
 use crate::ext::JsonVec;
 
 #[allow(async_fn_in_trait)]
 pub trait Administrable {
+	#[allow(clippy::too_many_arguments)] // profile fields, plus transaction to register within
 	async fn register_user(
 		&self,
 		username: String,
@@ -12,6 +13,7 @@ pub trait Administrable {
 		summary: Option<String>,
 		avatar_url: Option<String>,
 		banner_url: Option<String>,
+		tx: &impl ConnectionTrait,
 	) -> Result<(), DbErr>;
 }
 
@@ -24,10 +26,13 @@ impl Administrable for crate::Context {
 		summary: Option<String>,
 		avatar_url: Option<String>,
 		banner_url: Option<String>,
+		tx: &impl ConnectionTrait,
 	) -> Result<(), DbErr> {
 		let key = openssl::rsa::Rsa::generate(2048).unwrap();
+		let private_key = std::str::from_utf8(&key.private_key_to_pem().unwrap()).unwrap().to_string();
+		let public_key = std::str::from_utf8(&key.public_key_to_pem().unwrap()).unwrap().to_string();
+		let (ed_public_key, ed_private_key) = crate::keys::ed25519().unwrap();
 		let ap_id = self.uid(&username);
-		let db = self.db();
 		let domain = self.domain().to_string();
 		let user_model = crate::model::actor::ActiveModel {
 			internal: NotSet,
@@ -44,6 +49,7 @@ impl Administrable for crate::Context {
 			statuses_count: Set(0),
 			also_known_as: Set(JsonVec::default()),
 			moved_to: Set(None),
+			deleted: Set(None),
 			icon: Set(avatar_url),
 			image: Set(banner_url),
 			inbox: Set(None),
@@ -52,12 +58,19 @@ impl Administrable for crate::Context {
 			actor_type: Set(apb::ActorType::Person),
 			published: Set(chrono::Utc::now()),
 			updated: Set(chrono::Utc::now()),
-			private_key: Set(Some(std::str::from_utf8(&key.private_key_to_pem().unwrap()).unwrap().to_string())),
-			public_key: Set(std::str::from_utf8(&key.public_key_to_pem().unwrap()).unwrap().to_string()),
+			private_key: Set(Some(private_key.clone())),
+			public_key: Set(public_key.clone()),
 		};
 
 		crate::model::actor::Entity::insert(user_model)
-			.exec(db)
+			.exec(tx)
+			.await?;
+
+		crate::model::key::Entity::insert_many([
+			crate::keys::local(crate::keys::main_key_id(&ap_id), ap_id.clone(), crate::keys::RSA_SHA256, public_key, private_key),
+			crate::keys::local(crate::keys::ed25519_key_id(&ap_id), ap_id.clone(), crate::keys::ED25519, ed_public_key, ed_private_key),
+		])
+			.exec(tx)
 			.await?;
 
 		let config_model = crate::model::config::ActiveModel {
@@ -72,19 +85,25 @@ impl Administrable for crate::Context {
 		};
 
 		crate::model::config::Entity::insert(config_model)
-			.exec(db)
+			.exec(tx)
 			.await?;
 
 		let credentials_model = crate::model::credential::ActiveModel {
 			internal: NotSet,
 			actor: Set(ap_id),
 			login: Set(username),
-			password: Set(sha256::digest(password)),
+			password: Set(crate::passwords::hash_async(password).await),
 			active: Set(!self.cfg().security.require_user_approval),
+			totp: Set(None),
+			recovery_codes: Set(None),
+			reason: Set(None),
+			totp_step: Set(None),
+			totp_failures: Set(0),
+			totp_locked_until: Set(None),
 		};
 
 		crate::model::credential::Entity::insert(credentials_model)
-			.exec(db)
+			.exec(tx)
 			.await?;
 		
 		Ok(())
@@ -1,13 +1,13 @@
 use std::collections::BTreeMap;
 
 use apb::{Shortcuts, Activity, Actor, ActorMut, Base, Collection, CollectionPage, Object};
-use reqwest::{header::{ACCEPT, CONTENT_TYPE, USER_AGENT}, Method, Response};
+use reqwest::{header::{ACCEPT, CONTENT_TYPE, RETRY_AFTER, USER_AGENT}, Method, Response, StatusCode};
 use sea_orm::{ActiveValue::Set, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel, NotSet, QueryFilter, ActiveModelTrait};
 
 use super::{Addresser, Cloaker, Normalizer};
-use httpsign::HttpSignature;
+use httpsign::{HttpSignature, MessageSignature};
 
-use crate::AP;
+use crate::{model::domain_block::Severity, signatures::SignatureScheme, AP};
 
 #[derive(Debug, Clone)]
 pub enum Pull<T> {
@@ -47,6 +47,9 @@ pub enum RequestError {
 
 	#[error("error constructing http signature: {0:?}")]
 	HttpSignature(#[from] httpsign::HttpSignatureError),
+
+	#[error("remote is rate limiting us, retry after {0:?}")]
+	RateLimited(Option<chrono::DateTime<chrono::Utc>>),
 }
 
 impl RequestError {
@@ -114,63 +117,169 @@ pub trait Fetcher {
 	}
 
 	async fn request(
+		&self,
+		method: reqwest::Method,
+		url: &str,
+		payload: Option<&str>,
+		key_id: &str,
+		key: &str,
+		domain: &str,
+	) -> Result<Response, RequestError>;
+
+	async fn signed_request(
+		scheme: SignatureScheme,
 		method: reqwest::Method,
 		url: &str,
 		payload: Option<&str>,
-		from: &str,
+		key_id: &str,
 		key: &str,
 		domain: &str,
 	) -> Result<Response, RequestError> {
 		let host = crate::Context::server(url);
-		let date = chrono::Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string(); // lmao @ "GMT"
+		let now = chrono::Utc::now();
+		let date = now.format("%a, %d %b %Y %H:%M:%S GMT").to_string(); // lmao @ "GMT"
 		let path = url.replace("https://", "").replace("http://", "").replace(&host, "");
 		let digest = httpsign::digest(payload.unwrap_or_default());
 
-		let headers = vec!["(request-target)", "host", "date", "digest"];
-		let headers_map : BTreeMap<String, String> = [
-			("host".to_string(), host.clone()),
-			("date".to_string(), date.clone()),
-			("digest".to_string(), digest.clone()),
-		].into();
-
-		let mut signer = HttpSignature::new(
-			format!("{from}#main-key"), // TODO don't hardcode #main-key
-			//"hs2019".to_string(), // TODO could we switch to this now?
-			"rsa-sha256".to_string(),
-			&headers,
-		);
-		
-		signer
-			.build_manually(&method.to_string().to_lowercase(), &path, headers_map)
-			.sign(key)?;
-
-		let response = Self::client(domain)
-			.request(method, url)
+		let start = std::time::Instant::now();
+		let method_label = method.to_string();
+		let mut request = Self::client(domain)
+			.request(method.clone(), url)
 			.header(ACCEPT, apb::jsonld::CONTENT_TYPE_LD_JSON_ACTIVITYPUB)
 			.header(CONTENT_TYPE, apb::jsonld::CONTENT_TYPE_LD_JSON_ACTIVITYPUB)
 			.header("Host", host.clone())
 			.header("Date", date.clone())
-			.header("Digest", digest)
-			.header("Signature", signer.header())
+			.header("Digest", digest.clone());
+
+		match scheme {
+			SignatureScheme::Cavage => {
+				let headers = vec!["(request-target)", "host", "date", "digest"];
+				let headers_map : BTreeMap<String, String> = [
+					("host".to_string(), host.clone()),
+					("date".to_string(), date.clone()),
+					("digest".to_string(), digest),
+				].into();
+
+				let mut signer = HttpSignature::new(
+					key_id.to_string(),
+					"rsa-sha256".to_string(),
+					&headers,
+				);
+
+				signer
+					.build_manually(&method.to_string().to_lowercase(), &path, headers_map)
+					.sign(key)?;
+
+				request = request.header("Signature", signer.header());
+			},
+			SignatureScheme::Rfc9421 => {
+				let content_digest = httpsign::content_digest(payload.unwrap_or_default());
+				let mut components = vec!["@method", "@target-uri", "date"];
+				if payload.is_some() {
+					components.push("content-digest");
+				}
+				let headers_map : BTreeMap<String, String> = [
+					("date".to_string(), date),
+					("content-digest".to_string(), content_digest.clone()),
+				].into();
+
+				let mut signer = MessageSignature::new(
+					key_id.to_string(),
+					Some(MessageSignature::RSA_SHA256.to_string()),
+					&components,
+					now.timestamp(),
+				);
+
+				signer
+					.build_manually(method.as_str(), url, headers_map)
+					.sign(key)?;
+
+				request = request
+					.header("Content-Digest", content_digest)
+					.header("Signature-Input", signer.header_input())
+					.header("Signature", signer.header_signature());
+			},
+		}
+
+		let response = request
 			.body(payload.unwrap_or("").to_string())
 			.send()
-			.await?;
+			.await
+			.inspect_err(|_| metrics::counter!("upub_fetcher_requests_total", "method" => method_label.clone(), "domain" => crate::ext::metrics_domain(&host), "status" => "error").increment(1))?;
+
+		metrics::histogram!("upub_fetcher_request_duration_seconds", "method" => method_label.clone()).record(start.elapsed().as_secs_f64());
+		metrics::counter!("upub_fetcher_requests_total", "method" => method_label, "domain" => crate::ext::metrics_domain(&host), "status" => response.status().as_u16().to_string()).increment(1);
 
 		// TODO this is ugly but i want to see the raw response text when it's a failure
 		match response.error_for_status_ref() {
 			Ok(_) => Ok(response),
-			Err(e) =>
-				Err(RequestError::Fetch(
-					e.status().unwrap_or_default(),
-					response.text().await?,
-				)),
+			Err(e) => {
+				let status = e.status().unwrap_or_default();
+				let retry_after = retry_after(response.headers());
+				// 503 is also used by some software to ask for backoff, but only if it says for how long
+				if status == StatusCode::TOO_MANY_REQUESTS || (status == StatusCode::SERVICE_UNAVAILABLE && retry_after.is_some()) {
+					return Err(RequestError::RateLimited(retry_after));
+				}
+				Err(RequestError::Fetch(status, response.text().await?))
+			},
 		}
 	}
 }
 
+/// every outbound request passes through here: refuse talking with instances we don't federate with
+pub async fn gate(ctx: &crate::Context, url: &str) -> Result<(), RequestError> {
+	let domain = crate::Context::server(url);
+	if !crate::allowlist::is_allowed(ctx, &domain).await? {
+		tracing::debug!("not fetching {url}: {domain} is not allowlisted");
+		return Err(RequestError::AbortedForPolicy);
+	}
+	if crate::blocks::is_blocked(ctx.db(), &domain, Severity::Suspend).await? {
+		tracing::debug!("not fetching {url}: {domain} is suspended");
+		return Err(RequestError::AbortedForPolicy);
+	}
+	Ok(())
+}
+
+/// parse Retry-After header, which can be either seconds to wait or an http date
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<chrono::DateTime<chrono::Utc>> {
+	let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+	if let Ok(seconds) = value.parse::<u64>() {
+		return Some(chrono::Utc::now() + std::time::Duration::from_secs(seconds));
+	}
+	chrono::DateTime::parse_from_rfc2822(value)
+		.ok()
+		.map(|x| x.with_timezone(&chrono::Utc))
+}
+
 
 #[async_trait::async_trait]
 impl Fetcher for crate::Context {
+	async fn request(
+		&self,
+		method: reqwest::Method,
+		url: &str,
+		payload: Option<&str>,
+		key_id: &str,
+		key: &str,
+		domain: &str,
+	) -> Result<Response, RequestError> {
+		gate(self, url).await?;
+		let host = crate::Context::server(url);
+		// draft-cavage is what most of fedi speaks, so it's the default for instances we know nothing
+		// about. instances which sign with RFC 9421 get it back, and whichever scheme gets accepted
+		// after a refusal is stored on the instance for next requests
+		let scheme = crate::signatures::get(self.db(), &host).await?.unwrap_or(SignatureScheme::Cavage);
+		match Self::signed_request(scheme, method.clone(), url, payload, key_id, key, domain).await {
+			// 400 could be anything, but 401 is what software answers to signatures they can't verify
+			Err(RequestError::Fetch(StatusCode::UNAUTHORIZED, _)) => {
+				let res = Self::signed_request(scheme.other(), method, url, payload, key_id, key, domain).await?;
+				crate::signatures::set(self.db(), &host, scheme.other()).await?;
+				Ok(res)
+			},
+			res => res,
+		}
+	}
+
 	async fn pull_r(&self, id: &str, depth: u32) -> Result<Pull<serde_json::Value>, RequestError> {
 		if crate::ext::is_blacklisted(id, &self.cfg().reject.fetch) {
 			return Err(RequestError::AbortedForPolicy);
@@ -179,9 +288,9 @@ impl Fetcher for crate::Context {
 		tracing::debug!("fetching {id}");
 		// let _domain = self.fetch_domain(&crate::Context::server(id)).await?;
 
-		let document = Self::request(
+		let document = self.request(
 			Method::GET, id, None,
-			self.base(), self.pkey(), self.domain(),
+			self.key_id(), self.pkey(), self.domain(),
 		)
 			.await?
 			.json::<serde_json::Value>()
@@ -221,6 +330,7 @@ impl Fetcher for crate::Context {
 
 		let subject = format!("acct:{user}@{host}");
 		let webfinger_uri = format!("https://{host}/.well-known/webfinger?resource={subject}");
+		gate(self, &webfinger_uri).await?;
 		let resource = reqwest::Client::new()
 			.get(webfinger_uri)
 			.header(ACCEPT, "application/jrd+json")
@@ -255,6 +365,9 @@ impl Fetcher for crate::Context {
 			return Ok(x); // already in db, easy
 		}
 
+		// nodeinfo doesn't go through our request method, check before fetching anything
+		gate(self, &format!("https://{domain}")).await?;
+
 		let mut instance_model = crate::model::instance::Model {
 			internal: 0,
 			domain: domain.to_string(),
@@ -265,13 +378,14 @@ impl Fetcher for crate::Context {
 			version: None,
 			users: None,
 			posts: None,
+			signature_scheme: None,
 			published: chrono::Utc::now(),
 			updated: chrono::Utc::now(),
 		};
 
-		if let Ok(res) = Self::request(
+		if let Ok(res) = self.request(
 			Method::GET, &format!("https://{domain}"), None,
-			self.base(), self.pkey(), self.domain(),
+			self.key_id(), self.pkey(), self.domain(),
 		).await {
 			if let Ok(actor) = res.json::<serde_json::Value>().await {
 				if let Ok(name) = actor.name() {
@@ -362,12 +476,14 @@ impl Fetcher for crate::Context {
 			}
 		}
 
-		let active_model = self.resolve_user(document, tx).await?;
+		let active_model = self.resolve_user(document.clone(), tx).await?;
 
 		// TODO this may fail: while fetching, remote server may fetch our service actor.
 		//      if it does so with http signature, we will fetch that actor in background
 		//      meaning that, once we reach here, it's already inserted and returns an UNIQUE error
 		crate::model::actor::Entity::insert(active_model).exec(tx).await?;
+
+		crate::keys::learn(tx, &document).await?;
 		
 		// TODO fetch it back to get the internal id
 		Ok(
@@ -576,7 +692,7 @@ impl Dereferenceable<serde_json::Value> for apb::Node<serde_json::Value> {
 					return Err(RequestError::AbortedForPolicy);
 				}
 				tracing::info!("dereferencing {href}");
-				let res = crate::Context::request(Method::GET, &href, None, ctx.base(), ctx.pkey(), ctx.domain())
+				let res = ctx.request(Method::GET, &href, None, ctx.key_id(), ctx.pkey(), ctx.domain())
 					.await?
 					.json::<serde_json::Value>()
 					.await?;
@@ -103,7 +103,7 @@ impl Normalizer for crate::Context {
 						is_image = true;
 						if self.cfg().compat.fix_attachment_images_media_type {
 							document_type = apb::DocumentType::Image;
-							media_type = format!("image/{}", url.split('.').last().unwrap_or_default());
+							media_type = format!("image/{}", url.split('.').next_back().unwrap_or_default());
 						}
 					}
 
@@ -161,7 +161,7 @@ impl Normalizer for crate::Context {
 					},
 					Ok(apb::LinkType::Hashtag) => {
 						let hashtag = l.name()
-							.unwrap_or_else(|_| l.href().unwrap_or_default().split('/').last().unwrap_or_default().to_string()) // TODO maybe just fail?
+							.unwrap_or_else(|_| l.href().unwrap_or_default().split('/').next_back().unwrap_or_default().to_string()) // TODO maybe just fail?
 							.replace('#', "");
 						// TODO lemmy added a "fix" to make its communities kind of work with mastodon:
 						//      basically they include the community name as hashtag. ughhhh, since we handle
@@ -361,7 +361,7 @@ impl AP {
 				.replace("https://", "");
 			let mut splits = clean.split('/');
 			let first = splits.next().unwrap_or("");
-			let last = splits.last().unwrap_or(first);
+			let last = splits.next_back().unwrap_or(first);
 			(first.to_string(), last.to_string())
 		};
 		Ok(crate::model::actor::Model {
@@ -381,6 +381,7 @@ impl AP {
 			following: actor.following().id().ok(),
 			also_known_as: actor.also_known_as().flat().into_iter().filter_map(|x| x.id().ok()).collect::<Vec<String>>().into(),
 			moved_to: actor.moved_to().id().ok(),
+			deleted: None,
 			published: actor.published().unwrap_or(chrono::Utc::now()),
 			updated: chrono::Utc::now(),
 			following_count: actor.following_count().unwrap_or(0) as i32,
@@ -401,6 +402,7 @@ impl AP {
 		let mut m = AP::actor(actor)?.into_active_model();
 		m = m.reset_all();
 		m.private_key = NotSet;
+		m.deleted = NotSet; // only ever set locally
 		match internal {
 			Some(x) => m.internal = Unchanged(x),
 			None => m.internal = NotSet,
@@ -84,12 +84,25 @@ pub async fn process_create(ctx: &crate::Context, activity: impl apb::Activity,
 	let activity_model = ctx.insert_activity(activity, tx).await?;
 	ctx.address(Some(&activity_model), Some(&object_model), tx).await?;
 
+	let sender = crate::model::actor::Entity::find_by_ap_id(&activity_model.actor).one(tx).await?;
+
 	for uid in notified {
 		if !ctx.is_local(&uid) || uid == activity_model.actor { continue }
 		if let Some(actor_internal) = crate::model::actor::Entity::ap_to_internal(&uid, tx).await? {
-			crate::Query::notify(activity_model.internal, actor_internal)
-				.exec(tx)
-				.await?;
+			let hold = match sender {
+				Some(ref sender) => crate::quarantine::should_hold(ctx, sender, actor_internal, tx).await?,
+				None => false,
+			};
+			if hold {
+				tracing::info!("holding mention of {uid} from {} in quarantine", activity_model.actor);
+				crate::Query::notify_quarantined(activity_model.internal, actor_internal)
+					.exec(tx)
+					.await?;
+			} else {
+				crate::Query::notify(activity_model.internal, actor_internal)
+					.exec(tx)
+					.await?;
+			}
 		}
 	}
 
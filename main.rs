@@ -101,6 +101,10 @@ enum Mode {
 		#[arg(short, long, default_value_t = 20)]
 		/// interval for polling new tasks
 		poll: u64,
+
+		#[arg(long)]
+		/// serve prometheus metrics for this worker on given addr
+		metrics: Option<std::net::SocketAddr>,
 	},
 }
 
@@ -200,9 +204,14 @@ async fn init(args: Args, config: upub::Config) {
 				.await.expect("failed serving api routes"),
 
 		#[cfg(feature = "worker")]
-		Mode::Work { filter, tasks, poll } =>
+		Mode::Work { filter, tasks, poll, metrics } => {
+			if let Some(bind) = metrics {
+				worker::serve_metrics(bind).expect("failed serving worker metrics");
+			}
+
 			worker::spawn(ctx, tasks, poll, filter.into(), stop, wake)
-				.await.expect("failed running worker"),
+				.await.expect("failed running worker");
+		},
 
 		#[cfg(all(feature = "serve", feature = "worker"))]
 		Mode::Monolith { bind, tasks, poll } => {
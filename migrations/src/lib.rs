@@ -24,6 +24,22 @@ mod m20241226_000001_add_show_likes_collection;
 mod m20241226_000002_add_like_activities;
 mod m20241226_000003_create_downtime_table;
 mod m20250115_000001_add_content_to_activities;
+mod m20261018_000001_add_lease_to_jobs;
+mod m20261018_000002_add_domain_to_jobs;
+mod m20261018_000003_create_maintenance_table;
+mod m20261018_000004_create_keys_table;
+mod m20261018_000005_create_allowed_domains_table;
+mod m20261018_000006_create_domain_blocks_table;
+mod m20261018_000007_add_quarantine_to_notifications;
+mod m20261018_000008_create_oauth_tables;
+mod m20261018_000009_add_details_to_sessions;
+mod m20261018_000010_add_totp_to_credentials;
+mod m20261018_000011_create_invites_table;
+mod m20261018_000012_add_reason_to_credentials;
+mod m20261018_000013_add_deleted_to_actors;
+mod m20261018_000014_add_name_to_sessions;
+mod m20261018_000015_add_signature_scheme_to_instances;
+mod m20261018_000016_add_totp_limits_to_credentials;
 
 pub struct Migrator;
 
@@ -55,6 +71,22 @@ impl MigratorTrait for Migrator {
 			Box::new(m20241226_000002_add_like_activities::Migration),
 			Box::new(m20241226_000003_create_downtime_table::Migration),
 			Box::new(m20250115_000001_add_content_to_activities::Migration),
+			Box::new(m20261018_000001_add_lease_to_jobs::Migration),
+			Box::new(m20261018_000002_add_domain_to_jobs::Migration),
+			Box::new(m20261018_000003_create_maintenance_table::Migration),
+			Box::new(m20261018_000004_create_keys_table::Migration),
+			Box::new(m20261018_000005_create_allowed_domains_table::Migration),
+			Box::new(m20261018_000006_create_domain_blocks_table::Migration),
+			Box::new(m20261018_000007_add_quarantine_to_notifications::Migration),
+			Box::new(m20261018_000008_create_oauth_tables::Migration),
+			Box::new(m20261018_000009_add_details_to_sessions::Migration),
+			Box::new(m20261018_000010_add_totp_to_credentials::Migration),
+			Box::new(m20261018_000011_create_invites_table::Migration),
+			Box::new(m20261018_000012_add_reason_to_credentials::Migration),
+			Box::new(m20261018_000013_add_deleted_to_actors::Migration),
+			Box::new(m20261018_000014_add_name_to_sessions::Migration),
+			Box::new(m20261018_000015_add_signature_scheme_to_instances::Migration),
+			Box::new(m20261018_000016_add_totp_limits_to_credentials::Migration),
 		]
 	}
 }
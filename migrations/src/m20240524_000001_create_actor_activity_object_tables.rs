@@ -25,6 +25,7 @@ pub enum Actors {
 	PrivateKey,
 	AlsoKnownAs, // added with migration m20240715_000002
 	MovedTo, // added with migration m20240715_000002
+	Deleted, // added with m20261018_000013
 	Published,
 	Updated,
 }
@@ -88,6 +89,7 @@ pub enum Instances {
 	Posts,
 	Published,
 	Updated,
+	SignatureScheme, // added with m20261018_000015
 }
 
 
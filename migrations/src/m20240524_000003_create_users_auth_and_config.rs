@@ -23,6 +23,12 @@ pub enum Credentials {
 	Login,
 	Password,
 	Active, // ADDED
+	Totp, // added with m20261018_000010
+	RecoveryCodes, // added with m20261018_000010
+	Reason, // added with m20261018_000012
+	TotpStep, // added with m20261018_000016
+	TotpFailures, // added with m20261018_000016
+	TotpLockedUntil, // added with m20261018_000016
 }
 
 #[derive(DeriveIden)]
@@ -32,6 +38,14 @@ pub enum Sessions {
 	Actor,
 	Secret,
 	Expires,
+	Scopes, // added with m20261018_000008
+	App, // added with m20261018_000008
+	Refresh, // added with m20261018_000008
+	ActorInternal, // added with m20261018_000009
+	Created, // added with m20261018_000009
+	LastSeen, // added with m20261018_000009
+	UserAgent, // added with m20261018_000009
+	Name, // added with m20261018_000014
 }
 
 #[derive(DeriveMigrationName)]
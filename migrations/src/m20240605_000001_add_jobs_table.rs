@@ -15,6 +15,8 @@ pub enum Jobs {
 	NotBefore,
 	Attempt,
 	Error, // added after
+	LockedUntil, // added with m20261018_000001
+	Domain, // added with m20261018_000002
 }
 
 
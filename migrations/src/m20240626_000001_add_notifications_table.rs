@@ -11,6 +11,7 @@ pub enum Notifications {
 	Actor,
 	Seen,
 	Published,
+	Quarantined, // added with m20261018_000007
 }
 
 #[derive(DeriveMigrationName)]
@@ -0,0 +1,63 @@
+use sea_orm_migration::{prelude::*, sea_orm::{ConnectionTrait, DatabaseBackend}};
+
+use crate::m20240605_000001_add_jobs_table::Jobs;
+
+// must match upub_worker::dispatcher::JOBS_NOTIFY_CHANNEL
+const NOTIFY_FUNCTION: &str = r#"
+CREATE OR REPLACE FUNCTION notify_jobs_inserted() RETURNS trigger AS $$
+BEGIN
+	PERFORM pg_notify('upub_jobs', '');
+	RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+"#;
+
+const NOTIFY_TRIGGER: &str = r#"
+CREATE TRIGGER "trigger-jobs-notify"
+	AFTER INSERT ON jobs
+	FOR EACH STATEMENT
+	EXECUTE FUNCTION notify_jobs_inserted();
+"#;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Jobs::Table)
+					.add_column(ColumnDef::new(Jobs::LockedUntil).timestamp_with_time_zone().null())
+					.to_owned()
+			)
+			.await?;
+
+		// sqlite workers still lock jobs by deleting them, and can't LISTEN anyway
+		if manager.get_database_backend() == DatabaseBackend::Postgres {
+			manager.get_connection().execute_unprepared(NOTIFY_FUNCTION).await?;
+			manager.get_connection().execute_unprepared(NOTIFY_TRIGGER).await?;
+		}
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		if manager.get_database_backend() == DatabaseBackend::Postgres {
+			manager.get_connection().execute_unprepared(r#"DROP TRIGGER IF EXISTS "trigger-jobs-notify" ON jobs;"#).await?;
+			manager.get_connection().execute_unprepared("DROP FUNCTION IF EXISTS notify_jobs_inserted();").await?;
+		}
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Jobs::Table)
+					.drop_column(Jobs::LockedUntil)
+					.to_owned()
+			)
+			.await?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveIden)]
+pub enum Maintenance {
+	Table,
+	Internal,
+	Task,
+	LastRun,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+
+		manager
+			.create_table(
+				Table::create()
+					.table(Maintenance::Table)
+					.comment("last run of periodic maintenance tasks, shared across workers")
+					.col(
+						ColumnDef::new(Maintenance::Internal)
+							.big_integer()
+							.not_null()
+							.auto_increment()
+							.primary_key()
+					)
+					.col(ColumnDef::new(Maintenance::Task).string().not_null().unique_key())
+					.col(ColumnDef::new(Maintenance::LastRun).timestamp_with_time_zone().not_null())
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.create_index(Index::create().unique().name("index-maintenance-task").table(Maintenance::Table).col(Maintenance::Task).to_owned())
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(Maintenance::Table).to_owned())
+			.await?;
+
+		Ok(())
+	}
+}
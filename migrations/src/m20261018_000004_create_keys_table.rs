@@ -0,0 +1,83 @@
+use sea_orm_migration::{prelude::*, sea_orm::ConnectionTrait};
+
+use crate::m20240524_000001_create_actor_activity_object_tables::Actors;
+
+#[derive(DeriveIden)]
+pub enum Keys {
+	Table,
+	Internal,
+	Id,
+	Actor,
+	Algorithm,
+	PublicKey,
+	PrivateKey,
+	Published,
+	Expires,
+}
+
+// local actors keep signing with their current key, now tracked in keys table too
+const BACKFILL_LOCAL_KEYS: &str = r#"
+INSERT INTO "keys" ("id", "actor", "algorithm", "public_key", "private_key", "published")
+	SELECT "id" || '#main-key', "id", 'rsa-sha256', "public_key", "private_key", "published"
+	FROM "actors"
+	WHERE "private_key" IS NOT NULL;
+"#;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(Keys::Table)
+					.comment("public (and private, if local) keys of actors, with their validity")
+					.col(
+						ColumnDef::new(Keys::Internal)
+							.big_integer()
+							.not_null()
+							.primary_key()
+							.auto_increment()
+					)
+					.col(ColumnDef::new(Keys::Id).string().not_null().unique_key())
+					.col(ColumnDef::new(Keys::Actor).string().not_null())
+					.foreign_key(
+						ForeignKey::create()
+							.name("fkey-keys-actor")
+							.from(Keys::Table, Keys::Actor)
+							.to(Actors::Table, Actors::Id)
+							.on_update(ForeignKeyAction::Cascade)
+							.on_delete(ForeignKeyAction::Cascade)
+					)
+					.col(ColumnDef::new(Keys::Algorithm).string().not_null())
+					.col(ColumnDef::new(Keys::PublicKey).string().not_null())
+					.col(ColumnDef::new(Keys::PrivateKey).string().null())
+					.col(ColumnDef::new(Keys::Published).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+					.col(ColumnDef::new(Keys::Expires).timestamp_with_time_zone().null())
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.create_index(Index::create().unique().name("index-keys-id").table(Keys::Table).col(Keys::Id).to_owned())
+			.await?;
+
+		manager
+			.create_index(Index::create().name("index-keys-actor").table(Keys::Table).col(Keys::Actor).to_owned())
+			.await?;
+
+		manager.get_connection().execute_unprepared(BACKFILL_LOCAL_KEYS).await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(Keys::Table).to_owned())
+			.await?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveIden)]
+pub enum AllowedDomains {
+	Table,
+	Internal,
+	Domain,
+	Published,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+
+		manager
+			.create_table(
+				Table::create()
+					.table(AllowedDomains::Table)
+					.comment("instances we federate with when running in allowlist mode, on top of configured ones")
+					.col(
+						ColumnDef::new(AllowedDomains::Internal)
+							.big_integer()
+							.not_null()
+							.auto_increment()
+							.primary_key()
+					)
+					.col(ColumnDef::new(AllowedDomains::Domain).string().not_null().unique_key())
+					.col(ColumnDef::new(AllowedDomains::Published).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.create_index(Index::create().unique().name("index-allowed-domains-domain").table(AllowedDomains::Table).col(AllowedDomains::Domain).to_owned())
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(AllowedDomains::Table).to_owned())
+			.await?;
+
+		Ok(())
+	}
+}
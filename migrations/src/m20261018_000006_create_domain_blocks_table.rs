@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveIden)]
+pub enum DomainBlocks {
+	Table,
+	Internal,
+	Domain,
+	Severity,
+	PublicReason,
+	PrivateReason,
+	Published,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+
+		manager
+			.create_table(
+				Table::create()
+					.table(DomainBlocks::Table)
+					.comment("moderation decisions against whole instances, applied to their subdomains too")
+					.col(
+						ColumnDef::new(DomainBlocks::Internal)
+							.big_integer()
+							.not_null()
+							.auto_increment()
+							.primary_key()
+					)
+					.col(ColumnDef::new(DomainBlocks::Domain).string().not_null().unique_key())
+					.col(ColumnDef::new(DomainBlocks::Severity).small_integer().not_null())
+					.col(ColumnDef::new(DomainBlocks::PublicReason).string().null())
+					.col(ColumnDef::new(DomainBlocks::PrivateReason).string().null())
+					.col(ColumnDef::new(DomainBlocks::Published).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.create_index(Index::create().unique().name("index-domain-blocks-domain").table(DomainBlocks::Table).col(DomainBlocks::Domain).to_owned())
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(DomainBlocks::Table).to_owned())
+			.await?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,95 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20240524_000001_create_actor_activity_object_tables::Actors, m20240626_000001_add_notifications_table::Notifications};
+
+#[derive(DeriveIden)]
+pub enum QuarantineApprovals {
+	Table,
+	Internal,
+	Actor,
+	Sender,
+	Published,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Notifications::Table)
+					.add_column(ColumnDef::new(Notifications::Quarantined).boolean().not_null().default(false))
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.create_table(
+				Table::create()
+					.table(QuarantineApprovals::Table)
+					.comment("remote actors each local user accepts first contact from, skipping quarantine")
+					.col(
+						ColumnDef::new(QuarantineApprovals::Internal)
+							.big_integer()
+							.not_null()
+							.auto_increment()
+							.primary_key()
+					)
+					.col(ColumnDef::new(QuarantineApprovals::Actor).big_integer().not_null())
+					.foreign_key(
+						ForeignKey::create()
+							.name("fkey-quarantine-approvals-actor")
+							.from(QuarantineApprovals::Table, QuarantineApprovals::Actor)
+							.to(Actors::Table, Actors::Internal)
+							.on_update(ForeignKeyAction::Cascade)
+							.on_delete(ForeignKeyAction::Cascade)
+					)
+					.col(ColumnDef::new(QuarantineApprovals::Sender).big_integer().not_null())
+					.foreign_key(
+						ForeignKey::create()
+							.name("fkey-quarantine-approvals-sender")
+							.from(QuarantineApprovals::Table, QuarantineApprovals::Sender)
+							.to(Actors::Table, Actors::Internal)
+							.on_update(ForeignKeyAction::Cascade)
+							.on_delete(ForeignKeyAction::Cascade)
+					)
+					.col(ColumnDef::new(QuarantineApprovals::Published).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.unique()
+					.name("index-quarantine-approvals-actor-sender")
+					.table(QuarantineApprovals::Table)
+					.col(QuarantineApprovals::Actor)
+					.col(QuarantineApprovals::Sender)
+					.to_owned()
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(QuarantineApprovals::Table).to_owned())
+			.await?;
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Notifications::Table)
+					.drop_column(Notifications::Quarantined)
+					.to_owned()
+			)
+			.await?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,170 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20240524_000001_create_actor_activity_object_tables::Actors, m20240524_000003_create_users_auth_and_config::Sessions};
+
+#[derive(DeriveIden)]
+pub enum OauthApps {
+	Table,
+	Internal,
+	ClientId,
+	ClientSecret,
+	Name,
+	Website,
+	RedirectUris,
+	Scopes,
+	Published,
+}
+
+#[derive(DeriveIden)]
+pub enum OauthCodes {
+	Table,
+	Internal,
+	Code,
+	App,
+	Actor,
+	RedirectUri,
+	Scopes,
+	Challenge,
+	Expires,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(OauthApps::Table)
+					.comment("third party clients registered to request oauth tokens on behalf of local users")
+					.col(
+						ColumnDef::new(OauthApps::Internal)
+							.big_integer()
+							.not_null()
+							.auto_increment()
+							.primary_key()
+					)
+					.col(ColumnDef::new(OauthApps::ClientId).string().not_null().unique_key())
+					.col(ColumnDef::new(OauthApps::ClientSecret).string().not_null())
+					.col(ColumnDef::new(OauthApps::Name).string().not_null())
+					.col(ColumnDef::new(OauthApps::Website).string().null())
+					.col(ColumnDef::new(OauthApps::RedirectUris).text().not_null())
+					.col(ColumnDef::new(OauthApps::Scopes).string().not_null())
+					.col(ColumnDef::new(OauthApps::Published).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.create_index(Index::create().unique().name("index-oauth-apps-client-id").table(OauthApps::Table).col(OauthApps::ClientId).to_owned())
+			.await?;
+
+		manager
+			.create_table(
+				Table::create()
+					.table(OauthCodes::Table)
+					.comment("short lived authorization codes, exchanged by apps for sessions")
+					.col(
+						ColumnDef::new(OauthCodes::Internal)
+							.big_integer()
+							.not_null()
+							.auto_increment()
+							.primary_key()
+					)
+					.col(ColumnDef::new(OauthCodes::Code).string().not_null().unique_key())
+					.col(ColumnDef::new(OauthCodes::App).big_integer().not_null())
+					.foreign_key(
+						ForeignKey::create()
+							.name("fkey-oauth-codes-app")
+							.from(OauthCodes::Table, OauthCodes::App)
+							.to(OauthApps::Table, OauthApps::Internal)
+							.on_update(ForeignKeyAction::Cascade)
+							.on_delete(ForeignKeyAction::Cascade)
+					)
+					.col(ColumnDef::new(OauthCodes::Actor).string().not_null())
+					.foreign_key(
+						ForeignKey::create()
+							.name("fkey-oauth-codes-actor")
+							.from(OauthCodes::Table, OauthCodes::Actor)
+							.to(Actors::Table, Actors::Id)
+							.on_update(ForeignKeyAction::Cascade)
+							.on_delete(ForeignKeyAction::Cascade)
+					)
+					.col(ColumnDef::new(OauthCodes::RedirectUri).string().not_null())
+					.col(ColumnDef::new(OauthCodes::Scopes).string().not_null())
+					.col(ColumnDef::new(OauthCodes::Challenge).string().null())
+					.col(ColumnDef::new(OauthCodes::Expires).timestamp_with_time_zone().not_null())
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.create_index(Index::create().unique().name("index-oauth-codes-code").table(OauthCodes::Table).col(OauthCodes::Code).to_owned())
+			.await?;
+
+		// NOTE sqlite can't add foreign keys nor multiple columns with one alter, so sessions.app is a
+		//      plain column and oauth apps get cleaned up manually with their sessions
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Sessions::Table)
+					.add_column(ColumnDef::new(Sessions::Scopes).string().null())
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Sessions::Table)
+					.add_column(ColumnDef::new(Sessions::App).big_integer().null())
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Sessions::Table)
+					.add_column(ColumnDef::new(Sessions::Refresh).string().null())
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.create_index(Index::create().name("index-sessions-refresh").table(Sessions::Table).col(Sessions::Refresh).to_owned())
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_index(Index::drop().name("index-sessions-refresh").table(Sessions::Table).to_owned())
+			.await?;
+
+		manager
+			.alter_table(Table::alter().table(Sessions::Table).drop_column(Sessions::Scopes).to_owned())
+			.await?;
+
+		manager
+			.alter_table(Table::alter().table(Sessions::Table).drop_column(Sessions::App).to_owned())
+			.await?;
+
+		manager
+			.alter_table(Table::alter().table(Sessions::Table).drop_column(Sessions::Refresh).to_owned())
+			.await?;
+
+		manager
+			.drop_table(Table::drop().table(OauthCodes::Table).to_owned())
+			.await?;
+
+		manager
+			.drop_table(Table::drop().table(OauthApps::Table).to_owned())
+			.await?;
+
+		Ok(())
+	}
+}
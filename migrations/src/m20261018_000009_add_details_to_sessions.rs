@@ -0,0 +1,85 @@
+use sea_orm_migration::{prelude::*, sea_orm::ConnectionTrait};
+
+use crate::m20240524_000003_create_users_auth_and_config::Sessions;
+
+// sessions opened before this migration still know their actor ap id
+const BACKFILL_ACTOR_INTERNAL: &str = r#"
+UPDATE "sessions"
+	SET "actor_internal" = (SELECT "internal" FROM "actors" WHERE "actors"."id" = "sessions"."actor");
+"#;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Sessions::Table)
+					.add_column(ColumnDef::new(Sessions::ActorInternal).big_integer().null())
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Sessions::Table)
+					.add_column(ColumnDef::new(Sessions::Created).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Sessions::Table)
+					.add_column(ColumnDef::new(Sessions::LastSeen).timestamp_with_time_zone().null())
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Sessions::Table)
+					.add_column(ColumnDef::new(Sessions::UserAgent).string().null())
+					.to_owned()
+			)
+			.await?;
+
+		manager.get_connection().execute_unprepared(BACKFILL_ACTOR_INTERNAL).await?;
+
+		manager
+			.create_index(Index::create().name("index-sessions-actor").table(Sessions::Table).col(Sessions::Actor).to_owned())
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_index(Index::drop().name("index-sessions-actor").table(Sessions::Table).to_owned())
+			.await?;
+
+		manager
+			.alter_table(Table::alter().table(Sessions::Table).drop_column(Sessions::ActorInternal).to_owned())
+			.await?;
+
+		manager
+			.alter_table(Table::alter().table(Sessions::Table).drop_column(Sessions::Created).to_owned())
+			.await?;
+
+		manager
+			.alter_table(Table::alter().table(Sessions::Table).drop_column(Sessions::LastSeen).to_owned())
+			.await?;
+
+		manager
+			.alter_table(Table::alter().table(Sessions::Table).drop_column(Sessions::UserAgent).to_owned())
+			.await?;
+
+		Ok(())
+	}
+}
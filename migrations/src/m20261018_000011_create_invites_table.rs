@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240524_000001_create_actor_activity_object_tables::Actors;
+
+#[derive(DeriveIden)]
+pub enum Invites {
+	Table,
+	Internal,
+	Code,
+	CreatedBy,
+	MaxUses,
+	Uses,
+	Expires,
+	Published,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(Invites::Table)
+					.comment("invite codes allowing registration even when it's closed")
+					.col(
+						ColumnDef::new(Invites::Internal)
+							.big_integer()
+							.not_null()
+							.auto_increment()
+							.primary_key()
+					)
+					.col(ColumnDef::new(Invites::Code).string().not_null().unique_key())
+					.col(ColumnDef::new(Invites::CreatedBy).string().null())
+					.foreign_key(
+						ForeignKey::create()
+							.name("fkey-invites-created-by")
+							.from(Invites::Table, Invites::CreatedBy)
+							.to(Actors::Table, Actors::Id)
+							.on_update(ForeignKeyAction::Cascade)
+							.on_delete(ForeignKeyAction::Cascade)
+					)
+					.col(ColumnDef::new(Invites::MaxUses).integer().null())
+					.col(ColumnDef::new(Invites::Uses).integer().not_null().default(0))
+					.col(ColumnDef::new(Invites::Expires).timestamp_with_time_zone().null())
+					.col(ColumnDef::new(Invites::Published).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.create_index(Index::create().unique().name("index-invites-code").table(Invites::Table).col(Invites::Code).to_owned())
+			.await?;
+
+		manager
+			.create_index(Index::create().name("index-invites-created-by").table(Invites::Table).col(Invites::CreatedBy).to_owned())
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(Invites::Table).to_owned())
+			.await?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,30 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240524_000001_create_actor_activity_object_tables::Actors;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Actors::Table)
+					.add_column(ColumnDef::new(Actors::Deleted).timestamp_with_time_zone().null())
+					.to_owned()
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(Table::alter().table(Actors::Table).drop_column(Actors::Deleted).to_owned())
+			.await?;
+
+		Ok(())
+	}
+}
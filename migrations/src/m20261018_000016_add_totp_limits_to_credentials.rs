@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240524_000003_create_users_auth_and_config::Credentials;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Credentials::Table)
+					.add_column(ColumnDef::new(Credentials::TotpStep).big_integer().null())
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Credentials::Table)
+					.add_column(ColumnDef::new(Credentials::TotpFailures).integer().not_null().default(0))
+					.to_owned()
+			)
+			.await?;
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Credentials::Table)
+					.add_column(ColumnDef::new(Credentials::TotpLockedUntil).timestamp_with_time_zone().null())
+					.to_owned()
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(Table::alter().table(Credentials::Table).drop_column(Credentials::TotpStep).to_owned())
+			.await?;
+
+		manager
+			.alter_table(Table::alter().table(Credentials::Table).drop_column(Credentials::TotpFailures).to_owned())
+			.await?;
+
+		manager
+			.alter_table(Table::alter().table(Credentials::Table).drop_column(Credentials::TotpLockedUntil).to_owned())
+			.await?;
+
+		Ok(())
+	}
+}
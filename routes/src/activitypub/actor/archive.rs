@@ -0,0 +1,145 @@
+use std::io::Write;
+
+use apb::LD;
+use axum::{body::Body, extract::{Path, State}, http::{header, StatusCode}, response::IntoResponse, Json};
+use sea_orm::TransactionTrait;
+
+use upub::Context;
+
+use crate::{ApiError, AuthIdentity};
+
+/// packs outbox, likes and profile as activitystreams json, followers and following as csv and
+/// uploaded files attached to own posts, all in one tar archive
+pub async fn export(
+	State(ctx): State<Context>,
+	Path(id): Path<String>,
+	AuthIdentity(auth): AuthIdentity,
+) -> crate::ApiResult<impl IntoResponse> {
+	let (uid, _) = super::sessions::owner(&ctx, &id, &auth)?;
+
+	// read everything from a consistent snapshot, even if user keeps posting meanwhile
+	let tx = ctx.db().begin().await?;
+	let archive = upub::export::archive(&ctx, &uid, &tx)
+		.await?
+		.ok_or_else(ApiError::not_found)?;
+	tx.commit().await?;
+
+	// tar is built on a blocking thread and streamed out as it grows, media can be large
+	let (tx, rx) = tokio::sync::mpsc::channel(8);
+	let files_path = ctx.cfg().files.path.clone();
+	let who = uid.clone();
+	tokio::task::spawn_blocking(move || {
+		if let Err(e) = pack(archive, &files_path, &who, ChunkWriter(tx.clone())) {
+			tracing::warn!("export of {who} interrupted: {e}");
+			// fail the body rather than leaving a truncated archive that looks complete
+			let _ = tx.blocking_send(Err(e));
+		}
+	});
+	let body = futures::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|x| (x, rx)) });
+
+	let username = id.trim_start_matches('@');
+	Ok((
+		[
+			(header::CONTENT_TYPE, "application/x-tar".to_string()),
+			(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{username}-{}.tar\"", chrono::Utc::now().format("%Y%m%d"))),
+		],
+		Body::from_stream(body),
+	))
+}
+
+fn pack(archive: upub::export::Archive, files_path: &str, uid: &str, out: ChunkWriter) -> std::io::Result<()> {
+	let mut tar = tar::Builder::new(std::io::BufWriter::with_capacity(CHUNK_SIZE, out));
+	append(&mut tar, "actor.json", serde_json::to_vec_pretty(&archive.actor.ld_context()).unwrap_or_default())?;
+	append(&mut tar, "outbox.json", serde_json::to_vec_pretty(&archive.outbox.ld_context()).unwrap_or_default())?;
+	append(&mut tar, "likes.json", serde_json::to_vec_pretty(&archive.likes.ld_context()).unwrap_or_default())?;
+	append(&mut tar, "following_accounts.csv", archive.following.into_bytes())?;
+	append(&mut tar, "followers.csv", archive.followers.into_bytes())?;
+
+	match std::fs::canonicalize(files_path) {
+		Err(e) if !archive.files.is_empty() => tracing::warn!("skipping media from export of {uid}: {e}"),
+		Err(_) => {},
+		Ok(base) => for file in archive.files {
+			match open_upload(&base, &file) {
+				Ok((data, size)) => {
+					let mut header = entry(size);
+					tar.append_data(&mut header, format!("media/{file}"), data)?;
+				},
+				Err(e) => tracing::warn!("skipping file {file} from export of {uid}: {e}"),
+			}
+		},
+	}
+
+	tar.into_inner()?.flush()
+}
+
+/// uploads are named after their sha256 digest: anything else, or anything resolving outside of
+/// `files.path`, is refused
+fn open_upload(base: &std::path::Path, name: &str) -> std::io::Result<(std::fs::File, u64)> {
+	if !upub::export::is_upload(name) {
+		return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "not an upload name"));
+	}
+	let path = std::fs::canonicalize(base.join(name))?;
+	if !path.starts_with(base) {
+		return Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "outside of files path"));
+	}
+	let file = std::fs::File::open(path)?;
+	let size = file.metadata()?.len();
+	Ok((file, size))
+}
+
+fn entry(size: u64) -> tar::Header {
+	let mut header = tar::Header::new_gnu();
+	header.set_size(size);
+	header.set_mode(0o644);
+	header.set_mtime(chrono::Utc::now().timestamp() as u64);
+	header
+}
+
+fn append(tar: &mut tar::Builder<impl Write>, path: &str, data: Vec<u8>) -> std::io::Result<()> {
+	let mut header = entry(data.len() as u64);
+	tar.append_data(&mut header, path, data.as_slice())
+}
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// hands whatever tar writes over to the response body
+struct ChunkWriter(tokio::sync::mpsc::Sender<std::io::Result<Vec<u8>>>);
+
+impl Write for ChunkWriter {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		// fails once client went away, which stops packing too
+		self.0.blocking_send(Ok(buf.to_vec()))
+			.map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		Ok(())
+	}
+}
+
+/// takes a mastodon `following_accounts.csv` as body. accounts are resolved in background and
+/// followed one at a time: enqueued follows show up under /actors/{id}/scheduled until sent
+pub async fn import_following(
+	State(ctx): State<Context>,
+	Path(id): Path<String>,
+	AuthIdentity(auth): AuthIdentity,
+	body: String,
+) -> crate::ApiResult<(StatusCode, Json<usize>)> {
+	let (uid, _) = super::sessions::owner(&ctx, &id, &auth)?;
+
+	let accounts = upub::import::accounts(&body);
+	let count = accounts.len();
+	if count == 0 {
+		return Err(ApiError::bad_request());
+	}
+
+	tokio::spawn(async move {
+		match upub::import::follows(&ctx, &uid, accounts).await {
+			Ok(n) => tracing::info!("{uid} imported {n} follows"),
+			Err(e) => tracing::error!("failed importing follows for {uid}: {e}"),
+		}
+	});
+
+	Ok((StatusCode::ACCEPTED, Json(count)))
+}
@@ -87,7 +87,7 @@ pub async fn page<const OUTGOING: bool>(
 	if hidden {
 		match auth {
 			Identity::Anonymous => return Err(ApiError::unauthorized()),
-			Identity::Local { id, internal } => {
+			Identity::Local { id, internal, .. } => {
 				if id != ctx.uid(&id) {
 					filter = filter.add(if OUTGOING { Following } else { Follower }.eq(internal));
 				}
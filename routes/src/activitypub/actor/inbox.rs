@@ -27,7 +27,7 @@ pub async fn page(
 	AuthIdentity(auth): AuthIdentity,
 	Query(page): Query<Pagination>,
 ) -> crate::ApiResult<JsonLD<serde_json::Value>> {
-	let Identity::Local { id: uid, internal } = &auth else {
+	let Identity::Local { id: uid, internal, .. } = &auth else {
 		// local inbox is only for local users
 		return Err(crate::ApiError::forbidden());
 	};
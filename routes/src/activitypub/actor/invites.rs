@@ -0,0 +1,105 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+
+use upub::{model, Context};
+
+use crate::{ApiError, AuthIdentity, Identity};
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct InviteInfo {
+	code: String,
+	link: String,
+	created_by: Option<String>,
+	max_uses: Option<i32>,
+	uses: i32,
+	expires: Option<chrono::DateTime<chrono::Utc>>,
+	published: chrono::DateTime<chrono::Utc>,
+	usable: bool,
+}
+
+impl InviteInfo {
+	pub fn new(ctx: &Context, invite: model::invite::Model) -> Self {
+		InviteInfo {
+			link: upub::invites::link(ctx, &invite.code),
+			usable: invite.usable(),
+			code: invite.code,
+			created_by: invite.created_by,
+			max_uses: invite.max_uses,
+			uses: invite.uses,
+			expires: invite.expires,
+			published: invite.published,
+		}
+	}
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct InviteForm {
+	/// unlimited if missing
+	max_uses: Option<i32>,
+	/// never expires if missing
+	expires_in_hours: Option<i64>,
+}
+
+fn inviter(ctx: &Context, id: &str, auth: &Identity) -> crate::ApiResult<String> {
+	let Identity::Local { id: uid, .. } = auth else {
+		return Err(ApiError::unauthorized());
+	};
+	if uid != &ctx.uid(id) {
+		return Err(ApiError::forbidden());
+	}
+	Ok(uid.clone())
+}
+
+pub async fn get(
+	State(ctx): State<Context>,
+	Path(id): Path<String>,
+	AuthIdentity(auth): AuthIdentity,
+) -> crate::ApiResult<Json<Vec<InviteInfo>>> {
+	let uid = inviter(&ctx, &id, &auth)?;
+
+	let invites = upub::invites::list(ctx.db(), Some(&uid))
+		.await?
+		.into_iter()
+		.map(|x| InviteInfo::new(&ctx, x))
+		.collect();
+
+	Ok(Json(invites))
+}
+
+pub async fn create(
+	State(ctx): State<Context>,
+	Path(id): Path<String>,
+	AuthIdentity(auth): AuthIdentity,
+	Json(form): Json<InviteForm>,
+) -> crate::ApiResult<(StatusCode, Json<InviteInfo>)> {
+	let uid = inviter(&ctx, &id, &auth)?;
+	if !ctx.cfg().security.allow_user_invites && !auth.is_admin(&ctx) {
+		return Err(ApiError::forbidden());
+	}
+
+	if form.max_uses.is_some_and(|x| x <= 0) || form.expires_in_hours.is_some_and(|x| x <= 0) {
+		return Err(ApiError::bad_request());
+	}
+
+	let expires = match form.expires_in_hours {
+		None => None,
+		Some(h) => Some(upub::invites::expiry(h).ok_or_else(ApiError::unprocessable)?),
+	};
+	let invite = upub::invites::create(ctx.db(), Some(uid.clone()), form.max_uses, expires).await?;
+	tracing::info!("{uid} created invite {}", invite.code);
+
+	Ok((StatusCode::CREATED, Json(InviteInfo::new(&ctx, invite))))
+}
+
+pub async fn revoke(
+	State(ctx): State<Context>,
+	Path((id, code)): Path<(String, String)>,
+	AuthIdentity(auth): AuthIdentity,
+) -> crate::ApiResult<StatusCode> {
+	let uid = inviter(&ctx, &id, &auth)?;
+
+	if !upub::invites::revoke(ctx.db(), &code, Some(&uid)).await? {
+		return Err(ApiError::not_found());
+	}
+
+	Ok(StatusCode::OK)
+}
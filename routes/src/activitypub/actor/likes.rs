@@ -25,7 +25,7 @@ pub async fn page(
 		.await?
 		.ok_or_else(ApiError::not_found)?;
 
-	if !auth.is(&uid) && !config.map_or(true, |x| x.show_liked_objects) {
+	if !auth.is(&uid) && !config.is_none_or(|x| x.show_liked_objects) {
 		return Err(ApiError::forbidden());
 	}
 
@@ -3,6 +3,11 @@ pub mod outbox;
 pub mod likes;
 pub mod following;
 pub mod notifications;
+pub mod scheduled;
+pub mod sessions;
+pub mod invites;
+pub mod archive;
+pub mod tokens;
 // pub mod audience;
 
 use axum::extract::{Path, Query, State};
@@ -54,10 +59,13 @@ pub async fn view(
 		.find_also_related(model::config::Entity)
 		.one(ctx.db()).await?
 	{
+		// deleted local user, kept as tombstone
+		Some((user_model, _)) if user_model.deleted.is_some() => Err(ApiError::Status(axum::http::StatusCode::GONE)),
 		// local user
 		Some((user_model, Some(cfg))) => {
 			let (followers, following) = (user_model.followers_count, user_model.following_count);
-			let mut user = ctx.ap(user_model)
+			let mut user = upub::keys::publish(ctx.db(), ctx.ap(user_model))
+				.await?
 				.set_following_me(following_me)
 				.set_followed_by_me(followed_by_me)
 				.set_manually_approves_followers(Some(!cfg.accept_follow_requests));
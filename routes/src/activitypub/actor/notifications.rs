@@ -1,4 +1,4 @@
-use axum::extract::{Path, Query, State};
+use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
 use sea_orm::{PaginatorTrait, QuerySelect};
 
 use upub::{selector::RichNotification, Context};
@@ -10,7 +10,7 @@ pub async fn get(
 	Path(id): Path<String>,
 	AuthIdentity(auth): AuthIdentity,
 ) -> crate::ApiResult<JsonLD<serde_json::Value>> {
-	let Identity::Local { id: uid, internal } = &auth else {
+	let Identity::Local { id: uid, internal, .. } = &auth else {
 		// notifications are only for local users
 		return Err(crate::ApiError::forbidden());
 	};
@@ -31,7 +31,7 @@ pub async fn page(
 	AuthIdentity(auth): AuthIdentity,
 	Query(page): Query<Pagination>,
 ) -> crate::ApiResult<JsonLD<serde_json::Value>> {
-	let Identity::Local { id: uid, internal } = &auth else {
+	let Identity::Local { id: uid, internal, .. } = &auth else {
 		// notifications are only for local users
 		return Err(crate::ApiError::forbidden());
 	};
@@ -54,3 +54,84 @@ pub async fn page(
 	crate::builders::collection_page(&upub::url!(ctx, "/actors/{id}/notifications/page"), page, apb::Node::array(activities))
 
 }
+
+pub async fn quarantine(
+	State(ctx): State<Context>,
+	Path(id): Path<String>,
+	AuthIdentity(auth): AuthIdentity,
+) -> crate::ApiResult<JsonLD<serde_json::Value>> {
+	let Identity::Local { id: uid, internal, .. } = &auth else {
+		return Err(crate::ApiError::forbidden());
+	};
+	if uid != &ctx.uid(&id) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	let count = upub::Query::quarantined_notifications(*internal)
+		.count(ctx.db())
+		.await?;
+
+	crate::builders::collection(upub::url!(ctx, "/actors/{id}/notifications/quarantine"), Some(count))
+}
+
+pub async fn quarantine_page(
+	State(ctx): State<Context>,
+	Path(id): Path<String>,
+	AuthIdentity(auth): AuthIdentity,
+	Query(page): Query<Pagination>,
+) -> crate::ApiResult<JsonLD<serde_json::Value>> {
+	let Identity::Local { id: uid, internal, .. } = &auth else {
+		return Err(crate::ApiError::forbidden());
+	};
+	if uid != &ctx.uid(&id) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	let (limit, offset) = page.pagination();
+
+	let activities = upub::Query::quarantined_notifications(*internal)
+		.limit(limit)
+		.offset(offset)
+		.into_model::<RichNotification>()
+		.all(ctx.db())
+		.await?
+		.into_iter()
+		.map(|x| ctx.ap(x))
+		.collect();
+
+	crate::builders::collection_page(&upub::url!(ctx, "/actors/{id}/notifications/quarantine/page"), page, apb::Node::array(activities))
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ApproveRequest {
+	/// id of remote actor to accept mentions from
+	pub actor: String,
+}
+
+pub async fn approve(
+	State(ctx): State<Context>,
+	Path(id): Path<String>,
+	AuthIdentity(auth): AuthIdentity,
+	Json(req): Json<ApproveRequest>,
+) -> crate::ApiResult<StatusCode> {
+	let Identity::Local { id: uid, internal, .. } = &auth else {
+		return Err(crate::ApiError::forbidden());
+	};
+	if uid != &ctx.uid(&id) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	// only senders actually waiting in quarantine: never fetch whatever the client asks for
+	let sender = upub::model::actor::Entity::find_by_ap_id(&req.actor)
+		.one(ctx.db())
+		.await?
+		.ok_or_else(crate::ApiError::not_found)?;
+	if !upub::quarantine::is_held(ctx.db(), *internal, &sender.id).await? {
+		return Err(crate::ApiError::not_found());
+	}
+
+	let released = upub::quarantine::approve(ctx.db(), *internal, &sender).await?;
+	tracing::info!("{uid} approved {}, releasing {released} notifications", sender.id);
+
+	Ok(StatusCode::OK)
+}
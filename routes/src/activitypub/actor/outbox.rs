@@ -1,3 +1,4 @@
+use apb::{Activity, Object};
 use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
 use sea_orm::{ActiveValue::{NotSet, Set}, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
 
@@ -64,25 +65,53 @@ pub async fn post(
 	match auth {
 		Identity::Anonymous => Err(StatusCode::UNAUTHORIZED.into()),
 		Identity::Remote { .. } => Err(StatusCode::NOT_IMPLEMENTED.into()),
-		Identity::Local { id: uid, .. } => {
+		Identity::Local { id: uid, scopes, .. } => {
 			if ctx.uid(&id) != uid {
 				return Err(crate::ApiError::forbidden());
 			}
 
+			let activity_type = activity.get("type").and_then(|x| x.as_str()).unwrap_or_default();
+			let allowed = if activity_type == "Undo" {
+				// look up what's being undone, rather than trusting an embedded type
+				let undone = match activity.object().id() {
+					Err(_) => None,
+					Ok(undone) => model::activity::Entity::find_by_ap_id(&undone)
+						.filter(model::activity::Column::Actor.eq(&uid))
+						.one(ctx.db())
+						.await?,
+				};
+				match undone {
+					Some(undone) => scopes.allows_undo(undone.activity_type),
+					None => scopes.write,
+				}
+			} else {
+				scopes.allows_activity(activity_type)
+			};
+			if !allowed {
+				return Err(crate::ApiError::forbidden());
+			}
+
 			tracing::debug!("enqueuing new local activity: {}", serde_json::to_string(&activity).unwrap_or_default());
 			let aid = ctx.aid(&Context::new_id());
 
+			// activities published in the future are scheduled: job stays in queue, visible only to its
+			// author under /actors/{id}/scheduled, until it's time to process and deliver it
+			let now = chrono::Utc::now();
+			let when = activity.published().ok().filter(|x| *x > now).unwrap_or(now);
+
 			let job = model::job::ActiveModel {
 				internal: NotSet,
 				activity: Set(aid.clone()),
 				job_type: Set(model::job::JobType::Outbound),
 				actor: Set(uid.clone()),
 				target: Set(None),
-				published: Set(chrono::Utc::now()),
-				not_before: Set(chrono::Utc::now()),
+				published: Set(when),
+				not_before: Set(when),
 				attempt: Set(0),
 				payload: Set(Some(activity)),
 				error: Set(None),
+				locked_until: Set(None),
+				domain: Set(None),
 			};
 
 			model::job::Entity::insert(job).exec(ctx.db()).await?;
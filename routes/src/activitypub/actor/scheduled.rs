@@ -0,0 +1,139 @@
+use apb::{BaseMut, Object, ObjectMut};
+use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
+use sea_orm::{sea_query::Expr, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Select};
+
+use upub::{model, Context};
+
+use crate::{activitypub::Pagination, builders::JsonLD, AuthIdentity, Identity};
+
+// scheduled activities are just outbound jobs which haven't been attempted yet and aren't due
+fn scheduled(uid: &str) -> Select<model::job::Entity> {
+	model::job::Entity::find()
+		.filter(model::job::Column::JobType.eq(model::job::JobType::Outbound))
+		.filter(model::job::Column::Actor.eq(uid))
+		.filter(model::job::Column::Attempt.eq(0))
+		.filter(model::job::Column::NotBefore.gt(chrono::Utc::now()))
+}
+
+pub async fn get(
+	State(ctx): State<Context>,
+	Path(id): Path<String>,
+	AuthIdentity(auth): AuthIdentity,
+) -> crate::ApiResult<JsonLD<serde_json::Value>> {
+	let Identity::Local { id: uid, .. } = &auth else {
+		// scheduled activities are only visible to their local author
+		return Err(crate::ApiError::forbidden());
+	};
+	if uid != &ctx.uid(&id) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	let count = scheduled(uid)
+		.count(ctx.db())
+		.await?;
+
+	crate::builders::collection(upub::url!(ctx, "/actors/{id}/scheduled"), Some(count))
+}
+
+pub async fn page(
+	State(ctx): State<Context>,
+	Path(id): Path<String>,
+	AuthIdentity(auth): AuthIdentity,
+	Query(page): Query<Pagination>,
+) -> crate::ApiResult<JsonLD<serde_json::Value>> {
+	let Identity::Local { id: uid, .. } = &auth else {
+		// scheduled activities are only visible to their local author
+		return Err(crate::ApiError::forbidden());
+	};
+	if uid != &ctx.uid(&id) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	let (limit, offset) = page.pagination();
+
+	let activities = scheduled(uid)
+		.order_by_asc(model::job::Column::NotBefore)
+		.limit(limit)
+		.offset(offset)
+		.all(ctx.db())
+		.await?
+		.into_iter()
+		.filter_map(|job| Some(
+			job.payload?
+				.set_id(Some(job.activity))
+				.set_published(Some(job.not_before))
+		))
+		.collect();
+
+	crate::builders::collection_page(&upub::url!(ctx, "/actors/{id}/scheduled/page"), page, apb::Node::array(activities))
+}
+
+pub async fn edit(
+	State(ctx): State<Context>,
+	Path((id, activity_id)): Path<(String, String)>,
+	AuthIdentity(auth): AuthIdentity,
+	Json(activity): Json<serde_json::Value>,
+) -> crate::ApiResult<StatusCode> {
+	let Identity::Local { id: uid, .. } = &auth else {
+		return Err(crate::ApiError::forbidden());
+	};
+	if uid != &ctx.uid(&id) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	// leaving out published keeps previous schedule, a date in the past publishes right away
+	let mut update = model::job::Entity::update_many()
+		.col_expr(model::job::Column::Payload, Expr::value(activity.clone()));
+
+	if let Ok(when) = activity.published() {
+		update = update
+			.col_expr(model::job::Column::NotBefore, Expr::value(when.max(chrono::Utc::now())))
+			.col_expr(model::job::Column::Published, Expr::value(when.max(chrono::Utc::now())));
+	}
+
+	let res = update
+		.filter(model::job::Column::Activity.eq(ctx.aid(&activity_id)))
+		.filter(model::job::Column::JobType.eq(model::job::JobType::Outbound))
+		.filter(model::job::Column::Actor.eq(uid))
+		.filter(model::job::Column::Attempt.eq(0))
+		.filter(model::job::Column::NotBefore.gt(chrono::Utc::now()))
+		.exec(ctx.db())
+		.await?;
+
+	if res.rows_affected < 1 {
+		// either never existed or it's already being processed
+		return Err(crate::ApiError::not_found());
+	}
+
+	ctx.wake_workers(); // in case it got moved to now
+
+	Ok(StatusCode::OK)
+}
+
+pub async fn cancel(
+	State(ctx): State<Context>,
+	Path((id, activity_id)): Path<(String, String)>,
+	AuthIdentity(auth): AuthIdentity,
+) -> crate::ApiResult<StatusCode> {
+	let Identity::Local { id: uid, .. } = &auth else {
+		return Err(crate::ApiError::forbidden());
+	};
+	if uid != &ctx.uid(&id) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	let res = model::job::Entity::delete_many()
+		.filter(model::job::Column::Activity.eq(ctx.aid(&activity_id)))
+		.filter(model::job::Column::JobType.eq(model::job::JobType::Outbound))
+		.filter(model::job::Column::Actor.eq(uid))
+		.filter(model::job::Column::Attempt.eq(0))
+		.filter(model::job::Column::NotBefore.gt(chrono::Utc::now()))
+		.exec(ctx.db())
+		.await?;
+
+	if res.rows_affected < 1 {
+		return Err(crate::ApiError::not_found());
+	}
+
+	Ok(StatusCode::OK)
+}
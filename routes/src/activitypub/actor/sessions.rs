@@ -0,0 +1,104 @@
+use axum::{extract::{Path, State}, Json};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+use upub::{model, oauth::Scopes, Context};
+
+use crate::{ApiError, AuthIdentity, Identity};
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SessionInfo {
+	id: i64,
+	created: chrono::DateTime<chrono::Utc>,
+	last_seen: Option<chrono::DateTime<chrono::Utc>>,
+	expires: chrono::DateTime<chrono::Utc>,
+	user_agent: Option<String>,
+	/// oauth app which opened this session, if any
+	app: Option<String>,
+	scopes: String,
+	/// the session used to make this request
+	current: bool,
+}
+
+// managing logins is reserved to the user themselves: oauth apps can't see nor revoke sessions
+pub(crate) fn owner(ctx: &Context, id: &str, auth: &Identity) -> crate::ApiResult<(String, i64)> {
+	let Identity::Local { id: uid, session, login, .. } = auth else {
+		return Err(ApiError::unauthorized());
+	};
+	if uid != &ctx.uid(id) || !login {
+		return Err(ApiError::forbidden());
+	}
+	Ok((uid.clone(), *session))
+}
+
+pub async fn get(
+	State(ctx): State<Context>,
+	Path(id): Path<String>,
+	AuthIdentity(auth): AuthIdentity,
+) -> crate::ApiResult<Json<Vec<SessionInfo>>> {
+	let (uid, current) = owner(&ctx, &id, &auth)?;
+
+	let sessions = model::session::Entity::find()
+		.find_also_related(model::oauth_app::Entity)
+		.filter(model::session::Column::Actor.eq(&uid))
+		.filter(model::session::Column::Name.is_null()) // api tokens are managed under /tokens
+		.filter(model::session::Column::Expires.gt(chrono::Utc::now()))
+		.order_by_desc(model::session::Column::Created)
+		.all(ctx.db())
+		.await?
+		.into_iter()
+		.map(|(session, app)| SessionInfo {
+			id: session.internal,
+			created: session.created,
+			last_seen: session.last_seen,
+			expires: session.expires,
+			user_agent: session.user_agent,
+			app: app.map(|x| x.name),
+			scopes: session.scopes.unwrap_or_else(|| Scopes::all().to_string()),
+			current: session.internal == current,
+		})
+		.collect();
+
+	Ok(Json(sessions))
+}
+
+pub async fn revoke(
+	State(ctx): State<Context>,
+	Path((id, session)): Path<(String, i64)>,
+	AuthIdentity(auth): AuthIdentity,
+) -> crate::ApiResult<()> {
+	let (uid, _) = owner(&ctx, &id, &auth)?;
+
+	let res = model::session::Entity::delete_many()
+		.filter(model::session::Column::Internal.eq(session))
+		.filter(model::session::Column::Actor.eq(&uid))
+		.filter(model::session::Column::Name.is_null())
+		.exec(ctx.db())
+		.await?;
+
+	if res.rows_affected == 0 {
+		return Err(ApiError::not_found());
+	}
+
+	Ok(())
+}
+
+/// log out everywhere else: every session but the one making this request is revoked, api tokens
+/// are kept
+pub async fn revoke_all(
+	State(ctx): State<Context>,
+	Path(id): Path<String>,
+	AuthIdentity(auth): AuthIdentity,
+) -> crate::ApiResult<Json<u64>> {
+	let (uid, current) = owner(&ctx, &id, &auth)?;
+
+	let res = model::session::Entity::delete_many()
+		.filter(model::session::Column::Actor.eq(&uid))
+		.filter(model::session::Column::Name.is_null())
+		.filter(model::session::Column::Internal.ne(current))
+		.exec(ctx.db())
+		.await?;
+
+	tracing::info!("{uid} revoked {} other sessions", res.rows_affected);
+
+	Ok(Json(res.rows_affected))
+}
@@ -0,0 +1,107 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+
+use upub::{model, oauth::Scopes, Context};
+
+use crate::{ApiError, AuthIdentity};
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TokenInfo {
+	id: i64,
+	name: String,
+	scopes: String,
+	created: chrono::DateTime<chrono::Utc>,
+	last_seen: Option<chrono::DateTime<chrono::Utc>>,
+	expires: chrono::DateTime<chrono::Utc>,
+	/// only returned once, right after creation
+	#[serde(skip_serializing_if = "Option::is_none")]
+	token: Option<String>,
+}
+
+impl TokenInfo {
+	pub fn new(session: model::session::Model, with_secret: bool) -> Self {
+		TokenInfo {
+			id: session.internal,
+			name: session.name.unwrap_or_default(),
+			scopes: session.scopes.unwrap_or_else(|| Scopes::all().to_string()),
+			created: session.created,
+			last_seen: session.last_seen,
+			expires: session.expires,
+			token: if with_secret { Some(session.secret) } else { None },
+		}
+	}
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct TokenForm {
+	name: String,
+	/// same syntax as oauth scopes, read only if missing
+	scopes: Option<String>,
+	/// never expires if missing, can't exceed [upub::tokens::NEVER_EXPIRES_DAYS]
+	expires_in_days: Option<i64>,
+}
+
+// like sessions, api tokens can be managed only from a full session: tokens can't mint tokens
+pub async fn get(
+	State(ctx): State<Context>,
+	Path(id): Path<String>,
+	AuthIdentity(auth): AuthIdentity,
+) -> crate::ApiResult<Json<Vec<TokenInfo>>> {
+	let (uid, _) = super::sessions::owner(&ctx, &id, &auth)?;
+
+	let tokens = upub::tokens::list(ctx.db(), &uid)
+		.await?
+		.into_iter()
+		.map(|x| TokenInfo::new(x, false))
+		.collect();
+
+	Ok(Json(tokens))
+}
+
+pub async fn create(
+	State(ctx): State<Context>,
+	Path(id): Path<String>,
+	AuthIdentity(auth): AuthIdentity,
+	Json(form): Json<TokenForm>,
+) -> crate::ApiResult<(StatusCode, Json<TokenInfo>)> {
+	let (uid, _) = super::sessions::owner(&ctx, &id, &auth)?;
+
+	let name = form.name.trim().to_string();
+	if name.is_empty() {
+		return Err(ApiError::unprocessable());
+	}
+
+	let scopes = match form.scopes.as_deref() {
+		None => Scopes { read: true, ..Default::default() },
+		Some(x) => x.parse::<Scopes>().map_err(|_| ApiError::unprocessable())?,
+	};
+	if scopes.is_empty() {
+		return Err(ApiError::unprocessable());
+	}
+
+	let expires = match form.expires_in_days {
+		None => None,
+		Some(d) => Some(upub::tokens::expiry(d).ok_or_else(ApiError::unprocessable)?),
+	};
+
+	let token = upub::tokens::create(ctx.db(), &uid, name, scopes, expires)
+		.await?
+		.ok_or_else(ApiError::not_found)?;
+
+	tracing::info!("{uid} created api token '{}' with scopes '{scopes}'", token.name.as_deref().unwrap_or_default());
+
+	Ok((StatusCode::CREATED, Json(TokenInfo::new(token, true))))
+}
+
+pub async fn revoke(
+	State(ctx): State<Context>,
+	Path((id, token)): Path<(String, i64)>,
+	AuthIdentity(auth): AuthIdentity,
+) -> crate::ApiResult<()> {
+	let (uid, _) = super::sessions::owner(&ctx, &id, &auth)?;
+
+	if !upub::tokens::revoke(ctx.db(), &uid, token).await? {
+		return Err(ApiError::not_found());
+	}
+
+	Ok(())
+}
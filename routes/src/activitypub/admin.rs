@@ -0,0 +1,221 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use sea_orm::{EntityTrait, QueryOrder};
+use upub::{model::{self, domain_block::Severity}, Context};
+
+use crate::AuthIdentity;
+
+pub async fn allowlist(
+	State(ctx): State<Context>,
+	AuthIdentity(auth): AuthIdentity,
+) -> crate::ApiResult<Json<Vec<String>>> {
+	if !auth.is_admin(&ctx) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	let mut domains = upub::allowlist::list(ctx.db()).await?;
+	domains.extend(ctx.cfg().federation.allowlist.iter().cloned());
+
+	Ok(Json(domains.into_iter().collect()))
+}
+
+pub async fn allow(
+	State(ctx): State<Context>,
+	AuthIdentity(auth): AuthIdentity,
+	Path(domain): Path<String>,
+) -> crate::ApiResult<StatusCode> {
+	if !auth.is_admin(&ctx) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	upub::allowlist::allow(ctx.db(), &domain).await?;
+	tracing::info!("allowed federation with {domain}");
+
+	Ok(StatusCode::CREATED)
+}
+
+pub async fn disallow(
+	State(ctx): State<Context>,
+	AuthIdentity(auth): AuthIdentity,
+	Path(domain): Path<String>,
+) -> crate::ApiResult<StatusCode> {
+	if !auth.is_admin(&ctx) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	// configured domains can't be removed at runtime
+	if !upub::allowlist::disallow(ctx.db(), &domain).await? {
+		return Err(crate::ApiError::not_found());
+	}
+	tracing::info!("disallowed federation with {domain}");
+
+	Ok(StatusCode::OK)
+}
+
+pub async fn blocks(
+	State(ctx): State<Context>,
+	AuthIdentity(auth): AuthIdentity,
+) -> crate::ApiResult<Json<Vec<serde_json::Value>>> {
+	if !auth.is_admin(&ctx) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	let blocks = model::domain_block::Entity::find()
+		.order_by_asc(model::domain_block::Column::Domain)
+		.all(ctx.db())
+		.await?
+		.into_iter()
+		.map(|block| serde_json::json!({
+			"domain": block.domain,
+			"severity": block.severity,
+			"public_reason": block.public_reason,
+			"private_reason": block.private_reason,
+			"published": block.published,
+		}))
+		.collect();
+
+	Ok(Json(blocks))
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct BlockRequest {
+	pub severity: Severity,
+	pub public_reason: Option<String>,
+	pub private_reason: Option<String>,
+}
+
+pub async fn block(
+	State(ctx): State<Context>,
+	AuthIdentity(auth): AuthIdentity,
+	Path(domain): Path<String>,
+	Json(req): Json<BlockRequest>,
+) -> crate::ApiResult<StatusCode> {
+	if !auth.is_admin(&ctx) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	upub::blocks::block(ctx.db(), &domain, req.severity, req.public_reason, req.private_reason).await?;
+	tracing::info!("blocked {domain} with severity {}", req.severity.as_str());
+
+	Ok(StatusCode::CREATED)
+}
+
+pub async fn unblock(
+	State(ctx): State<Context>,
+	AuthIdentity(auth): AuthIdentity,
+	Path(domain): Path<String>,
+) -> crate::ApiResult<StatusCode> {
+	if !auth.is_admin(&ctx) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	if !upub::blocks::unblock(ctx.db(), &domain).await? {
+		return Err(crate::ApiError::not_found());
+	}
+	tracing::info!("unblocked {domain}");
+
+	Ok(StatusCode::OK)
+}
+
+/// blocked instances with their public reasons, if enabled in config
+pub async fn blocklist(
+	State(ctx): State<Context>,
+) -> crate::ApiResult<Json<Vec<serde_json::Value>>> {
+	if !ctx.cfg().federation.public_blocklist {
+		return Err(crate::ApiError::not_found());
+	}
+
+	let blocks = model::domain_block::Entity::find()
+		.order_by_asc(model::domain_block::Column::Domain)
+		.all(ctx.db())
+		.await?
+		.into_iter()
+		.map(|block| serde_json::json!({
+			"domain": block.domain,
+			"severity": block.severity,
+			"reason": block.public_reason,
+			"published": block.published,
+		}))
+		.collect();
+
+	Ok(Json(blocks))
+}
+
+/// invites created by anyone, including the ones from cli
+pub async fn invites(
+	State(ctx): State<Context>,
+	AuthIdentity(auth): AuthIdentity,
+) -> crate::ApiResult<Json<Vec<crate::activitypub::actor::invites::InviteInfo>>> {
+	if !auth.is_admin(&ctx) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	let invites = upub::invites::list(ctx.db(), None)
+		.await?
+		.into_iter()
+		.map(|x| crate::activitypub::actor::invites::InviteInfo::new(&ctx, x))
+		.collect();
+
+	Ok(Json(invites))
+}
+
+pub async fn revoke_invite(
+	State(ctx): State<Context>,
+	AuthIdentity(auth): AuthIdentity,
+	Path(code): Path<String>,
+) -> crate::ApiResult<StatusCode> {
+	if !auth.is_admin(&ctx) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	if !upub::invites::revoke(ctx.db(), &code, None).await? {
+		return Err(crate::ApiError::not_found());
+	}
+	tracing::info!("revoked invite {code}");
+
+	Ok(StatusCode::OK)
+}
+
+pub async fn registrations(
+	State(ctx): State<Context>,
+	AuthIdentity(auth): AuthIdentity,
+) -> crate::ApiResult<Json<Vec<upub::registrations::Pending>>> {
+	if !auth.is_admin(&ctx) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	Ok(Json(upub::registrations::pending(ctx.db()).await?))
+}
+
+pub async fn approve_registration(
+	State(ctx): State<Context>,
+	AuthIdentity(auth): AuthIdentity,
+	Path(username): Path<String>,
+) -> crate::ApiResult<StatusCode> {
+	if !auth.is_admin(&ctx) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	if !upub::registrations::approve(ctx.db(), &ctx.uid(&username)).await? {
+		return Err(crate::ApiError::not_found());
+	}
+	tracing::info!("approved registration of {username}");
+
+	Ok(StatusCode::OK)
+}
+
+pub async fn reject_registration(
+	State(ctx): State<Context>,
+	AuthIdentity(auth): AuthIdentity,
+	Path(username): Path<String>,
+) -> crate::ApiResult<StatusCode> {
+	if !auth.is_admin(&ctx) {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	if !upub::registrations::reject(ctx.db(), &ctx.uid(&username)).await? {
+		return Err(crate::ApiError::not_found());
+	}
+	tracing::info!("rejected registration of {username}");
+
+	Ok(StatusCode::OK)
+}
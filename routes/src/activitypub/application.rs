@@ -24,7 +24,7 @@ pub async fn view(State(ctx): State<Context>) -> crate::ApiResult<Response> {
 			.set_url(apb::Node::link(upub::url!(ctx, "/")))
 			.set_public_key(apb::Node::object(
 				apb::new()
-					.set_id(Some(upub::url!(ctx, "#main-key")))
+					.set_id(Some(ctx.key_id().to_string()))
 					.set_owner(Some(upub::url!(ctx, "")))
 					.set_public_key_pem(ctx.actor().public_key.clone())
 			))
@@ -83,26 +83,20 @@ pub async fn ap_fetch(
 	AuthIdentity(auth): AuthIdentity,
 	Query(query): Query<ProxyQuery>,
 ) -> crate::ApiResult<axum::Json<serde_json::Value>> {
-	let _user; // need this for lifetimes
+	let app_key = || (ctx.key_id().to_string(), ctx.pkey().to_string());
 
-	let (from, key) = match auth {
+	let (key_id, key) = match auth {
 		crate::Identity::Anonymous => {
 			if !ctx.cfg().security.allow_public_debugger {
 				return Err(crate::ApiError::unauthorized());
 			}
-			(ctx.base(), ctx.pkey())
+			app_key()
 		},
 		crate::Identity::Remote { .. } => return Err(crate::ApiError::forbidden()),
 		crate::Identity::Local { internal, .. } => {
-			_user = upub::model::actor::Entity::find_by_id(internal)
-				.one(ctx.db())
-				.await?;
-			match _user {
-				None => (ctx.base(), ctx.pkey()),
-				Some(ref u) => match u.private_key {
-					None => (ctx.base(), ctx.pkey()),
-					Some(ref k) => (u.id.as_str(), k.as_str()),
-				}
+			match upub::model::actor::Entity::find_by_id(internal).one(ctx.db()).await? {
+				None => app_key(),
+				Some(u) => upub::keys::signing(ctx.db(), &u).await?.unwrap_or_else(app_key),
 			}
 		},
 	};
@@ -111,12 +105,12 @@ pub async fn ap_fetch(
 		return Err(crate::ApiError::FetchError(upub::traits::fetch::RequestError::AbortedForPolicy));
 	}
 
-	let resp = Context::request(
+	let resp = ctx.request(
 			Method::GET,
 			&query.uri,
 			None,
-			from,
-			key,
+			&key_id,
+			&key,
 			&format!("{}+fetch", ctx.domain()),
 		)
 			.await?
@@ -130,13 +124,29 @@ pub async fn cloak_proxy(
 	State(ctx): State<Context>,
 	Path((hmac, uri)): Path<(String, String)>,
 ) -> crate::ApiResult<impl IntoResponse> {
+	let res = proxy(ctx, hmac, uri).await;
+	match res {
+		Ok((_, ref body)) => {
+			metrics::counter!("upub_media_proxy_requests_total", "outcome" => "ok").increment(1);
+			metrics::counter!("upub_media_proxy_bytes_total").increment(body.len() as u64);
+		},
+		Err(_) => metrics::counter!("upub_media_proxy_requests_total", "outcome" => "error").increment(1),
+	}
+	res
+}
+
+async fn proxy(ctx: Context, hmac: String, uri: String) -> crate::ApiResult<(axum::http::HeaderMap, Vec<u8>)> {
 	let uri = ctx.uncloak(&hmac, &uri)
 		.ok_or_else(ApiError::unauthorized)?;
 
-	if upub::ext::is_blacklisted(&uri, &ctx.cfg().reject.media) {
+	if upub::ext::is_blacklisted(&uri, &ctx.cfg().reject.media)
+		|| upub::blocks::is_blocked(ctx.db(), &Context::server(&uri), upub::model::domain_block::Severity::RejectMedia).await?
+	{
 		return Err(ApiError::Status(axum::http::StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS));
 	}
 
+	upub::traits::fetch::gate(&ctx, &uri).await?;
+
 	let resp = Context::client(ctx.domain())
 		.get(uri)
 		.send()
@@ -1,13 +1,16 @@
-use axum::{http::StatusCode, extract::State, Json};
+use axum::{http::{header, HeaderMap, StatusCode}, extract::State, Json};
 use rand::Rng;
-use sea_orm::{ActiveValue::{Set, NotSet}, ColumnTrait, Condition, EntityTrait, QueryFilter};
-use upub::{traits::Administrable, Context};
+use sea_orm::{sea_query::Expr, ActiveValue::{Set, NotSet}, ColumnTrait, Condition, EntityTrait, QueryFilter, TransactionTrait};
+use upub::{passwords::Verification, traits::Administrable, Context};
 
 
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct LoginForm {
 	email: String,
 	password: String,
+	/// totp or recovery code, required only if user enabled two factor authentication
+	#[serde(default)]
+	code: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -17,7 +20,7 @@ pub struct AuthSuccess {
 	expires: chrono::DateTime<chrono::Utc>,
 }
 
-fn token() -> String {
+pub(crate) fn token() -> String {
 	// TODO should probably use crypto-safe rng
 	rand::rng()
 		.sample_iter(&rand::distr::Alphanumeric)
@@ -26,21 +29,51 @@ fn token() -> String {
 		.collect()
 }
 
+pub(crate) fn user_agent(headers: &HeaderMap) -> Option<String> {
+	headers
+		.get(header::USER_AGENT)
+		.and_then(|x| x.to_str().ok())
+		.map(|x| x.chars().take(256).collect())
+}
+
 pub async fn login(
 	State(ctx): State<Context>,
+	headers: HeaderMap,
 	Json(login): Json<LoginForm>
 ) -> crate::ApiResult<Json<AuthSuccess>> {
-	// TODO salt the pwd
-	match upub::model::credential::Entity::find()
+	let credential = upub::model::credential::Entity::find()
 		.filter(Condition::all()
 			.add(upub::model::credential::Column::Login.eq(login.email))
-			.add(upub::model::credential::Column::Password.eq(sha256::digest(login.password)))
 			.add(upub::model::credential::Column::Active.eq(true))
 		)
 		.one(ctx.db())
-		.await?
-	{
+		.await?;
+
+	let verified = match credential {
+		None => None,
+		Some(x) => match upub::passwords::verify_async(login.password.clone(), x.password.clone()).await {
+			Verification::Invalid => None,
+			Verification::Valid | Verification::ValidLegacy => Some(x),
+		},
+	};
+
+	match verified {
 		Some(x) => {
+			second_factor(&ctx, &x, login.code.as_deref()).await?;
+
+			if upub::passwords::is_legacy(&x.password) {
+				// we finally know the plain password again: upgrade its hash
+				upub::model::credential::Entity::update_many()
+					.col_expr(upub::model::credential::Column::Password, Expr::value(upub::passwords::hash_async(login.password.clone()).await))
+					.filter(upub::model::credential::Column::Internal.eq(x.internal))
+					.exec(ctx.db())
+					.await?;
+				tracing::info!("upgraded password hash of {}", x.actor);
+			}
+
+			let internal = upub::model::actor::Entity::ap_to_internal(&x.actor, ctx.db())
+				.await?
+				.ok_or_else(crate::ApiError::internal_server_error)?;
 			let token = token();
 			let expires = chrono::Utc::now() + chrono::Duration::hours(ctx.cfg().security.session_duration_hours);
 			upub::model::session::Entity::insert(
@@ -49,6 +82,14 @@ pub async fn login(
 					secret: sea_orm::ActiveValue::Set(token.clone()),
 					actor: sea_orm::ActiveValue::Set(x.actor.clone()),
 					expires: sea_orm::ActiveValue::Set(expires),
+					scopes: sea_orm::ActiveValue::Set(None),
+					app: sea_orm::ActiveValue::Set(None),
+					refresh: sea_orm::ActiveValue::Set(None),
+					actor_internal: sea_orm::ActiveValue::Set(Some(internal)),
+					created: sea_orm::ActiveValue::Set(chrono::Utc::now()),
+					last_seen: sea_orm::ActiveValue::Set(None),
+					user_agent: sea_orm::ActiveValue::Set(user_agent(&headers)),
+					name: sea_orm::ActiveValue::Set(None),
 				}
 			)
 				.exec(ctx.db())
@@ -62,13 +103,84 @@ pub async fn login(
 	}
 }
 
+/// credentials with totp enabled need a valid code, or one of their recovery codes (which then
+/// gets consumed). missing codes are signaled with 428, so that clients know to ask for one
+pub(crate) async fn second_factor(ctx: &Context, credential: &upub::model::credential::Model, code: Option<&str>) -> crate::ApiResult<()> {
+	let Some(ref secret) = credential.totp else {
+		return Ok(());
+	};
+
+	let Some(code) = code.filter(|x| !x.is_empty()) else {
+		return Err(crate::ApiError::Status(StatusCode::PRECONDITION_REQUIRED));
+	};
+
+	let now = chrono::Utc::now();
+	if credential.totp_locked_until.is_some_and(|x| x > now) {
+		tracing::warn!("refusing second factor of {}: too many wrong codes", credential.actor);
+		return Err(crate::ApiError::Status(StatusCode::TOO_MANY_REQUESTS));
+	}
+
+	if let Some(step) = upub::totp::verify(secret, code, credential.totp_step) {
+		// only succeed if nobody used this or a later code in the meantime
+		let res = upub::model::credential::Entity::update_many()
+			.col_expr(upub::model::credential::Column::TotpStep, Expr::value(step))
+			.col_expr(upub::model::credential::Column::TotpFailures, Expr::value(0))
+			.filter(upub::model::credential::Column::Internal.eq(credential.internal))
+			.filter(Condition::any()
+				.add(upub::model::credential::Column::TotpStep.is_null())
+				.add(upub::model::credential::Column::TotpStep.lt(step))
+			)
+			.exec(ctx.db())
+			.await?;
+		if res.rows_affected == 1 {
+			return Ok(());
+		}
+	}
+
+	let stored = credential.recovery_codes.clone().unwrap_or_default();
+	if let Some(remaining) = upub::totp::redeem_recovery_code(&stored, code) {
+		// only succeed if nobody else redeemed codes in the meantime
+		let res = upub::model::credential::Entity::update_many()
+			.col_expr(upub::model::credential::Column::RecoveryCodes, Expr::value(remaining))
+			.col_expr(upub::model::credential::Column::TotpFailures, Expr::value(0))
+			.filter(upub::model::credential::Column::Internal.eq(credential.internal))
+			.filter(upub::model::credential::Column::RecoveryCodes.eq(stored))
+			.exec(ctx.db())
+			.await?;
+		if res.rows_affected == 1 {
+			tracing::warn!("{} authenticated with a recovery code", credential.actor);
+			return Ok(());
+		}
+	}
+
+	// count failures in db, so that guessing is slow no matter how many processes are serving
+	let failures = credential.totp_failures + 1;
+	let update = upub::model::credential::Entity::update_many()
+		.filter(upub::model::credential::Column::Internal.eq(credential.internal));
+	let update = if failures >= upub::totp::MAX_FAILURES {
+		tracing::warn!("locking second factor of {} after {failures} wrong codes", credential.actor);
+		update
+			.col_expr(upub::model::credential::Column::TotpFailures, Expr::value(0))
+			.col_expr(upub::model::credential::Column::TotpLockedUntil, Expr::value(now + chrono::Duration::minutes(upub::totp::LOCKOUT_MINUTES)))
+	} else {
+		update.col_expr(upub::model::credential::Column::TotpFailures, Expr::col(upub::model::credential::Column::TotpFailures).add(1))
+	};
+	update.exec(ctx.db()).await?;
+
+	Err(crate::ApiError::unauthorized())
+}
+
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct RefreshForm {
 	token: String,
+	/// needed to refresh already expired sessions of users with two factor authentication
+	#[serde(default)]
+	code: Option<String>,
 }
 
 pub async fn refresh(
 	State(ctx): State<Context>,
+	headers: HeaderMap,
 	Json(login): Json<RefreshForm>
 ) -> crate::ApiResult<Json<AuthSuccess>> {
 	if !ctx.cfg().security.allow_login_refresh {
@@ -81,24 +193,59 @@ pub async fn refresh(
 		.await?
 		.ok_or_else(crate::ApiError::unauthorized)?;
 
+	// only password logins get refreshed here: api tokens have their own expiry chosen by the user,
+	// and oauth apps must go through their refresh token, which gets rotated
+	if !prev.is_login() {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	// an expired token is just a leftover secret: with two factor authentication, it's not enough
+	if prev.expires < chrono::Utc::now() {
+		let credential = upub::model::credential::Entity::find()
+			.filter(upub::model::credential::Column::Actor.eq(&prev.actor))
+			.one(ctx.db())
+			.await?
+			.ok_or_else(crate::ApiError::unauthorized)?;
+		second_factor(&ctx, &credential, login.code.as_deref()).await?;
+	}
+
 	// allow refreshing tokens a little bit before they expire, specifically 1/4 of their lifespan before
-	let quarter_session_lifespan = chrono::Duration::days(ctx.cfg().security.session_duration_hours) / 4;
-	if prev.expires - quarter_session_lifespan > chrono::Utc::now() {
+	let session_lifespan = chrono::Duration::hours(ctx.cfg().security.session_duration_hours);
+	if prev.expires - session_lifespan / 4 > chrono::Utc::now() {
 		return Ok(Json(AuthSuccess { token: prev.secret, user: prev.actor, expires: prev.expires }));
 	}
 
 	let token = token();
-	let expires = chrono::Utc::now() + std::time::Duration::from_secs(3600 * 6);
+	let expires = chrono::Utc::now() + session_lifespan;
 	let user = prev.actor;
 	let new_session = upub::model::session::ActiveModel {
 		internal: NotSet,
 		actor: Set(user.clone()),
 		secret: Set(token.clone()),
 		expires: Set(expires),
+		scopes: Set(prev.scopes),
+		app: Set(None),
+		refresh: Set(None),
+		actor_internal: Set(prev.actor_internal),
+		created: Set(chrono::Utc::now()),
+		last_seen: Set(None),
+		user_agent: Set(user_agent(&headers)),
+		name: Set(None),
 	};
+
+	// refreshed session replaces the previous one, which can only be refreshed once
+	let tx = ctx.db().begin().await?;
+	let replaced = upub::model::session::Entity::delete_many()
+		.filter(upub::model::session::Column::Internal.eq(prev.internal))
+		.exec(&tx)
+		.await?;
+	if replaced.rows_affected == 0 {
+		return Err(crate::ApiError::unauthorized());
+	}
 	upub::model::session::Entity::insert(new_session)
-		.exec(ctx.db())
+		.exec(&tx)
 		.await?;
+	tx.commit().await?;
 
 	Ok(Json(AuthSuccess { token, expires, user }))
 }
@@ -111,24 +258,263 @@ pub struct RegisterForm {
 	summary: Option<String>,
 	avatar_url: Option<String>,
 	banner_url: Option<String>,
+	/// invite code, allows registering even when registrations are closed
+	#[serde(default)]
+	invite: Option<String>,
+	/// why user wants to join, for admins approving registrations
+	#[serde(default)]
+	reason: Option<String>,
 }
 
 pub async fn register(
 	State(ctx): State<Context>,
 	Json(registration): Json<RegisterForm>
 ) -> crate::ApiResult<Json<String>> {
-	if !ctx.cfg().security.allow_registration {
+	let invite = registration.invite.filter(|x| !x.is_empty());
+	if !ctx.cfg().security.allow_registration && invite.is_none() {
 		return Err(crate::ApiError::forbidden());
 	}
 
+	let uid = ctx.uid(&registration.username);
+	if upub::model::actor::Entity::find_by_ap_id(&uid).one(ctx.db()).await?.is_some() {
+		// check before redeeming invite, so that it's not wasted on a taken username
+		return Err(crate::ApiError::Status(StatusCode::CONFLICT));
+	}
+
+	// invite use is given back if registration fails
+	let tx = ctx.db().begin().await?;
+
+	let mut vouched = false;
+	if let Some(ref code) = invite {
+		let Some(redeemed) = upub::invites::redeem(&tx, code).await? else {
+			return Err(crate::ApiError::forbidden());
+		};
+		vouched = upub::invites::vouches(&ctx, &redeemed);
+	}
+
 	ctx.register_user(
 		registration.username.clone(),
 		registration.password,
 		registration.display_name,
 		registration.summary,
 		registration.avatar_url,
-		registration.banner_url
+		registration.banner_url,
+		&tx,
 	).await?;
 
-	Ok(Json(ctx.uid(&registration.username)))
+	let reason = registration.reason.filter(|x| !x.is_empty());
+	if vouched || reason.is_some() {
+		upub::model::credential::Entity::update_many()
+			// admins (or cli) inviting them vouch for them, no need to wait for approval
+			.col_expr(upub::model::credential::Column::Active, Expr::value(vouched || !ctx.cfg().security.require_user_approval))
+			.col_expr(upub::model::credential::Column::Reason, Expr::value(reason))
+			.filter(upub::model::credential::Column::Actor.eq(&uid))
+			.exec(&tx)
+			.await?;
+	}
+
+	tx.commit().await?;
+
+	if ctx.cfg().security.require_user_approval && !vouched {
+		tracing::info!("new registration from {uid} is waiting for approval");
+	}
+
+	Ok(Json(uid))
+}
+
+// two factor authentication can only be managed by users themselves, not by oauth apps
+fn own_credential_session(auth: &crate::Identity) -> crate::ApiResult<(&str, i64)> {
+	match auth {
+		crate::Identity::Local { id, session, login: true, .. } => Ok((id, *session)),
+		crate::Identity::Local { .. } => Err(crate::ApiError::forbidden()),
+		_ => Err(crate::ApiError::unauthorized()),
+	}
+}
+
+async fn own_credential(ctx: &Context, uid: &str) -> crate::ApiResult<upub::model::credential::Model> {
+	upub::model::credential::Entity::find()
+		.filter(upub::model::credential::Column::Actor.eq(uid))
+		.one(ctx.db())
+		.await?
+		.ok_or_else(crate::ApiError::not_found)
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TotpSetup {
+	secret: String,
+	uri: String,
+}
+
+/// generates a new secret, which is not stored until confirmed with a valid code
+pub async fn totp_setup(
+	State(ctx): State<Context>,
+	crate::AuthIdentity(auth): crate::AuthIdentity,
+) -> crate::ApiResult<Json<TotpSetup>> {
+	let (uid, _) = own_credential_session(&auth)?;
+	let credential = own_credential(&ctx, uid).await?;
+	let secret = upub::totp::secret();
+	let uri = upub::totp::uri(ctx.domain(), &credential.login, &secret);
+	Ok(Json(TotpSetup { secret, uri }))
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct TotpEnrollForm {
+	secret: String,
+	code: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TotpEnrolled {
+	/// shown only once, only hashes are stored
+	recovery_codes: Vec<String>,
+}
+
+pub async fn totp_enroll(
+	State(ctx): State<Context>,
+	crate::AuthIdentity(auth): crate::AuthIdentity,
+	Json(form): Json<TotpEnrollForm>,
+) -> crate::ApiResult<Json<TotpEnrolled>> {
+	let (uid, session) = own_credential_session(&auth)?;
+	let credential = own_credential(&ctx, uid).await?;
+
+	if credential.totp.is_some() {
+		return Err(crate::ApiError::Status(StatusCode::CONFLICT));
+	}
+
+	// make sure user's authenticator actually works before locking them out
+	if upub::totp::verify(&form.secret, &form.code, None).is_none() {
+		return Err(crate::ApiError::bad_request());
+	}
+
+	let recovery_codes = upub::totp::recovery_codes();
+	let hashes: Vec<String> = recovery_codes.iter().map(|x| upub::totp::hash_recovery_code(x)).collect();
+
+	upub::model::credential::Entity::update_many()
+		.col_expr(upub::model::credential::Column::Totp, Expr::value(form.secret))
+		.col_expr(upub::model::credential::Column::RecoveryCodes, Expr::value(hashes.join("\n")))
+		.filter(upub::model::credential::Column::Internal.eq(credential.internal))
+		.exec(ctx.db())
+		.await?;
+
+	// other logins happened with just a password
+	upub::model::session::Entity::delete_many()
+		.filter(upub::model::session::Column::Actor.eq(uid))
+		.filter(upub::model::session::Column::Internal.ne(session))
+		.exec(ctx.db())
+		.await?;
+
+	tracing::info!("{uid} enabled two factor authentication");
+
+	Ok(Json(TotpEnrolled { recovery_codes }))
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct TotpDisableForm {
+	password: String,
+	/// a current code (or a recovery code), a stolen session and password shouldn't be enough
+	code: String,
+}
+
+pub async fn totp_disable(
+	State(ctx): State<Context>,
+	crate::AuthIdentity(auth): crate::AuthIdentity,
+	Json(form): Json<TotpDisableForm>,
+) -> crate::ApiResult<()> {
+	let (uid, _) = own_credential_session(&auth)?;
+	let credential = own_credential(&ctx, uid).await?;
+
+	if upub::passwords::verify_async(form.password.clone(), credential.password.clone()).await == Verification::Invalid {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	second_factor(&ctx, &credential, Some(&form.code)).await?;
+
+	upub::model::credential::Entity::update_many()
+		.col_expr(upub::model::credential::Column::Totp, Expr::value(Option::<String>::None))
+		.col_expr(upub::model::credential::Column::RecoveryCodes, Expr::value(Option::<String>::None))
+		.col_expr(upub::model::credential::Column::TotpStep, Expr::value(Option::<i64>::None))
+		.filter(upub::model::credential::Column::Internal.eq(credential.internal))
+		.exec(ctx.db())
+		.await?;
+
+	tracing::info!("{uid} disabled two factor authentication");
+
+	Ok(())
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct PasswordChangeForm {
+	password: String,
+	new_password: String,
+}
+
+/// changes own password, logging out every other session
+pub async fn change_password(
+	State(ctx): State<Context>,
+	crate::AuthIdentity(auth): crate::AuthIdentity,
+	Json(form): Json<PasswordChangeForm>,
+) -> crate::ApiResult<()> {
+	let (uid, session) = own_credential_session(&auth)?;
+	let credential = own_credential(&ctx, uid).await?;
+
+	if upub::passwords::verify_async(form.password.clone(), credential.password.clone()).await == Verification::Invalid {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	if form.new_password.is_empty() {
+		return Err(crate::ApiError::unprocessable());
+	}
+
+	let tx = ctx.db().begin().await?;
+
+	upub::model::credential::Entity::update_many()
+		.col_expr(upub::model::credential::Column::Password, Expr::value(upub::passwords::hash_async(form.new_password.clone()).await))
+		.filter(upub::model::credential::Column::Internal.eq(credential.internal))
+		.exec(&tx)
+		.await?;
+
+	upub::model::session::Entity::delete_many()
+		.filter(upub::model::session::Column::Actor.eq(uid))
+		.filter(upub::model::session::Column::Internal.ne(session))
+		.exec(&tx)
+		.await?;
+
+	tx.commit().await?;
+
+	tracing::info!("{uid} changed password");
+
+	Ok(())
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct DeleteAccountForm {
+	password: String,
+	#[serde(default)]
+	code: Option<String>,
+}
+
+/// deletes own account for good: federates a Delete and leaves a tombstone behind
+pub async fn delete_account(
+	State(ctx): State<Context>,
+	crate::AuthIdentity(auth): crate::AuthIdentity,
+	Json(form): Json<DeleteAccountForm>,
+) -> crate::ApiResult<()> {
+	let (uid, _) = own_credential_session(&auth)?;
+	let credential = own_credential(&ctx, uid).await?;
+
+	if upub::passwords::verify_async(form.password.clone(), credential.password.clone()).await == Verification::Invalid {
+		return Err(crate::ApiError::forbidden());
+	}
+
+	second_factor(&ctx, &credential, form.code.as_deref()).await?;
+
+	let tx = ctx.db().begin().await?;
+	if upub::accounts::delete(&ctx, uid, &tx).await?.is_none() {
+		return Err(crate::ApiError::not_found());
+	}
+	tx.commit().await?;
+
+	ctx.wake_workers(); // dispatch immediately
+
+	Ok(())
 }
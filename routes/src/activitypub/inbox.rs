@@ -1,7 +1,7 @@
 use apb::{Activity, ActivityType, Base};
 use axum::{extract::{Query, State}, http::StatusCode, Json};
 use sea_orm::{sea_query::IntoCondition, ActiveValue::{NotSet, Set}, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
-use upub::{model::job::JobType, selector::{RichActivity, RichFillable}, traits::Fetcher, Context};
+use upub::{model::{domain_block::Severity, job::JobType}, policy::Direction, selector::{RichActivity, RichFillable}, traits::Fetcher, Context};
 
 use crate::{AuthIdentity, Identity, builders::JsonLD};
 
@@ -41,8 +41,19 @@ pub async fn page(
 pub async fn post(
 	State(ctx): State<Context>,
 	AuthIdentity(auth): AuthIdentity,
-	Json(mut activity): Json<serde_json::Value>
+	Json(activity): Json<serde_json::Value>
 ) -> crate::ApiResult<StatusCode> {
+	let res = accept(ctx, auth, activity).await;
+	let outcome = match res {
+		Ok(StatusCode::ACCEPTED) => "accepted",
+		Ok(_) => "ignored",
+		Err(_) => "rejected",
+	};
+	metrics::counter!("upub_inbox_activities_total", "outcome" => outcome).increment(1);
+	res
+}
+
+async fn accept(ctx: Context, auth: Identity, mut activity: serde_json::Value) -> crate::ApiResult<StatusCode> {
 	let Identity::Remote { domain, user: uid, .. } = auth else {
 		if matches!(activity.activity_type(), Ok(ActivityType::Delete)) {
 			// this is spammy af, ignore them!
@@ -71,8 +82,19 @@ pub async fn post(
 	let aid = activity.id()?.to_string();
 	let server = upub::Context::server(&aid);
 
+	// relays may forward activities from instances we don't federate with, check origin too
+	if !upub::allowlist::is_allowed(&ctx, &domain).await? || !upub::allowlist::is_allowed(&ctx, &server).await? {
+		return Err(crate::ApiError::Status(StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS));
+	}
+
+	if upub::blocks::is_blocked(ctx.db(), &server, Severity::Suspend).await? {
+		return Err(crate::ApiError::Status(StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS));
+	}
+
 	if activity.actor().id()? != uid {
-		if ctx.cfg().compat.verify_relayed_activities_by_fetching {
+		if verify_proof(&ctx, &activity).await? {
+			tracing::debug!("accepting relayed activity {aid} thanks to its integrity proof");
+		} else if ctx.cfg().compat.verify_relayed_activities_by_fetching {
 			activity = ctx.pull(&activity.id()?).await?.activity()?;
 		} else {
 			return Err(crate::ApiError::forbidden());
@@ -83,6 +105,11 @@ pub async fn post(
 		return Ok(StatusCode::OK); // already processed
 	}
 
+	let Some(activity) = ctx.policies().apply(&ctx, Direction::Inbound, &server, activity).await? else {
+		// don't let remotes know, they would just keep retrying
+		return Ok(StatusCode::OK);
+	};
+
 	let job = upub::model::job::ActiveModel {
 		internal: NotSet,
 		job_type: Set(JobType::Inbound),
@@ -94,6 +121,8 @@ pub async fn post(
 		not_before: Set(chrono::Utc::now()),
 		attempt: Set(0),
 		error: Set(None),
+		locked_until: Set(None),
+		domain: Set(None),
 	};
 
 	upub::model::job::Entity::insert(job).exec(ctx.db()).await?;
@@ -102,3 +131,43 @@ pub async fn post(
 
 	Ok(StatusCode::ACCEPTED)
 }
+
+/// check FEP-8b32 integrity proof of given activity, true only if it's valid and made by its actor
+async fn verify_proof(ctx: &Context, activity: &serde_json::Value) -> crate::ApiResult<bool> {
+	let Some(method) = upub::integrity::verification_method(activity) else { return Ok(false) };
+	let actor = activity.actor().id()?;
+
+	let key = match upub::keys::find(ctx.db(), method).await? {
+		Some(key) => key,
+		None => {
+			// maybe a key we never saw: make sure we know its owner and its latest keys. if we can't,
+			// just treat proof as invalid so that activity falls back to being verified by fetching
+			let user = match ctx.fetch_user(&actor, ctx.db()).await {
+				Ok(x) => x,
+				Err(upub::traits::fetch::RequestError::Database(e)) => return Err(e.into()),
+				Err(e) => {
+					tracing::debug!("could not fetch {actor} to verify proof: {e}");
+					return Ok(false);
+				},
+			};
+			crate::auth::refresh_keys(ctx, &user, method).await?;
+			match upub::keys::find(ctx.db(), method).await? {
+				Some(key) => key,
+				None => return Ok(false),
+			}
+		},
+	};
+
+	if key.actor != actor || key.algorithm != upub::keys::ED25519 {
+		tracing::debug!("proof on {} uses key {} which can't assert for {actor}", activity.id().unwrap_or_default(), key.id);
+		return Ok(false);
+	}
+
+	match upub::integrity::verify(activity, &key.public_key) {
+		Ok(valid) => Ok(valid),
+		Err(e) => {
+			tracing::debug!("could not verify proof on {}: {e}", activity.id().unwrap_or_default());
+			Ok(false)
+		},
+	}
+}
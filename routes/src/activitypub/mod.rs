@@ -9,8 +9,10 @@ pub mod auth;
 pub mod tags;
 pub mod file;
 pub mod well_known;
+pub mod admin;
+pub mod oauth;
 
-use axum::{http::StatusCode, response::IntoResponse, routing::{get, patch, post, put}, Router};
+use axum::{http::StatusCode, middleware::from_fn_with_state, response::IntoResponse, routing::{delete, get, patch, post, put}, Router};
 
 pub fn ap_routes(ctx: upub::Context) -> Router {
 	use crate::activitypub as ap; // TODO use self ?
@@ -20,7 +22,7 @@ pub fn ap_routes(ctx: upub::Context) -> Router {
 		.route("/search", get(ap::application::search))
 		.route("/fetch", get(ap::application::ap_fetch))
 		.route("/proxy/{hmac}/{uri}", get(ap::application::cloak_proxy))
-		.route("/inbox", post(ap::inbox::post))
+		.route("/inbox", post(ap::inbox::post).layer(from_fn_with_state(ctx.clone(), crate::digest::verify)))
 		.route("/inbox", get(ap::inbox::get))
 		.route("/inbox/page", get(ap::inbox::page))
 		.route("/outbox", post(ap::outbox::post))
@@ -29,6 +31,15 @@ pub fn ap_routes(ctx: upub::Context) -> Router {
 		.route("/auth", put(ap::auth::register))
 		.route("/auth", post(ap::auth::login))
 		.route("/auth", patch(ap::auth::refresh))
+		.route("/auth", delete(ap::auth::delete_account))
+		.route("/auth/password", post(ap::auth::change_password))
+		.route("/auth/totp", get(ap::auth::totp_setup).put(ap::auth::totp_enroll).delete(ap::auth::totp_disable))
+		.route("/oauth/apps", post(ap::oauth::register_app))
+		.route("/oauth/apps/{client_id}", get(ap::oauth::view_app))
+		.route("/oauth/authorize", get(ap::oauth::authorize))
+		.route("/oauth/authorize", post(ap::oauth::consent))
+		.route("/oauth/token", post(ap::oauth::token_grant))
+		.route("/oauth/revoke", post(ap::oauth::revoke))
 		.nest("/.well-known", Router::new()
 			.route("/webfinger", get(ap::well_known::webfinger))
 			.route("/host-meta", get(ap::well_known::host_meta))
@@ -41,7 +52,7 @@ pub fn ap_routes(ctx: upub::Context) -> Router {
 		.route("/groups/page", get(ap::groups::page))
 		.nest("/actors/{id}", Router::new()
 			.route("/", get(ap::actor::view))
-			.route("/inbox", post(ap::actor::inbox::post))
+			.route("/inbox", post(ap::actor::inbox::post).layer(from_fn_with_state(ctx.clone(), crate::digest::verify)))
 			.route("/inbox", get(ap::actor::inbox::get))
 			.route("/inbox/page", get(ap::actor::inbox::page))
 			.route("/outbox", post(ap::actor::outbox::post))
@@ -49,6 +60,20 @@ pub fn ap_routes(ctx: upub::Context) -> Router {
 			.route("/outbox/page", get(ap::actor::outbox::page))
 			.route("/notifications", get(ap::actor::notifications::get))
 			.route("/notifications/page", get(ap::actor::notifications::page))
+			.route("/notifications/quarantine", get(ap::actor::notifications::quarantine))
+			.route("/notifications/quarantine/page", get(ap::actor::notifications::quarantine_page))
+			.route("/notifications/quarantine/approve", post(ap::actor::notifications::approve))
+			.route("/scheduled", get(ap::actor::scheduled::get))
+			.route("/scheduled/page", get(ap::actor::scheduled::page))
+			.route("/scheduled/{aid}", put(ap::actor::scheduled::edit).delete(ap::actor::scheduled::cancel))
+			.route("/sessions", get(ap::actor::sessions::get).delete(ap::actor::sessions::revoke_all))
+			.route("/sessions/{sid}", delete(ap::actor::sessions::revoke))
+			.route("/tokens", get(ap::actor::tokens::get).post(ap::actor::tokens::create))
+			.route("/tokens/{tid}", delete(ap::actor::tokens::revoke))
+			.route("/invites", get(ap::actor::invites::get).post(ap::actor::invites::create))
+			.route("/invites/{code}", delete(ap::actor::invites::revoke))
+			.route("/export", get(ap::actor::archive::export))
+			.route("/import/following", post(ap::actor::archive::import_following))
 			.route("/followers", get(ap::actor::following::get::<false>))
 			.route("/followers/page", get(ap::actor::following::page::<false>))
 			.route("/following", get(ap::actor::following::get::<true>))
@@ -70,6 +95,16 @@ pub fn ap_routes(ctx: upub::Context) -> Router {
 			.route("/shares", get(ap::object::shares::get))
 			.route("/shares/page", get(ap::object::shares::page))
 		)
+		.route("/admin/allowlist", get(ap::admin::allowlist))
+		.route("/admin/allowlist/{domain}", put(ap::admin::allow).delete(ap::admin::disallow))
+		.route("/admin/blocks", get(ap::admin::blocks))
+		.route("/admin/blocks/{domain}", put(ap::admin::block).delete(ap::admin::unblock))
+		.route("/admin/invites", get(ap::admin::invites))
+		.route("/admin/invites/{code}", delete(ap::admin::revoke_invite))
+		.route("/admin/registrations", get(ap::admin::registrations))
+		.route("/admin/registrations/{username}/approve", post(ap::admin::approve_registration))
+		.route("/admin/registrations/{username}/reject", post(ap::admin::reject_registration))
+		.route("/blocklist", get(ap::admin::blocklist))
 		.route("/tags/{id}", get(ap::tags::get))
 		.route("/tags/{id}/page", get(ap::tags::page))
 		.route("/file", post(ap::file::upload))
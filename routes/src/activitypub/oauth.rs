@@ -0,0 +1,390 @@
+use axum::{extract::{Path, Query, State}, Form, Json};
+use sea_orm::{ActiveValue::{NotSet, Set}, ColumnTrait, EntityTrait, QueryFilter};
+use upub::{model, oauth::Scopes, Context};
+
+use crate::{activitypub::auth::{token, user_agent}, ApiError, AuthIdentity, Identity};
+
+/// out of band redirect: the consent page shows the code instead of redirecting
+pub const OOB: &str = "urn:ietf:wg:oauth:2.0:oob";
+
+fn parse_scopes(scope: Option<&str>) -> crate::ApiResult<Scopes> {
+	match scope {
+		None => Ok(Scopes { read: true, ..Default::default() }),
+		Some(s) => {
+			let scopes: Scopes = s.parse().map_err(|_| ApiError::bad_request())?;
+			if scopes.is_empty() {
+				return Err(ApiError::bad_request());
+			}
+			Ok(scopes)
+		},
+	}
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct AppRegistration {
+	client_name: String,
+	/// whitespace separated
+	redirect_uris: String,
+	scopes: Option<String>,
+	website: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AppCredentials {
+	client_id: String,
+	client_secret: String,
+	name: String,
+	website: Option<String>,
+	redirect_uris: Vec<String>,
+	scopes: String,
+}
+
+pub async fn register_app(
+	State(ctx): State<Context>,
+	Json(registration): Json<AppRegistration>,
+) -> crate::ApiResult<Json<AppCredentials>> {
+	let scopes = parse_scopes(registration.scopes.as_deref())?;
+	let redirect_uris: Vec<String> = registration.redirect_uris.split_whitespace().map(|x| x.to_string()).collect();
+	if redirect_uris.is_empty() || registration.client_name.is_empty() {
+		return Err(ApiError::bad_request());
+	}
+
+	let client_id = token();
+	let client_secret = token();
+	model::oauth_app::Entity::insert(model::oauth_app::ActiveModel {
+		internal: NotSet,
+		client_id: Set(client_id.clone()),
+		client_secret: Set(client_secret.clone()),
+		name: Set(registration.client_name.clone()),
+		website: Set(registration.website.clone()),
+		redirect_uris: Set(redirect_uris.join("\n")),
+		scopes: Set(scopes.to_string()),
+		published: Set(chrono::Utc::now()),
+	})
+		.exec(ctx.db())
+		.await?;
+
+	tracing::info!("registered oauth app '{}'", registration.client_name);
+
+	Ok(Json(AppCredentials {
+		client_id, client_secret, redirect_uris,
+		name: registration.client_name,
+		website: registration.website,
+		scopes: scopes.to_string(),
+	}))
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AppInfo {
+	name: String,
+	website: Option<String>,
+	scopes: String,
+}
+
+/// public details shown on the consent page
+pub async fn view_app(
+	State(ctx): State<Context>,
+	Path(client_id): Path<String>,
+) -> crate::ApiResult<Json<AppInfo>> {
+	let app = model::oauth_app::Entity::find_by_client_id(&client_id)
+		.one(ctx.db())
+		.await?
+		.ok_or_else(ApiError::not_found)?;
+
+	Ok(Json(AppInfo { name: app.name, website: app.website, scopes: app.scopes }))
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct AuthorizeQuery {
+	response_type: String,
+	client_id: String,
+	redirect_uri: String,
+	scope: Option<String>,
+	code_challenge: Option<String>,
+	code_challenge_method: Option<String>,
+}
+
+/// validates the request and hands it over to the consent page on the frontend, which is where
+/// users (already logged in there) decide what to grant
+pub async fn authorize(
+	State(ctx): State<Context>,
+	Query(query): Query<AuthorizeQuery>,
+	axum::extract::RawQuery(raw): axum::extract::RawQuery,
+) -> crate::ApiResult<()> {
+	if query.response_type != "code" {
+		return Err(ApiError::bad_request());
+	}
+
+	if query.code_challenge.is_some() && query.code_challenge_method.as_deref() != Some("S256") {
+		return Err(ApiError::bad_request());
+	}
+
+	let app = model::oauth_app::Entity::find_by_client_id(&query.client_id)
+		.one(ctx.db())
+		.await?
+		.ok_or_else(ApiError::not_found)?;
+
+	if !upub::oauth::valid_redirect(&app, &query.redirect_uri) {
+		return Err(ApiError::bad_request());
+	}
+
+	parse_scopes(query.scope.as_deref())?;
+
+	Err(ApiError::Redirect(upub::url!(ctx, "/web/oauth/authorize?{}", raw.unwrap_or_default())))
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ConsentForm {
+	client_id: String,
+	redirect_uri: String,
+	scope: Option<String>,
+	state: Option<String>,
+	code_challenge: Option<String>,
+	code_challenge_method: Option<String>,
+	approve: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConsentResult {
+	/// where to send the user back to the app
+	redirect: Option<String>,
+	/// for out of band apps, user must copy this manually
+	code: Option<String>,
+}
+
+pub async fn consent(
+	State(ctx): State<Context>,
+	AuthIdentity(auth): AuthIdentity,
+	Json(form): Json<ConsentForm>,
+) -> crate::ApiResult<Json<ConsentResult>> {
+	// only sessions from password logins can grant access to other apps
+	let Identity::Local { id: uid, login, .. } = auth else {
+		return Err(ApiError::unauthorized());
+	};
+	if !login {
+		return Err(ApiError::forbidden());
+	}
+
+	let app = model::oauth_app::Entity::find_by_client_id(&form.client_id)
+		.one(ctx.db())
+		.await?
+		.ok_or_else(ApiError::not_found)?;
+
+	if !upub::oauth::valid_redirect(&app, &form.redirect_uri) {
+		return Err(ApiError::bad_request());
+	}
+
+	if form.code_challenge.is_some() && form.code_challenge_method.as_deref() != Some("S256") {
+		return Err(ApiError::bad_request());
+	}
+
+	let requested = parse_scopes(form.scope.as_deref())?;
+	let registered: Scopes = app.scopes.parse().map_err(|_| ApiError::internal_server_error())?;
+	if !registered.contains(&requested) {
+		return Err(ApiError::bad_request());
+	}
+
+	let code = if form.approve { Some(token()) } else { None };
+
+	if let Some(ref code) = code {
+		model::oauth_code::Entity::insert(model::oauth_code::ActiveModel {
+			internal: NotSet,
+			code: Set(code.clone()),
+			app: Set(app.internal),
+			actor: Set(uid.clone()),
+			redirect_uri: Set(form.redirect_uri.clone()),
+			scopes: Set(requested.to_string()),
+			challenge: Set(form.code_challenge),
+			expires: Set(chrono::Utc::now() + chrono::Duration::minutes(upub::oauth::CODE_LIFETIME_MINUTES)),
+		})
+			.exec(ctx.db())
+			.await?;
+		tracing::info!("{uid} granted '{requested}' to oauth app '{}'", app.name);
+	}
+
+	if form.redirect_uri == OOB {
+		return Ok(Json(ConsentResult { redirect: None, code }));
+	}
+
+	let mut redirect = reqwest::Url::parse(&form.redirect_uri).map_err(|_| ApiError::bad_request())?;
+	{
+		let mut params = redirect.query_pairs_mut();
+		match code {
+			Some(code) => params.append_pair("code", &code),
+			None => params.append_pair("error", "access_denied"),
+		};
+		if let Some(state) = form.state {
+			params.append_pair("state", &state);
+		}
+	}
+
+	Ok(Json(ConsentResult { redirect: Some(redirect.to_string()), code: None }))
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct TokenForm {
+	grant_type: String,
+	client_id: String,
+	client_secret: Option<String>,
+	code: Option<String>,
+	redirect_uri: Option<String>,
+	code_verifier: Option<String>,
+	refresh_token: Option<String>,
+	scope: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TokenResponse {
+	access_token: String,
+	token_type: &'static str,
+	scope: String,
+	created_at: i64,
+	expires_in: i64,
+	refresh_token: String,
+}
+
+pub async fn token_grant(
+	State(ctx): State<Context>,
+	headers: axum::http::HeaderMap,
+	Form(form): Form<TokenForm>,
+) -> crate::ApiResult<Json<TokenResponse>> {
+	let app = model::oauth_app::Entity::find_by_client_id(&form.client_id)
+		.one(ctx.db())
+		.await?
+		.ok_or_else(ApiError::unauthorized)?;
+
+	// public clients can't keep a secret, but must then prove they started the flow with PKCE
+	let authenticated = form.client_secret.as_deref().is_some_and(|x| upub::oauth::secret_matches(x, &app.client_secret));
+	if form.client_secret.is_some() && !authenticated {
+		return Err(ApiError::unauthorized());
+	}
+
+	let (actor, scopes) = match form.grant_type.as_str() {
+		"authorization_code" => {
+			let code = form.code.ok_or_else(ApiError::bad_request)?;
+			let grant = model::oauth_code::Entity::find()
+				.filter(model::oauth_code::Column::Code.eq(&code))
+				.filter(model::oauth_code::Column::App.eq(app.internal))
+				.one(ctx.db())
+				.await?
+				.ok_or_else(ApiError::bad_request)?;
+
+			// codes are single use: if someone else redeemed it first, refuse
+			let deleted = model::oauth_code::Entity::delete_by_id(grant.internal)
+				.exec(ctx.db())
+				.await?;
+			if deleted.rows_affected == 0 || grant.expires < chrono::Utc::now() {
+				return Err(ApiError::bad_request());
+			}
+
+			if form.redirect_uri.as_deref() != Some(grant.redirect_uri.as_str()) {
+				return Err(ApiError::bad_request());
+			}
+
+			match (grant.challenge, form.code_verifier) {
+				(Some(challenge), Some(verifier)) => if !upub::oauth::verify_challenge(&verifier, &challenge) {
+					return Err(ApiError::bad_request());
+				},
+				(Some(_), None) => return Err(ApiError::bad_request()),
+				(None, _) => if !authenticated {
+					return Err(ApiError::unauthorized());
+				},
+			}
+
+			(grant.actor, grant.scopes)
+		},
+		"refresh_token" => {
+			let refresh = form.refresh_token.ok_or_else(ApiError::bad_request)?;
+			let refreshable = chrono::Utc::now() - chrono::Duration::days(ctx.cfg().security.refresh_duration_days);
+			let prev = model::session::Entity::find()
+				.filter(model::session::Column::Refresh.eq(upub::tokens::hash(&refresh)))
+				.filter(model::session::Column::App.eq(app.internal))
+				.filter(model::session::Column::Expires.gt(refreshable))
+				.one(ctx.db())
+				.await?
+				.ok_or_else(ApiError::bad_request)?;
+
+			// refresh tokens are single use too: old session is replaced by the new one
+			let deleted = model::session::Entity::delete_by_id(prev.internal)
+				.exec(ctx.db())
+				.await?;
+			if deleted.rows_affected == 0 {
+				return Err(ApiError::bad_request());
+			}
+
+			let mut scopes: Scopes = prev.scopes.unwrap_or_default().parse().map_err(|_| ApiError::internal_server_error())?;
+			if let Some(narrower) = form.scope {
+				scopes = scopes.intersect(&parse_scopes(Some(narrower.as_str()))?);
+			}
+
+			(prev.actor, scopes.to_string())
+		},
+		_ => return Err(ApiError::bad_request()),
+	};
+
+	let internal = model::actor::Entity::ap_to_internal(&actor, ctx.db())
+		.await?
+		.ok_or_else(ApiError::internal_server_error)?;
+	let access_token = token();
+	let refresh_token = token();
+	let now = chrono::Utc::now();
+	let duration = chrono::Duration::hours(ctx.cfg().security.session_duration_hours);
+	model::session::Entity::insert(model::session::ActiveModel {
+		internal: NotSet,
+		actor: Set(actor),
+		// like api tokens, only hashes are stored
+		secret: Set(upub::tokens::hash(&access_token)),
+		expires: Set(now + duration),
+		scopes: Set(Some(scopes.clone())),
+		app: Set(Some(app.internal)),
+		refresh: Set(Some(upub::tokens::hash(&refresh_token))),
+		actor_internal: Set(Some(internal)),
+		created: Set(now),
+		last_seen: Set(None),
+		user_agent: Set(user_agent(&headers)),
+		name: Set(None),
+	})
+		.exec(ctx.db())
+		.await?;
+
+	Ok(Json(TokenResponse {
+		access_token, refresh_token, scope: scopes,
+		token_type: "Bearer",
+		created_at: now.timestamp(),
+		expires_in: duration.num_seconds(),
+	}))
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct RevokeForm {
+	token: String,
+	client_id: String,
+	client_secret: Option<String>,
+}
+
+/// revokes both access and refresh tokens, always succeeds as per RFC7009
+pub async fn revoke(
+	State(ctx): State<Context>,
+	Form(form): Form<RevokeForm>,
+) -> crate::ApiResult<()> {
+	let Some(app) = model::oauth_app::Entity::find_by_client_id(&form.client_id).one(ctx.db()).await? else {
+		return Err(ApiError::unauthorized());
+	};
+
+	if form.client_secret.is_some_and(|x| !upub::oauth::secret_matches(&x, &app.client_secret)) {
+		return Err(ApiError::unauthorized());
+	}
+
+	let token = upub::tokens::hash(&form.token);
+	model::session::Entity::delete_many()
+		.filter(model::session::Column::App.eq(app.internal))
+		.filter(
+			sea_orm::Condition::any()
+				.add(model::session::Column::Secret.eq(&token))
+				.add(model::session::Column::Refresh.eq(&token))
+		)
+		.exec(ctx.db())
+		.await?;
+
+	Ok(())
+}
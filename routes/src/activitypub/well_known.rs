@@ -130,6 +130,10 @@ pub async fn webfinger(
 			return Err(StatusCode::UNPROCESSABLE_ENTITY.into());
 		};
 
+	if user.deleted.is_some() {
+		return Err(StatusCode::GONE.into());
+	}
+
 	let expires = if user.domain == ctx.domain() {
 		// TODO configurable webfinger TTL, also 30 days may be too much???
 		Some(chrono::Utc::now() + chrono::Duration::days(30))
@@ -208,6 +212,7 @@ pub struct OauthAuthorizationServerResponse {
 	issuer: String,
 	authorization_endpoint: String,
 	token_endpoint: String,
+	revocation_endpoint: String,
 	scopes_supported: Vec<String>,
 	response_types_supported: Vec<String>,
 	grant_types_supported: Vec<String>,
@@ -219,21 +224,14 @@ pub struct OauthAuthorizationServerResponse {
 pub async fn oauth_authorization_server(State(ctx): State<Context>) -> crate::ApiResult<Json<OauthAuthorizationServerResponse>> {
 	Ok(Json(OauthAuthorizationServerResponse {
 		issuer: upub::url!(ctx, ""),
-		authorization_endpoint: upub::url!(ctx, "/auth"),
-		token_endpoint: "".to_string(),
-		scopes_supported: vec![
-			"read:account".to_string(),
-			"write:account".to_string(),
-			"read:favorites".to_string(),
-			"write:favorites".to_string(),
-			"read:following".to_string(),
-			"write:following".to_string(),
-			"write:notes".to_string(),
-		],
+		authorization_endpoint: upub::url!(ctx, "/oauth/authorize"),
+		token_endpoint: upub::url!(ctx, "/oauth/token"),
+		revocation_endpoint: upub::url!(ctx, "/oauth/revoke"),
+		scopes_supported: upub::oauth::Scopes::SUPPORTED.iter().map(|x| x.to_string()).collect(),
 		response_types_supported: vec!["code".to_string()],
-		grant_types_supported: vec!["authorization_code".to_string()],
+		grant_types_supported: vec!["authorization_code".to_string(), "refresh_token".to_string()],
 		service_documentation: "".to_string(),
-		code_challenge_methods_supported: vec![],
+		code_challenge_methods_supported: vec!["S256".to_string()],
 		authorization_response_iss_parameter_supported: false,
 	}))
 }
@@ -1,10 +1,12 @@
 use axum::{extract::{FromRef, FromRequestParts}, http::{header, request::Parts}};
-use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter};
-use httpsign::HttpSignature;
+use sea_orm::{sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter};
+use httpsign::{HttpSignature, HttpSignatureError, MessageSignature};
 use upub::traits::{fetch::RequestError, Fetcher};
 
 use crate::ApiError;
 
+const LAST_SEEN_GRANULARITY_MINUTES: i64 = 5;
+
 #[derive(Debug, Clone)]
 pub enum Identity {
 	Anonymous,
@@ -16,6 +18,12 @@ pub enum Identity {
 	Local {
 		id: String,
 		internal: i64,
+		/// what this session was granted, password logins get all scopes
+		scopes: upub::oauth::Scopes,
+		/// internal id of the session used to authenticate
+		session: i64,
+		/// authenticated with a session from a password login, not from an oauth app nor an api token
+		login: bool,
 	},
 }
 
@@ -31,7 +39,7 @@ impl Identity {
 		match self {
 			Identity::Anonymous => base_cond,
 			Identity::Remote { internal, .. } => base_cond.add(upub::model::addressing::Column::Instance.eq(*internal)), 
-			Identity::Local { internal, id } => base_cond
+			Identity::Local { internal, id, .. } => base_cond
 				.add(upub::model::addressing::Column::Actor.eq(*internal))
 				.add(upub::model::object::Column::AttributedTo.eq(id))
 		}
@@ -42,7 +50,7 @@ impl Identity {
 		match self {
 			Identity::Anonymous => base_cond,
 			Identity::Remote { internal, .. } => base_cond.add(upub::model::addressing::Column::Instance.eq(*internal)), 
-			Identity::Local { internal, id } => base_cond
+			Identity::Local { internal, id, .. } => base_cond
 				.add(upub::model::addressing::Column::Actor.eq(*internal))
 				.add(upub::model::object::Column::AttributedTo.eq(id))
 				.add(upub::model::activity::Column::Actor.eq(id)),
@@ -64,6 +72,14 @@ impl Identity {
 		}
 	}
 
+	/// local users listed in `security.admins`
+	pub fn is_admin(&self, ctx: &upub::Context) -> bool {
+		match self {
+			Identity::Local { id, .. } => ctx.cfg().security.admins.iter().any(|x| &ctx.uid(x) == id),
+			_ => false,
+		}
+	}
+
 	#[allow(unused)]
 	pub fn is_anon(&self) -> bool {
 		matches!(self, Self::Anonymous)
@@ -82,6 +98,85 @@ impl Identity {
 
 pub struct AuthIdentity(pub Identity);
 
+/// remotes may sign either with legacy draft-cavage `Signature` header or with RFC 9421
+/// `Signature-Input` + `Signature` headers, accept both
+enum RequestSignature {
+	Cavage(HttpSignature),
+	Message(MessageSignature),
+}
+
+impl RequestSignature {
+	fn from_parts(parts: &Parts) -> Option<Result<Self, HttpSignatureError>> {
+		let signature = parts.headers.get("Signature")?.to_str().unwrap_or("");
+		tracing::debug!("validating http signature '{signature}'");
+		match parts.headers.get("Signature-Input").map(|v| v.to_str().unwrap_or("")) {
+			Some(input) => Some(
+				MessageSignature::parse(input, signature)
+					.and_then(|x| x.check_components(parts.method == axum::http::Method::POST).map(|_| x))
+					.map(RequestSignature::Message)
+			),
+			None => Some(Ok(RequestSignature::Cavage(HttpSignature::parse(signature)))),
+		}
+	}
+
+	fn key_id(&self) -> &str {
+		match self {
+			RequestSignature::Cavage(x) => &x.key_id,
+			RequestSignature::Message(x) => &x.key_id,
+		}
+	}
+
+	/// when this signature was made, only if it's covered by the signature itself
+	fn created(&self, parts: &Parts) -> Option<chrono::DateTime<chrono::Utc>> {
+		match self {
+			RequestSignature::Message(x) => chrono::DateTime::from_timestamp(x.created?, 0),
+			RequestSignature::Cavage(_) => {
+				if !self.covers("date") { return None }
+				let date = parts.headers.get(header::DATE)?.to_str().ok()?;
+				chrono::DateTime::parse_from_rfc2822(date).ok().map(|x| x.with_timezone(&chrono::Utc))
+			},
+		}
+	}
+
+	/// only RFC 9421 signatures can carry their own expiration
+	fn expired(&self) -> bool {
+		match self {
+			RequestSignature::Message(x) => x.expired(chrono::Utc::now().timestamp()),
+			RequestSignature::Cavage(_) => false,
+		}
+	}
+
+	fn signature(&self) -> &str {
+		match self {
+			RequestSignature::Cavage(x) => &x.signature,
+			RequestSignature::Message(x) => &x.signature,
+		}
+	}
+
+	fn covers(&self, header: &str) -> bool {
+		match self {
+			RequestSignature::Cavage(x) => x.headers.iter().any(|h| h.eq_ignore_ascii_case(header)),
+			RequestSignature::Message(x) => x.components.iter().any(|c| c.eq_ignore_ascii_case(header)),
+		}
+	}
+
+	fn verify(&mut self, parts: &Parts, base: &str, key: &str) -> Result<bool, HttpSignatureError> {
+		match self {
+			RequestSignature::Cavage(x) => x.build_from_parts(parts).verify(key),
+			RequestSignature::Message(x) => x.build_from_parts(parts, base).verify(key),
+		}
+	}
+
+	/// signatures we can't verify (unsupported algorithms or key types, malformed values) are just
+	/// invalid, not server errors
+	fn verify_or_refuse(&mut self, parts: &Parts, base: &str, key: &str) -> bool {
+		self.verify(parts, base, key).unwrap_or_else(|e| {
+			tracing::debug!("could not verify http signature with {}: {e}", self.key_id());
+			false
+		})
+	}
+}
+
 impl<S> FromRequestParts<S> for AuthIdentity
 where
 	upub::Context: FromRef<S>,
@@ -100,55 +195,161 @@ where
 			.unwrap_or("");
 
 		if auth_header.starts_with("Bearer ") {
+			let bearer = auth_header.replace("Bearer ", "");
 			match upub::model::session::Entity::find()
-				.filter(upub::model::session::Column::Secret.eq(auth_header.replace("Bearer ", "")))
+				// api tokens and oauth sessions are stored hashed, and their hash must not work as a token
+				.filter(
+					Condition::any()
+						.add(
+							Condition::all()
+								.add(upub::model::session::Column::Name.is_null())
+								.add(upub::model::session::Column::App.is_null())
+								.add(upub::model::session::Column::Secret.eq(&bearer))
+						)
+						.add(
+							Condition::all()
+								.add(
+									Condition::any()
+										.add(upub::model::session::Column::Name.is_not_null())
+										.add(upub::model::session::Column::App.is_not_null())
+								)
+								.add(upub::model::session::Column::Secret.eq(upub::tokens::hash(&bearer)))
+						)
+				)
 				.filter(upub::model::session::Column::Expires.gt(chrono::Utc::now()))
 				.one(ctx.db())
 				.await?
 			{
 				None => return Err(ApiError::unauthorized()),
 				Some(x) => {
-					// TODO could we store both actor ap id and internal id in session? to avoid this extra
-					// lookup on *every* local authed request we receive...
-					let internal = upub::model::actor::Entity::ap_to_internal(&x.actor, ctx.db())
-						.await?
-						.ok_or_else(ApiError::internal_server_error)?;
-					identity = Identity::Local { id: x.actor, internal };
+					let login = x.is_login();
+					let internal = match x.actor_internal {
+						Some(internal) => internal,
+						None => upub::model::actor::Entity::ap_to_internal(&x.actor, ctx.db())
+							.await?
+							.ok_or_else(ApiError::internal_server_error)?,
+					};
+					let scopes = match x.scopes {
+						None => upub::oauth::Scopes::all(),
+						Some(s) => s.parse().map_err(|_| ApiError::internal_server_error())?,
+					};
+					// nested routers strip their prefix from request uri
+					let path = parts.extensions.get::<axum::extract::OriginalUri>()
+						.map(|x| x.path().to_string())
+						.unwrap_or_else(|| parts.uri.path().to_string());
+					if !scopes.allows_request(parts.method.as_str(), &path) {
+						tracing::debug!("refusing {} {path} to session without required scope (has '{scopes}')", parts.method);
+						return Err(ApiError::forbidden());
+					}
+					// don't write on every request, knowing roughly when a session was last used is enough
+					let now = chrono::Utc::now();
+					if x.last_seen.is_none_or(|t| now - t > chrono::Duration::minutes(LAST_SEEN_GRANULARITY_MINUTES)) {
+						upub::model::session::Entity::update_many()
+							.col_expr(upub::model::session::Column::LastSeen, Expr::value(now))
+							.filter(upub::model::session::Column::Internal.eq(x.internal))
+							.exec(ctx.db())
+							.await?;
+					}
+					identity = Identity::Local { id: x.actor, internal, scopes, session: x.internal, login };
 				},
 			}
 		}
 
-		if let Some(sig) = parts
-			.headers
-			.get("Signature")
-			.map(|v| v.to_str().unwrap_or(""))
-		{
-			tracing::debug!("validating http signature '{sig}'");
-			let mut http_signature = HttpSignature::parse(sig);
+		if let Some(sig) = RequestSignature::from_parts(parts) {
+			let mut http_signature = sig.map_err(|e| {
+				tracing::warn!("refusing malformed http signature: {e}");
+				ApiError::bad_request()
+			})?;
+
+			// refuse signatures made too long ago (or in the future), so captured requests can't be
+			// replayed forever
+			let skew = chrono::Duration::seconds(ctx.cfg().security.signature_clock_skew_seconds);
+			match http_signature.created(parts) {
+				None => {
+					tracing::warn!("refusing http signature without signed date");
+					return Err(ApiError::unauthorized());
+				},
+				Some(created) if !crate::replay::fresh(created, skew) => {
+					tracing::warn!("refusing http signature dated {created}, outside allowed clock skew");
+					return Err(ApiError::unauthorized());
+				},
+				Some(_) => {},
+			}
 
-			// TODO assert payload's digest is equal to signature's
-			//      really annoying to do here because we're streaming
-			//      the request, maybe even impossible with this design?
+			if http_signature.expired() {
+				tracing::warn!("refusing expired http signature");
+				return Err(ApiError::unauthorized());
+			}
 
-			let user_id = http_signature.key_id
-				.replace("/main-key", "") // gotosocial whyyyyy
-				.split('#')
-				.next().ok_or(ApiError::bad_request())?
-				.to_string();
+			// signatures only cover headers: for requests carrying a body, make sure they sign a digest
+			// which crate::digest::verify already checked against the buffered payload, otherwise a valid
+			// signature could be replayed with any body
+			if parts.method == axum::http::Method::POST {
+				let verified = parts.extensions.get::<crate::digest::VerifiedDigest>().cloned().unwrap_or_default();
+				if !verified.0.iter().any(|h| http_signature.covers(h)) {
+					tracing::warn!("refusing http signature not covering a verified body digest");
+					return Err(ApiError::unauthorized());
+				}
+			}
+
+			// keys we already know (local ones, or learned from remote actor documents) tell us their
+			// owner, otherwise we have to guess it from key id
+			let key_id = http_signature.key_id().to_string();
+
+			// don't even bother fetching keys of instances we don't federate with
+			if !upub::allowlist::is_allowed(&ctx, &upub::Context::server(&key_id)).await? {
+				tracing::debug!("refusing request signed by non allowlisted instance: {key_id}");
+				return Err(ApiError::Status(axum::http::StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS));
+			}
+
+			if upub::blocks::is_blocked(ctx.db(), &upub::Context::server(&key_id), upub::model::domain_block::Severity::Suspend).await? {
+				tracing::debug!("refusing request signed by suspended instance: {key_id}");
+				return Err(ApiError::Status(axum::http::StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS));
+			}
+			let known_key = upub::keys::find(ctx.db(), &key_id).await?;
+			let user_id = match known_key {
+				Some(ref key) => key.actor.clone(),
+				None => key_id
+					.replace("/main-key", "") // gotosocial whyyyyy
+					.split('#')
+					.next().ok_or(ApiError::bad_request())?
+					.to_string(),
+			};
 
 			match ctx.fetch_user(&user_id, ctx.db()).await {
 				Err(RequestError::Database(x)) => return Err(RequestError::Database(x).into()),
 				Err(e) => tracing::debug!("could not fetch {user_id} to verify signature: {e}"),
-				Ok(user) => {
-					let signature = http_signature.build_from_parts(parts);
-					tracing::debug!("constructed http signature {signature:?}");
-					let valid = signature.verify(&user.public_key)?;
+				Ok(mut user) => {
+					let public_key = known_key.map(|x| x.public_key).unwrap_or_else(|| user.public_key.clone());
+					let mut valid = http_signature.verify_or_refuse(parts, ctx.base(), &public_key);
+
+					if !valid {
+						// remote may have rotated its keys since we last fetched it: refresh once and retry
+						if let Some((refreshed, public_key)) = refresh_keys(&ctx, &user, &key_id).await? {
+							valid = http_signature.verify_or_refuse(parts, ctx.base(), &public_key);
+							user = refreshed;
+						}
+					}
 
 					if !valid {
 						tracing::warn!("refusing mismatching http signature");
 						return Err(ApiError::unauthorized());
 					}
 
+					// GETs are harmless and could legitimately produce identical signatures if repeated
+					// within the same second, but each POST should be delivered only once
+					if parts.method == axum::http::Method::POST
+						&& !crate::replay::first_use(http_signature.signature(), skew, ctx.cfg().security.signature_replay_cache_size)
+					{
+						tracing::warn!("refusing replayed http signature from {user_id}");
+						return Err(ApiError::unauthorized());
+					}
+
+					// now we know this instance speaks RFC 9421, so we can sign our requests that way too
+					if let RequestSignature::Message(_) = http_signature {
+						upub::signatures::set(ctx.db(), &user.domain, upub::signatures::SignatureScheme::Rfc9421).await?;
+					}
+
 					if ctx.cfg().reject.requests.contains(&user.domain) {
 						return Err(ApiError::Status(axum::http::StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS));
 					}
@@ -167,3 +368,44 @@ where
 		Ok(AuthIdentity(identity))
 	}
 }
+
+/// re-fetch remote actor to pick up rotated keys, returns refreshed actor and key to verify with
+pub(crate) async fn refresh_keys(
+	ctx: &upub::Context,
+	user: &upub::model::actor::Model,
+	key_id: &str,
+) -> Result<Option<(upub::model::actor::Model, String)>, ApiError> {
+	if ctx.is_local(&user.id) {
+		return Ok(None);
+	}
+
+	// don't let anyone make us hammer remotes just by sending bogus signatures
+	if user.updated > chrono::Utc::now() - chrono::Duration::minutes(1) {
+		return Ok(None);
+	}
+
+	let document = match ctx.pull(&user.id).await.and_then(|x| x.actor()) {
+		Ok(x) => x,
+		Err(e) => {
+			tracing::debug!("could not refresh {} to verify signature: {e}", user.id);
+			return Ok(None);
+		},
+	};
+
+	let refreshed = match upub::AP::actor_q(&document, Some(user.internal)) {
+		Ok(x) => x.update(ctx.db()).await?,
+		Err(e) => {
+			tracing::debug!("could not normalize refreshed actor {}: {e}", user.id);
+			return Ok(None);
+		},
+	};
+
+	upub::keys::learn(ctx.db(), &document).await?;
+
+	let public_key = upub::keys::find(ctx.db(), key_id)
+		.await?
+		.map(|x| x.public_key)
+		.unwrap_or_else(|| refreshed.public_key.clone());
+
+	Ok(Some((refreshed, public_key)))
+}
@@ -0,0 +1,44 @@
+use axum::{body::Body, extract::{Request, State}, http::StatusCode, middleware::Next, response::Response};
+
+use crate::ApiError;
+
+/// digest headers which have been checked against the request body
+#[derive(Debug, Clone, Default)]
+pub struct VerifiedDigest(pub Vec<&'static str>);
+
+/// buffer request body (up to configured limit) and check its `Digest` and `Content-Digest`
+/// headers, if present. [crate::AuthIdentity] will then only trust signatures covering them
+pub async fn verify(
+	State(ctx): State<upub::Context>,
+	req: Request,
+	next: Next,
+) -> Result<Response, ApiError> {
+	let (mut parts, body) = req.into_parts();
+	let bytes = axum::body::to_bytes(body, ctx.cfg().security.max_inbox_body_bytes)
+		.await
+		.map_err(|e| {
+			tracing::warn!("refusing inbound request body: {e}");
+			ApiError::Status(StatusCode::PAYLOAD_TOO_LARGE)
+		})?;
+
+	let mut verified = VerifiedDigest::default();
+	for header in ["digest", "content-digest"] {
+		let Some(value) = parts.headers.get(header) else { continue };
+		let value = value.to_str().map_err(|_| ApiError::bad_request())?;
+		let res = match header {
+			"digest" => httpsign::verify_digest(value, &bytes),
+			_ => httpsign::verify_content_digest(value, &bytes),
+		};
+		match res {
+			Some(true) => verified.0.push(header),
+			None => tracing::debug!("no supported algorithm in {header} header '{value}'"),
+			Some(false) => {
+				tracing::warn!("refusing request with mismatching {header} header '{value}'");
+				return Err(ApiError::bad_request());
+			},
+		}
+	}
+	parts.extensions.insert(verified);
+
+	Ok(next.run(Request::from_parts(parts, Body::from(bytes))).await)
+}
@@ -1,11 +1,16 @@
 pub mod auth;
 pub use auth::{AuthIdentity, Identity};
 
+pub mod digest;
+pub mod replay;
+
 pub mod error;
 pub use error::{ApiError, ApiResult};
 
 pub mod builders;
 
+pub mod prometheus;
+
 
 #[cfg(feature = "activitypub")]
 pub mod activitypub;
@@ -33,6 +38,18 @@ pub async fn serve(ctx: upub::Context, bind: String, shutdown: impl ShutdownToke
 	#[cfg(feature = "mastodon")] { router = router.merge(mastodon::masto_routes(ctx.clone())); }
 	#[cfg(feature = "web")] { router = router.merge(web::web_routes(ctx.clone())); }
 
+	if ctx.cfg().metrics.enabled {
+		if let Some(handle) = prometheus::install() {
+			// added after this layer so that scrapes don't show up in http metrics
+			router = router.route_layer(axum::middleware::from_fn(prometheus::track));
+			if ctx.cfg().metrics.bind.is_empty() {
+				router = router.merge(prometheus::routes(handle));
+			} else {
+				prometheus::spawn(&ctx.cfg().metrics.bind, handle);
+			}
+		}
+	}
+
 	router = router
 		.layer(
 			tower::ServiceBuilder::new()
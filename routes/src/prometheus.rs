@@ -0,0 +1,76 @@
+use axum::{extract::{MatchedPath, Request, State}, middleware::Next, response::Response, routing::get, Router};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+
+/// how often histograms and idle series get cleaned up by the recorder
+const UPKEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// install global prometheus recorder: metrics from everything running in this process (including
+/// workers, if in monolith mode) will be exported. returns None if metrics can't be collected
+pub fn install() -> Option<PrometheusHandle> {
+	match PrometheusBuilder::new().install_recorder() {
+		Ok(handle) => {
+			let upkeep = handle.clone();
+			tokio::spawn(async move {
+				let mut interval = tokio::time::interval(UPKEEP_INTERVAL);
+				loop {
+					interval.tick().await;
+					upkeep.run_upkeep();
+				}
+			});
+			Some(handle)
+		},
+		Err(e) => {
+			tracing::error!("could not install metrics recorder: {e}");
+			None
+		},
+	}
+}
+
+/// `/metrics` route, rendering everything collected so far in prometheus text format
+pub fn routes(handle: PrometheusHandle) -> Router {
+	Router::new()
+		.route("/metrics", get(render))
+		.with_state(handle)
+}
+
+async fn render(State(handle): State<PrometheusHandle>) -> String {
+	handle.render()
+}
+
+/// serve `/metrics` on its own listener, away from public api routes
+pub fn spawn(bind: &str, handle: PrometheusHandle) {
+	let addr = match bind.parse::<std::net::SocketAddr>() {
+		Ok(x) => x,
+		Err(e) => return tracing::error!("invalid metrics bind address '{bind}': {e}"),
+	};
+	tokio::spawn(async move {
+		let listener = match tokio::net::TcpListener::bind(addr).await {
+			Ok(x) => x,
+			Err(e) => return tracing::error!("could not bind metrics listener on {addr}: {e}"),
+		};
+		tracing::info!("serving metrics on {addr}");
+		if let Err(e) = axum::serve(listener, routes(handle)).await {
+			tracing::error!("metrics listener stopped: {e}");
+		}
+	});
+}
+
+pub async fn track(req: Request, next: Next) -> Response {
+	let start = std::time::Instant::now();
+	let method = req.method().to_string();
+	// use route template rather than full uri, so that labels don't explode
+	let route = req.extensions()
+		.get::<MatchedPath>()
+		.map(|x| x.as_str().to_string())
+		.unwrap_or_else(|| "unmatched".to_string());
+
+	let res = next.run(req).await;
+
+	let status = res.status().as_u16().to_string();
+	metrics::histogram!("upub_http_request_duration_seconds", "method" => method.clone(), "route" => route.clone())
+		.record(start.elapsed().as_secs_f64());
+	metrics::counter!("upub_http_requests_total", "method" => method, "route" => route, "status" => status)
+		.increment(1);
+
+	res
+}
@@ -0,0 +1,43 @@
+use std::{collections::{HashSet, VecDeque}, sync::{LazyLock, Mutex}};
+
+/// recently accepted signatures, so that each can only be used once while its date is still
+/// within allowed clock skew. after that, the date check alone refuses it
+static SEEN: LazyLock<Mutex<SeenSignatures>> = LazyLock::new(Default::default);
+
+#[derive(Default)]
+struct SeenSignatures {
+	set: HashSet<String>,
+	queue: VecDeque<(chrono::DateTime<chrono::Utc>, String)>,
+}
+
+/// check that signature date is within allowed skew from now
+pub fn fresh(date: chrono::DateTime<chrono::Utc>, skew: chrono::Duration) -> bool {
+	let now = chrono::Utc::now();
+	date > now - skew && date < now + skew
+}
+
+/// remember signature as used, returns false if it was already seen
+pub fn first_use(signature: &str, skew: chrono::Duration, capacity: usize) -> bool {
+	let now = chrono::Utc::now();
+	let mut seen = match SEEN.lock() {
+		Ok(x) => x,
+		Err(e) => {
+			tracing::error!("seen signatures lock is poisoned: {e}");
+			return true; // don't break federation because of this
+		},
+	};
+
+	// signatures older than the allowed skew would get refused anyway, forget them
+	while let Some((when, _)) = seen.queue.front() {
+		if *when > now - (skew * 2) && seen.queue.len() < capacity { break }
+		if let Some((_, sig)) = seen.queue.pop_front() {
+			seen.set.remove(&sig);
+		}
+	}
+
+	if !seen.set.insert(signature.to_string()) {
+		return false;
+	}
+	seen.queue.push_back((now, signature.to_string()));
+	true
+}
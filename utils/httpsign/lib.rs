@@ -3,6 +3,9 @@ use std::collections::BTreeMap;
 use base64::Engine;
 use openssl::{hash::MessageDigest, pkey::PKey, sign::Verifier};
 
+mod message;
+pub use message::MessageSignature;
+
 #[derive(Debug, thiserror::Error)]
 pub enum HttpSignatureError {
 	#[error("openssl error: {0:?}")]
@@ -16,6 +19,12 @@ pub enum HttpSignatureError {
 
 	#[error("invalid base64: {0}")]
 	Base64(#[from] base64::DecodeError),
+
+	#[error("malformed signature: {0}")]
+	Malformed(&'static str),
+
+	#[error("unsupported signature algorithm: {0}")]
+	Unsupported(String),
 }
 
 pub fn digest(data: &str) -> String {
@@ -26,6 +35,54 @@ pub fn digest(data: &str) -> String {
 	)
 }
 
+/// RFC 9530 Content-Digest header value
+pub fn content_digest(data: &str) -> String {
+	format!("sha-256=:{}:",
+		base64::prelude::BASE64_STANDARD.encode(
+			openssl::sha::sha256(data.as_bytes())
+		)
+	)
+}
+
+/// check legacy `Digest` header (RFC 3230) against body, None if no supported algorithm is present
+pub fn verify_digest(header: &str, body: &[u8]) -> Option<bool> {
+	verify_digests(header, body, |x| x)
+}
+
+/// check RFC 9530 `Content-Digest` header against body, None if no supported algorithm is present
+pub fn verify_content_digest(header: &str, body: &[u8]) -> Option<bool> {
+	verify_digests(header, body, |x| x.trim_matches(':'))
+}
+
+fn verify_digests(header: &str, body: &[u8], unwrap: impl Fn(&str) -> &str) -> Option<bool> {
+	let mut checked = None;
+	for (alg, value) in header.split(',').filter_map(|x| x.trim().split_once('=')) {
+		let expected = match alg.to_lowercase().as_str() {
+			"sha-256" => openssl::sha::sha256(body).to_vec(),
+			"sha-512" => openssl::sha::sha512(body).to_vec(),
+			_ => continue,
+		};
+		let Ok(found) = base64::prelude::BASE64_STANDARD.decode(unwrap(value.trim())) else {
+			return Some(false);
+		};
+		if found != expected {
+			return Some(false);
+		}
+		checked = Some(true);
+	}
+	checked
+}
+
+/// only rsa keys can verify sha256 signatures: actors may publish others too (such as ed25519 ones
+/// for object proofs), and a key id pointing to them must not be mistaken for an openssl failure
+pub(crate) fn rsa_public_key(key: &str) -> Result<PKey<openssl::pkey::Public>, HttpSignatureError> {
+	let pubkey = PKey::public_key_from_pem(key.as_bytes())?;
+	if pubkey.id() != openssl::pkey::Id::RSA {
+		return Err(HttpSignatureError::Unsupported("non rsa key".to_string()));
+	}
+	Ok(pubkey)
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct HttpSignature {
 	pub key_id: String,
@@ -106,7 +163,7 @@ impl HttpSignature {
 	}
 
 	pub fn verify(&self, key: &str) -> Result<bool, HttpSignatureError> {
-		let pubkey = PKey::public_key_from_pem(key.as_bytes())?;
+		let pubkey = rsa_public_key(key)?;
 		let mut verifier = Verifier::new(MessageDigest::sha256(), &pubkey)?;
 		let signature = base64::prelude::BASE64_STANDARD.decode(&self.signature)?;
 		Ok(verifier.verify_oneshot(&signature, self.control.as_bytes())?)
@@ -153,4 +210,109 @@ mod test {
 
 		assert!(verifier.verify(&public_key).unwrap());
 	}
+
+	fn message_signature_headers() -> std::collections::BTreeMap<String, String> {
+		[
+			("content-digest".into(), super::content_digest("{\"hello\":\"world\"}")),
+			("date".into(), "Sat, 13 Apr 2024 13:36:23 GMT".into()),
+		].into()
+	}
+
+	#[test]
+	fn message_signature_signs_and_verifies() {
+		let key = openssl::rsa::Rsa::generate(2048).unwrap();
+		let private_key = std::str::from_utf8(&key.private_key_to_pem().unwrap()).unwrap().to_string();
+		let public_key = std::str::from_utf8(&key.public_key_to_pem().unwrap()).unwrap().to_string();
+		let mut signer = super::MessageSignature::new(
+			"https://example.net/actor#main-key".to_string(),
+			Some(super::MessageSignature::RSA_SHA256.to_string()),
+			&["@method", "@target-uri", "content-digest", "date"],
+			1713015383,
+		);
+
+		signer
+			.build_manually("post", "https://example.net/actor/inbox", message_signature_headers())
+			.sign(&private_key)
+			.unwrap();
+
+		let mut verifier = super::MessageSignature::parse(&signer.header_input(), &signer.header_signature()).unwrap();
+		assert_eq!(verifier.key_id, "https://example.net/actor#main-key");
+		assert_eq!(verifier.created, Some(1713015383));
+		verifier.build_manually("POST", "https://example.net/actor/inbox", message_signature_headers());
+		assert_eq!(verifier.control, signer.control);
+		assert!(verifier.verify(&public_key).unwrap());
+
+		// tampering with target uri must invalidate signature
+		verifier.build_manually("POST", "https://example.net/other/inbox", message_signature_headers());
+		assert!(!verifier.verify(&public_key).unwrap());
+	}
+
+	#[test]
+	fn digests_are_checked_against_body() {
+		let body = "{\"hello\":\"world\"}";
+		assert_eq!(super::verify_digest(&super::digest(body), body.as_bytes()), Some(true));
+		assert_eq!(super::verify_content_digest(&super::content_digest(body), body.as_bytes()), Some(true));
+		assert_eq!(super::verify_digest(&super::digest(body), b"{}"), Some(false));
+		assert_eq!(super::verify_content_digest(&super::content_digest(body), b"{}"), Some(false));
+		assert_eq!(super::verify_digest("MD5=AAAA", body.as_bytes()), None);
+	}
+
+	#[test]
+	fn message_signature_base_matches_rfc() {
+		let mut sig = super::MessageSignature::parse(
+			r#"sig1=("@method" "@authority" "@path" "content-digest");created=1618884473;keyid="test-key-rsa-pss""#,
+			"sig1=:AAAA:",
+		).unwrap();
+		sig.build_manually(
+			"POST",
+			"https://example.com/foo?param=Value&Pet=dog",
+			[("content-digest".into(), "sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrIiYllu7BNNyealdVLvRwEmTHWXvJwew==:".into())].into(),
+		);
+		assert_eq!(sig.signature, "AAAA");
+		assert_eq!(
+			sig.control,
+			"\"@method\": POST\n\"@authority\": example.com\n\"@path\": /foo\n\"content-digest\": sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrIiYllu7BNNyealdVLvRwEmTHWXvJwew==:\n\"@signature-params\": (\"@method\" \"@authority\" \"@path\" \"content-digest\");created=1618884473;keyid=\"test-key-rsa-pss\""
+		);
+	}
+
+	#[test]
+	fn message_signature_requires_request_components() {
+		let parse = |components: &str| super::MessageSignature::parse(
+			&format!(r#"sig1=({components});created=1618884473;keyid="test""#),
+			"sig1=:AAAA:",
+		).unwrap();
+		assert!(parse("").check_components(false).is_err());
+		assert!(parse(r#""date""#).check_components(false).is_err());
+		assert!(parse(r#""@method" "@authority""#).check_components(false).is_err());
+		assert!(parse(r#""@method" "@target-uri""#).check_components(false).is_ok());
+		assert!(parse(r#""@method" "@authority" "@path""#).check_components(false).is_ok());
+		assert!(parse(r#""@method" "@target-uri""#).check_components(true).is_err());
+		assert!(parse(r#""@method" "@target-uri" "content-digest""#).check_components(true).is_ok());
+	}
+
+	#[test]
+	fn non_rsa_keys_are_unsupported() {
+		let key = openssl::pkey::PKey::generate_ed25519().unwrap();
+		let public_key = std::str::from_utf8(&key.public_key_to_pem().unwrap()).unwrap().to_string();
+		let mut sig = super::MessageSignature::parse(
+			r#"sig1=("@method" "@target-uri");created=1618884473;keyid="https://example.net/actor#ed25519-key""#,
+			"sig1=:AAAA:",
+		).unwrap();
+		sig.build_manually("GET", "https://example.net/actor", Default::default());
+		assert!(matches!(sig.verify(&public_key), Err(super::HttpSignatureError::Unsupported(_))));
+		let cavage = super::HttpSignature::parse(r#"keyId="https://example.net/actor#ed25519-key",algorithm="rsa-sha256",headers="date",signature="AAAA""#);
+		assert!(matches!(cavage.verify(&public_key), Err(super::HttpSignatureError::Unsupported(_))));
+	}
+
+	#[test]
+	fn message_signature_expiration() {
+		let parse = |params: &str| super::MessageSignature::parse(
+			&format!(r#"sig1=("@method" "@target-uri");created=1618884473{params};keyid="test""#),
+			"sig1=:AAAA:",
+		).unwrap();
+		assert!(!parse("").expired(1618884473 + 3600));
+		assert!(!parse(";expires=1618884773").expired(1618884473));
+		assert!(!parse(";expires=1618884773").expired(1618884773));
+		assert!(parse(";expires=1618884773").expired(1618884774));
+	}
 }
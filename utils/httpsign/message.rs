@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+
+use base64::Engine;
+use openssl::{hash::MessageDigest, pkey::PKey, sign::Verifier};
+
+use crate::HttpSignatureError;
+
+/// RFC 9421 http message signature, carried in `Signature-Input` and `Signature` headers
+#[derive(Debug, Clone, Default)]
+pub struct MessageSignature {
+	pub label: String,
+	pub key_id: String,
+	pub algorithm: Option<String>,
+	pub created: Option<i64>,
+	pub expires: Option<i64>,
+	pub components: Vec<String>,
+	pub signature: String,
+	/// serialized signature parameters, exactly as they appear (or will appear) in Signature-Input
+	pub params: String,
+	pub control: String,
+}
+
+impl MessageSignature {
+	pub const DEFAULT_LABEL: &str = "sig1";
+	pub const RSA_SHA256: &str = "rsa-v1_5-sha256";
+
+	pub fn new(key_id: String, algorithm: Option<String>, components: &[&str], created: i64) -> Self {
+		let mut sig = MessageSignature {
+			label: Self::DEFAULT_LABEL.to_string(),
+			key_id,
+			algorithm,
+			created: Some(created),
+			expires: None,
+			components: components.iter().map(|x| x.to_string()).collect(),
+			signature: String::new(),
+			params: String::new(),
+			control: String::new(),
+		};
+		sig.params = sig.serialize_params();
+		sig
+	}
+
+	fn serialize_params(&self) -> String {
+		let components = self.components
+			.iter()
+			.map(|x| format!("\"{x}\""))
+			.collect::<Vec<String>>()
+			.join(" ");
+		let mut out = format!("({components})");
+		if let Some(created) = self.created {
+			out.push_str(&format!(";created={created}"));
+		}
+		if let Some(expires) = self.expires {
+			out.push_str(&format!(";expires={expires}"));
+		}
+		out.push_str(&format!(";keyid=\"{}\"", self.key_id));
+		if let Some(ref alg) = self.algorithm {
+			out.push_str(&format!(";alg=\"{alg}\""));
+		}
+		out
+	}
+
+	/// parse first signature from given `Signature-Input` and its value from `Signature` header
+	pub fn parse(signature_input: &str, signature: &str) -> Result<Self, HttpSignatureError> {
+		let (label, params) = signature_input
+			.trim()
+			.split_once('=')
+			.ok_or(HttpSignatureError::Malformed("missing signature label"))?;
+
+		let params = params.trim();
+		let inner_end = params.find(')').ok_or(HttpSignatureError::Malformed("unterminated component list"))?;
+		let inner = params
+			.strip_prefix('(')
+			.ok_or(HttpSignatureError::Malformed("missing component list"))?;
+		let inner = &inner[..inner_end - 1];
+
+		// params go on until next dictionary member, if any. this is a bit naive but keyids are urls
+		// and won't contain commas
+		let rest = &params[inner_end + 1..];
+		let rest = rest.split_once(',').map(|(x, _)| x).unwrap_or(rest).trim_end();
+
+		let mut sig = MessageSignature {
+			label: label.trim().to_string(),
+			params: format!("{}{rest}", &params[..=inner_end]),
+			components: inner
+				.split_whitespace()
+				.map(|x| x.trim_matches('"').to_string())
+				.collect(),
+			..Default::default()
+		};
+
+		for param in rest.split(';').filter(|x| !x.is_empty()) {
+			let Some((k, v)) = param.split_once('=') else { continue };
+			let v = v.trim_matches('"');
+			match k.trim() {
+				"keyid" => sig.key_id = v.to_string(),
+				"alg" => sig.algorithm = Some(v.to_string()),
+				"created" => sig.created = v.parse().ok(),
+				"expires" => sig.expires = v.parse().ok(),
+				_ => tracing::debug!("ignoring message signature parameter '{k}={v}'"),
+			}
+		}
+
+		if sig.key_id.is_empty() {
+			return Err(HttpSignatureError::Malformed("missing keyid"));
+		}
+
+		let prefix = format!("{}=", sig.label);
+		sig.signature = signature
+			.split(',')
+			.map(|x| x.trim())
+			.find_map(|x| x.strip_prefix(&prefix))
+			.ok_or(HttpSignatureError::Malformed("no signature for label"))?
+			.trim_matches(':')
+			.to_string();
+
+		Ok(sig)
+	}
+
+	/// RFC 9421 lets signers cover any components, even none at all: make sure this signature binds
+	/// at least method and target of the request, and its body if there is one
+	pub fn check_components(&self, has_body: bool) -> Result<(), HttpSignatureError> {
+		let covers = |c: &str| self.components.iter().any(|x| x.eq_ignore_ascii_case(c));
+		if self.components.is_empty() {
+			return Err(HttpSignatureError::Malformed("no covered components"));
+		}
+		if !covers("@method") {
+			return Err(HttpSignatureError::Malformed("method not covered"));
+		}
+		let target = covers("@target-uri") || (covers("@authority") && covers("@path"));
+		if !target {
+			return Err(HttpSignatureError::Malformed("target uri not covered"));
+		}
+		if has_body && !covers("content-digest") {
+			return Err(HttpSignatureError::Malformed("content digest not covered"));
+		}
+		Ok(())
+	}
+
+	/// whether given unix time is past the expiration set by signer, if any
+	pub fn expired(&self, now: i64) -> bool {
+		self.expires.is_some_and(|x| now > x)
+	}
+
+	pub fn header_input(&self) -> String {
+		format!("{}={}", self.label, self.params)
+	}
+
+	pub fn header_signature(&self) -> String {
+		format!("{}=:{}:", self.label, self.signature)
+	}
+
+	pub fn build_manually(&mut self, method: &str, target_uri: &str, mut headers: BTreeMap<String, String>) -> &mut Self {
+		let (authority, path, query) = split_uri(target_uri);
+		let mut out = Vec::new();
+		for component in &self.components {
+			let value = match component.as_str() {
+				"@method" => method.to_uppercase(),
+				"@target-uri" => target_uri.to_string(),
+				"@authority" => authority.to_lowercase(),
+				"@path" => path.to_string(),
+				"@query" => format!("?{query}"),
+				"@request-target" => if query.is_empty() { path.to_string() } else { format!("{path}?{query}") },
+				// TODO other derived components
+				_ => headers.remove(component).unwrap_or_default().trim().to_string(),
+			};
+			out.push(format!("\"{component}\": {value}"));
+		}
+		out.push(format!("\"@signature-params\": {}", self.params));
+		self.control = out.join("\n");
+		self
+	}
+
+	#[cfg(feature = "axum")]
+	/// `base` is the scheme and authority this request was addressed to, since behind reverse
+	/// proxies it can't be reliably guessed from the request itself
+	pub fn build_from_parts(&mut self, parts: &axum::http::request::Parts, base: &str) -> &mut Self {
+		let target = format!(
+			"{base}{}",
+			parts.uri.path_and_query().map(|x| x.as_str()).unwrap_or("/")
+		);
+		let headers = self.components
+			.iter()
+			.filter(|x| !x.starts_with('@'))
+			.map(|x| (
+				x.clone(),
+				parts.headers.get(x.as_str()).map(|x| x.to_str().unwrap_or("")).unwrap_or("").to_string()
+			))
+			.collect();
+		self.build_manually(parts.method.as_str(), &target, headers)
+	}
+
+	pub fn verify(&self, key: &str) -> Result<bool, HttpSignatureError> {
+		match self.algorithm.as_deref() {
+			None | Some(Self::RSA_SHA256) => {},
+			Some(alg) => return Err(HttpSignatureError::Unsupported(alg.to_string())),
+		}
+		let pubkey = crate::rsa_public_key(key)?;
+		let mut verifier = Verifier::new(MessageDigest::sha256(), &pubkey)?;
+		let signature = base64::prelude::BASE64_STANDARD.decode(&self.signature)?;
+		Ok(verifier.verify_oneshot(&signature, self.control.as_bytes())?)
+	}
+
+	pub fn sign(&mut self, key: &str) -> Result<&str, HttpSignatureError> {
+		let privkey = PKey::private_key_from_pem(key.as_bytes())?;
+		let mut signer = openssl::sign::Signer::new(MessageDigest::sha256(), &privkey)?;
+		signer.update(self.control.as_bytes())?;
+		self.signature = base64::prelude::BASE64_STANDARD.encode(signer.sign_to_vec()?);
+		Ok(&self.signature)
+	}
+}
+
+fn split_uri(uri: &str) -> (&str, &str, &str) {
+	let without_scheme = uri.split_once("://").map(|(_, x)| x).unwrap_or(uri);
+	let (authority, path_and_query) = match without_scheme.find('/') {
+		Some(i) => (&without_scheme[..i], &without_scheme[i..]),
+		None => (without_scheme, "/"),
+	};
+	let (path, query) = path_and_query.split_once('?').unwrap_or((path_and_query, ""));
+	(authority, path, query)
+}
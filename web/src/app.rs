@@ -185,9 +185,20 @@ pub fn App() -> impl IntoView {
 										// static pages, configs and tools
 										<Route path=path!("about") view=AboutPage />
 										<Route path=path!("config") view=move || view! { <ConfigPage setter=set_config /> } />
+										<Route path=path!("sessions") view=move || if auth.present() {
+											Either::Left(view! { <SessionsPage /> })
+										} else {
+											Either::Right(view! { <Unauthorized /> })
+										} />
+										<Route path=path!("tokens") view=move || if auth.present() {
+											Either::Left(view! { <TokensPage /> })
+										} else {
+											Either::Right(view! { <Unauthorized /> })
+										} />
 										<Route path=path!("explore") view=DebugPage />
 										<Route path=path!("search") view=SearchPage />
 										<Route path=path!("register") view=RegisterPage />
+										<Route path=path!("oauth/authorize") view=OAuthConsentPage />
 
 										// actors
 										<ParentRoute path=path!("actors/:id") view=ActorHeader > // TODO can we avoid this?
@@ -9,6 +9,8 @@ pub fn LoginBox(
 	let auth = use_context::<Auth>().expect("missing auth context");
 	let username_ref: NodeRef<leptos::html::Input> = NodeRef::new();
 	let password_ref: NodeRef<leptos::html::Input> = NodeRef::new();
+	let code_ref: NodeRef<leptos::html::Input> = NodeRef::new();
+	let (needs_code, set_needs_code) = signal(false);
 	view! {
 		<div>
 			<div class="w-100" class:hidden=move || !auth.present() >
@@ -26,11 +28,12 @@ pub fn LoginBox(
 					tracing::info!("logging in...");
 					let email = username_ref.get().map(|x| x.value()).unwrap_or("".into());
 					let password = password_ref.get().map(|x| x.value()).unwrap_or("".into());
+					let code = code_ref.get().map(|x| x.value()).filter(|x| !x.is_empty());
 					leptos::task::spawn_local(async move {
 						let res = match crate::Http::request::<LoginForm>(
 							reqwest::Method::POST,
 							&format!("{URL_BASE}/auth"),
-							Some(&LoginForm { email, password }),
+							Some(&LoginForm { email, password, code }),
 							auth,
 						).await {
 							Ok(res) => res,
@@ -42,6 +45,15 @@ pub fn LoginBox(
 								return
 							}
 						};
+						// password was right, but this user also has two factor authentication enabled
+						if res.status() == reqwest::StatusCode::PRECONDITION_REQUIRED {
+							tracing::info!("login requires a totp code");
+							set_needs_code.set(true);
+							return
+						}
+						if let Some(rf) = code_ref.get() {
+							rf.set_value("")
+						};
 						let auth_response = match res.json::<AuthResponse>().await {
 							Ok(r) => r,
 							Err(e) => {
@@ -56,6 +68,7 @@ pub fn LoginBox(
 						// update our username and token cookies
 						userid_tx.set(Some(auth_response.user));
 						token_tx.set(Some(auth_response.token));
+						set_needs_code.set(false);
 						// clear caches: we may see things differently now that we're logged in!
 						crate::cache::OBJECTS.clear();
 						crate::cache::TIMELINES.clear();
@@ -69,6 +82,9 @@ pub fn LoginBox(
 						<tr>
 							<td colspan="2"><input class="w-100" type="password" node_ref=password_ref placeholder="password" /></td>
 						</tr>
+						<tr class:hidden=move || !needs_code.get()>
+							<td colspan="2"><input class="w-100" type="text" inputmode="numeric" autocomplete="one-time-code" node_ref=code_ref placeholder="authenticator or recovery code" /></td>
+						</tr>
 						<tr>
 							<td class="w-50"><input class="w-100" type="submit" value="login" /></td>
 							<td class="w-50"><a href="/web/register"><input class="w-100" type="button" value="register" /></a></td>
@@ -85,6 +101,7 @@ pub fn LoginBox(
 struct LoginForm {
 	email: String,
 	password: String,
+	code: Option<String>,
 }
 
 
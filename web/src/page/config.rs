@@ -59,6 +59,7 @@ pub fn ConfigPage(setter: WriteSignal<crate::Config>) -> impl IntoView {
 	view! {
 		<div>
 			<p class="center mt-0"><small>config is saved in your browser local storage</small></p>
+			<p class="center mt-0" class:hidden=move || !auth.present()><small><a href="/web/sessions">manage active sessions</a>" | "<a href="/web/tokens">api tokens</a></small></p>
 			<p>
 				<span title="embedded video attachments will loop like gifs if this option is enabled">
 					<input type="checkbox" class="mr-1"
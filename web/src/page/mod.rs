@@ -7,8 +7,17 @@ pub use config::ConfigPage;
 mod debug;
 pub use debug::DebugPage;
 
+mod oauth;
+pub use oauth::OAuthConsentPage;
+
 mod register;
 pub use register::RegisterPage;
 
+mod sessions;
+pub use sessions::SessionsPage;
+
+mod tokens;
+pub use tokens::TokensPage;
+
 mod search;
 pub use search::SearchPage;
@@ -0,0 +1,98 @@
+use leptos::prelude::*;
+use leptos_router::hooks::use_query_map;
+use crate::prelude::*;
+
+// TODO this should get moved in a common crate so its not duplicated across FE/BE
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct AppInfo {
+	name: String,
+	website: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConsentForm {
+	client_id: String,
+	redirect_uri: String,
+	scope: Option<String>,
+	state: Option<String>,
+	code_challenge: Option<String>,
+	code_challenge_method: Option<String>,
+	approve: bool,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ConsentResult {
+	redirect: Option<String>,
+	code: Option<String>,
+}
+
+#[component]
+pub fn OAuthConsentPage() -> impl IntoView {
+	let auth = use_context::<Auth>().expect("missing auth context");
+	let query = use_query_map();
+	let param = move |key: &str| query.with(|q| q.get(key));
+	let (message, set_message) = signal(None::<String>);
+
+	let app = LocalResource::new(move || {
+		let client_id = param("client_id").unwrap_or_default();
+		async move { Http::fetch::<AppInfo>(&format!("{URL_BASE}/oauth/apps/{client_id}"), auth).await.ok() }
+	});
+
+	let answer = move |approve: bool| {
+		let form = ConsentForm {
+			client_id: param("client_id").unwrap_or_default(),
+			redirect_uri: param("redirect_uri").unwrap_or_default(),
+			scope: param("scope"),
+			state: param("state"),
+			code_challenge: param("code_challenge"),
+			code_challenge_method: param("code_challenge_method"),
+			approve,
+		};
+		leptos::task::spawn_local(async move {
+			match Http::request(reqwest::Method::POST, &format!("{URL_BASE}/oauth/authorize"), Some(&form), auth).await {
+				Err(e) => set_message.set(Some(e.to_string())),
+				Ok(res) => match res.error_for_status() {
+					Err(e) => set_message.set(Some(e.to_string())),
+					Ok(res) => match res.json::<ConsentResult>().await {
+						Err(e) => set_message.set(Some(format!("invalid response: {e}"))),
+						Ok(ConsentResult { redirect: Some(to), .. }) => {
+							if let Err(e) = window().location().set_href(&to) {
+								tracing::error!("could not redirect back to app: {e:?}");
+							}
+						},
+						Ok(ConsentResult { code: Some(code), .. }) => set_message.set(Some(format!("paste this code in the app: {code}"))),
+						Ok(_) => set_message.set(Some("access denied".to_string())),
+					},
+				},
+			}
+		});
+	};
+
+	view! {
+		<div class="border ma-2 pa-1">
+			{move || match app.get().map(|x| x.take()) {
+				None => view! { <p class="center"><small>loading...</small></p> }.into_any(),
+				Some(None) => view! { <p class="center"><code>unknown app</code></p> }.into_any(),
+				Some(Some(app)) if !auth.present() => view! {
+					<p class="center"><b>{app.name}</b>" wants to access your account: login first"</p>
+				}.into_any(),
+				Some(Some(app)) => view! {
+					<p class="center">
+						<b>{app.name}</b>
+						{app.website.map(|w| view! { " ("<a href=w.clone() target="_blank">{w}</a>")" })}
+						" wants to access "<code>{auth.username()}</code>
+					</p>
+					<p class="center">"requested scopes: "<code>{param("scope").unwrap_or_else(|| "read".to_string())}</code></p>
+					<p class="center"><small>"you will be sent back to "<code>{param("redirect_uri").unwrap_or_default()}</code></small></p>
+					<table class="align w-100">
+						<tr>
+							<td><input class="w-100" type="button" value="deny" on:click=move |_| answer(false) /></td>
+							<td><input class="w-100" type="button" value="allow" on:click=move |_| answer(true) /></td>
+						</tr>
+					</table>
+				}.into_any(),
+			}}
+		</div>
+		<p>{move || message.get().map(|msg| view! { <blockquote>{msg}</blockquote> })}</p>
+	}
+}
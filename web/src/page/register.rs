@@ -1,4 +1,5 @@
 use leptos::prelude::*;
+use leptos_router::hooks::use_query_map;
 use reqwest::Method;
 use crate::prelude::*;
 
@@ -23,6 +24,8 @@ pub struct RegisterForm {
 	summary: Option<String>,
 	avatar_url: Option<String>,
 	banner_url: Option<String>,
+	invite: Option<String>,
+	reason: Option<String>,
 }
 
 #[component]
@@ -34,7 +37,11 @@ pub fn RegisterPage() -> impl IntoView {
 	let summary_ref: NodeRef<leptos::html::Input> = NodeRef::new();
 	let avatar_url_ref: NodeRef<leptos::html::Input> = NodeRef::new();
 	let banner_url_ref: NodeRef<leptos::html::Input> = NodeRef::new();
+	let reason_ref: NodeRef<leptos::html::Input> = NodeRef::new();
 	let (error, set_error) = signal(None);
+	// invite links look like /web/register?invite=<code>
+	let query = use_query_map();
+	let invite = Signal::derive(move || query.with(|x| x.get("invite")).filter(|x| !x.is_empty()));
 	view! {
 		<div class="two-col">
 			<div class="border ma-2 pa-1">
@@ -47,6 +54,7 @@ pub fn RegisterPage() -> impl IntoView {
 					let summary = get_ref!(summary_ref);
 					let avatar_url = get_ref!(avatar_url_ref);
 					let banner_url = get_ref!(banner_url_ref);
+					let reason = get_ref!(reason_ref);
 
 					if email.is_none() || password.is_none() {
 						set_error.set(Some("no credentials provided".to_string()));
@@ -57,7 +65,9 @@ pub fn RegisterPage() -> impl IntoView {
 						let payload = RegisterForm {
 							username: email.unwrap_or_default(),
 							password: password.unwrap_or_default(),
-							display_name, summary, avatar_url, banner_url
+							display_name, summary, avatar_url, banner_url,
+							invite: invite.get_untracked(),
+							reason,
 						};
 						match Http::request(Method::PUT, &format!("{URL_BASE}/auth"), Some(&payload), auth).await {
 							Err(e) => set_error.set(Some(e.to_string())),
@@ -70,12 +80,17 @@ pub fn RegisterPage() -> impl IntoView {
 									reset_ref!(summary_ref);
 									reset_ref!(avatar_url_ref);
 									reset_ref!(banner_url_ref);
+									reset_ref!(reason_ref);
 									set_error.set(Some("registration successful! your user may need to be approved by an administrator before you can login".to_string()));
 								},
 							},
 						}
 					});
 				} >
+					{move || invite.get().map(|code| view! {
+						<p class="center mt-0"><small>"registering with invite "<code>{code}</code></small></p>
+					})}
+
 					<div class="col-side mb-0">username</div>
 					<div class="col-main">
 						<input class="w-100" type="text" node_ref=username_ref placeholder="doll" />
@@ -112,6 +127,14 @@ pub fn RegisterPage() -> impl IntoView {
 					<div class="col-side mb-0"><hr /></div>
 					<div class="col-main"><hr class="hidden-on-mobile" /></div>
 
+					<div class="col-side mb-0">reason</div>
+					<div class="col-main">
+						<input class="w-100" type="text" node_ref=reason_ref placeholder="why do you want to join? admins may need this to approve you" />
+					</div>
+
+					<div class="col-side mb-0"><hr /></div>
+					<div class="col-main"><hr class="hidden-on-mobile" /></div>
+
 					<input class="w-100" type="submit" value="register" />
 				</form>
 			</div>
@@ -0,0 +1,74 @@
+use leptos::prelude::*;
+use reqwest::Method;
+use crate::prelude::*;
+
+// TODO this should get moved in a common crate so its not duplicated across FE/BE
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct SessionInfo {
+	id: i64,
+	created: chrono::DateTime<chrono::Utc>,
+	last_seen: Option<chrono::DateTime<chrono::Utc>>,
+	expires: chrono::DateTime<chrono::Utc>,
+	user_agent: Option<String>,
+	app: Option<String>,
+	scopes: String,
+	current: bool,
+}
+
+#[component]
+pub fn SessionsPage() -> impl IntoView {
+	let auth = use_context::<Auth>().expect("missing auth context");
+	let (error, set_error) = signal(None::<String>);
+
+	let sessions = LocalResource::new(move || {
+		let url = format!("{}/sessions", auth.user_id());
+		async move { Http::fetch::<Vec<SessionInfo>>(&url, auth).await }
+	});
+
+	let revoke = move |url: String| {
+		leptos::task::spawn_local(async move {
+			match Http::request::<()>(Method::DELETE, &url, None, auth).await.and_then(|x| x.error_for_status()) {
+				Err(e) => set_error.set(Some(e.to_string())),
+				Ok(_) => sessions.refetch(),
+			}
+		});
+	};
+
+	view! {
+		<div>
+			<p class="center mt-0"><small>devices and apps currently logged in as you</small></p>
+			{move || match sessions.get().map(|x| x.take()) {
+				None => view! { <p class="center"><small>loading...</small></p> }.into_any(),
+				Some(Err(e)) => view! { <p class="center"><code>{e.to_string()}</code></p> }.into_any(),
+				Some(Ok(list)) => list.into_iter().map(|s| {
+					let url = format!("{}/sessions/{}", auth.user_id(), s.id);
+					view! {
+						<blockquote class="mt-1 mb-1">
+							<b>{s.app.unwrap_or_else(|| "web login".to_string())}</b>
+							{if s.current { " (this session)" } else { "" }}
+							<br />
+							<small>{s.user_agent.unwrap_or_else(|| "unknown user agent".to_string())}</small>
+							<br />
+							<small>
+								"logged in "{s.created.format("%Y/%m/%d %H:%M").to_string()}
+								", last seen "{s.last_seen.map(|x| x.format("%Y/%m/%d %H:%M").to_string()).unwrap_or_else(|| "never".to_string())}
+								", expires "{s.expires.format("%Y/%m/%d %H:%M").to_string()}
+							</small>
+							<br />
+							<small>"scopes: "<code>{s.scopes}</code></small>
+							<input class="ml-1" style="float:right" type="submit" value="revoke"
+								class:hidden=s.current
+								on:click=move |_| revoke(url.clone())
+							/>
+						</blockquote>
+					}
+				}).collect_view().into_any(),
+			}}
+			<hr />
+			<input class="w-100" type="submit" value="log out everywhere else"
+				on:click=move |_| revoke(format!("{}/sessions", auth.user_id()))
+			/>
+			<p>{move || error.get().map(|msg| view! { <blockquote>{msg}</blockquote> })}</p>
+		</div>
+	}
+}
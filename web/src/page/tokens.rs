@@ -0,0 +1,107 @@
+use leptos::prelude::*;
+use reqwest::Method;
+use crate::prelude::*;
+
+// TODO this should get moved in a common crate so its not duplicated across FE/BE
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct TokenInfo {
+	id: i64,
+	name: String,
+	scopes: String,
+	created: chrono::DateTime<chrono::Utc>,
+	last_seen: Option<chrono::DateTime<chrono::Utc>>,
+	expires: chrono::DateTime<chrono::Utc>,
+	token: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TokenForm {
+	name: String,
+	scopes: Option<String>,
+	expires_in_days: Option<i64>,
+}
+
+#[component]
+pub fn TokensPage() -> impl IntoView {
+	let auth = use_context::<Auth>().expect("missing auth context");
+	let (error, set_error) = signal(None::<String>);
+	let (created, set_created) = signal(None::<String>);
+	let name_ref: NodeRef<leptos::html::Input> = NodeRef::new();
+	let scopes_ref: NodeRef<leptos::html::Input> = NodeRef::new();
+	let expires_ref: NodeRef<leptos::html::Input> = NodeRef::new();
+
+	let tokens = LocalResource::new(move || {
+		let url = format!("{}/tokens", auth.user_id());
+		async move { Http::fetch::<Vec<TokenInfo>>(&url, auth).await }
+	});
+
+	let revoke = move |url: String| {
+		leptos::task::spawn_local(async move {
+			match Http::request::<()>(Method::DELETE, &url, None, auth).await.and_then(|x| x.error_for_status()) {
+				Err(e) => set_error.set(Some(e.to_string())),
+				Ok(_) => tokens.refetch(),
+			}
+		});
+	};
+
+	let create = move |_| {
+		let form = TokenForm {
+			name: name_ref.get().map(|x| x.value()).unwrap_or_default(),
+			scopes: scopes_ref.get().map(|x| x.value()).filter(|x| !x.is_empty()),
+			expires_in_days: expires_ref.get().and_then(|x| x.value().parse().ok()),
+		};
+		leptos::task::spawn_local(async move {
+			let url = format!("{}/tokens", auth.user_id());
+			match Http::request(Method::POST, &url, Some(&form), auth).await.and_then(|x| x.error_for_status()) {
+				Err(e) => set_error.set(Some(e.to_string())),
+				Ok(res) => match res.json::<TokenInfo>().await {
+					Err(e) => set_error.set(Some(format!("invalid response: {e}"))),
+					Ok(token) => {
+						set_created.set(token.token);
+						tokens.refetch();
+					},
+				},
+			}
+		});
+	};
+
+	view! {
+		<div>
+			<p class="center mt-0"><small>api tokens let bots and scripts act as you, without your password</small></p>
+			{move || match tokens.get().map(|x| x.take()) {
+				None => view! { <p class="center"><small>loading...</small></p> }.into_any(),
+				Some(Err(e)) => view! { <p class="center"><code>{e.to_string()}</code></p> }.into_any(),
+				Some(Ok(list)) => list.into_iter().map(|t| {
+					let url = format!("{}/tokens/{}", auth.user_id(), t.id);
+					view! {
+						<blockquote class="mt-1 mb-1">
+							<b>{t.name}</b>
+							<input class="ml-1" style="float:right" type="submit" value="revoke"
+								on:click=move |_| revoke(url.clone())
+							/>
+							<br />
+							<small>
+								"created "{t.created.format("%Y/%m/%d %H:%M").to_string()}
+								", last used "{t.last_seen.map(|x| x.format("%Y/%m/%d %H:%M").to_string()).unwrap_or_else(|| "never".to_string())}
+								", expires "{t.expires.format("%Y/%m/%d").to_string()}
+							</small>
+							<br />
+							<small>"scopes: "<code>{t.scopes}</code></small>
+						</blockquote>
+					}
+				}).collect_view().into_any(),
+			}}
+			<hr />
+			<table class="align w-100">
+				<tr><td><input class="w-100" type="text" node_ref=name_ref placeholder="name" /></td></tr>
+				<tr><td><input class="w-100" type="text" node_ref=scopes_ref placeholder="scopes, such as: read write (default read)" /></td></tr>
+				<tr><td><input class="w-100" type="number" node_ref=expires_ref placeholder="expires in days (default never)" /></td></tr>
+				<tr><td><input class="w-100" type="submit" value="create token" on:click=create /></td></tr>
+			</table>
+			<p>{move || created.get().map(|token| view! {
+				<blockquote>"copy this token now, it won't be shown again: "<code>{token}</code></blockquote>
+			})}</p>
+			<p>{move || error.get().map(|msg| view! { <blockquote>{msg}</blockquote> })}</p>
+		</div>
+	}
+}
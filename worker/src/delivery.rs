@@ -12,6 +12,16 @@ pub async fn process(ctx: Context, job: &model::job::Model) -> crate::JobResult<
 		return Ok(());
 	}
 
+	if !upub::allowlist::is_allowed(&ctx, &Context::server(target)).await? {
+		tracing::warn!("skipping delivery to {target}: instance is not allowlisted");
+		return Ok(());
+	}
+
+	if upub::blocks::is_blocked(ctx.db(), &Context::server(target), model::domain_block::Severity::Suspend).await? {
+		tracing::warn!("skipping delivery to {target}: instance is suspended");
+		return Ok(());
+	}
+
 	tracing::info!("delivering {} to {target}", job.activity);
 
 	let Some(activity) = model::activity::Entity::find_by_ap_id(&job.activity)
@@ -38,7 +48,7 @@ pub async fn process(ctx: Context, job: &model::job::Model) -> crate::JobResult<
 				if let Some(o) = model::object::Entity::find_by_ap_id(oid).one(ctx.db()).await? {
 					Some(ctx.ap(o))
 				} else if let Some(a) = model::actor::Entity::find_by_ap_id(oid).one(ctx.db()).await? {
-					Some(ctx.ap(a))
+					Some(upub::keys::publish(ctx.db(), ctx.ap(a)).await?)
 				} else {
 					None
 				}
@@ -60,16 +70,28 @@ pub async fn process(ctx: Context, job: &model::job::Model) -> crate::JobResult<
 		return Ok(());
 	};
 
-	let Some(key) = actor.private_key
+	let Some((key_id, key)) = upub::keys::signing(ctx.db(), &actor).await?
 	else {
 		tracing::error!("abandoning delivery from actor without private key {}: {job:#?}", job.actor);
 		return Ok(());
 	};
 
-	Context::request(
+	let Some(payload) = ctx.policies().apply(&ctx, upub::policy::Direction::Outbound, &Context::server(target), payload).await? else {
+		return Ok(());
+	};
+
+	let mut payload = payload.ld_context();
+	if let Some((proof_key_id, proof_key)) = upub::keys::assertion(ctx.db(), &actor.id).await? {
+		match upub::integrity::sign(payload.clone(), &proof_key_id, &proof_key) {
+			Ok(signed) => payload = signed,
+			Err(e) => tracing::warn!("could not attach integrity proof to {}: {e}", job.activity),
+		}
+	}
+
+	ctx.request(
 		reqwest::Method::POST, target,
-		Some(&serde_json::to_string(&payload.ld_context()).unwrap()),
-		&job.actor, &key, ctx.domain()
+		Some(&serde_json::to_string(&payload).unwrap()),
+		&key_id, &key, ctx.domain()
 	).await?;
 
 	Ok(())
@@ -1,7 +1,12 @@
 use reqwest::StatusCode;
-use sea_orm::{ColumnTrait, EntityTrait, Order, QueryFilter, QueryOrder};
+use sea_orm::{sea_query::{Expr, LockBehavior, LockType}, ColumnTrait, Condition, ConnectionTrait, DbBackend, EntityTrait, Order, QueryFilter, QueryOrder, QuerySelect, TransactionTrait};
 
-use upub::{model, traits::{fetch::RequestError, process::ProcessorError}, Context};
+use upub::{ext::LoggableError, model, traits::{fetch::RequestError, process::ProcessorError}, Context};
+
+use crate::scheduler::DomainScheduler;
+
+/// postgres channel notified whenever new jobs get inserted, see migration m20261018_000001
+pub const JOBS_NOTIFY_CHANNEL: &str = "upub_jobs";
 
 #[derive(Debug, thiserror::Error)]
 pub enum JobError {
@@ -31,15 +36,19 @@ pub type JobResult<T> = Result<T, JobError>;
 
 #[allow(async_fn_in_trait)]
 pub trait JobDispatcher : Sized {
-	async fn poll(&self, filter: Option<model::job::JobType>) -> JobResult<Option<model::job::Model>>;
+	async fn poll(&self, filter: Option<model::job::JobType>, busy: &[String]) -> JobResult<Option<model::job::Model>>;
 	async fn lock(&self, job_internal: i64) -> JobResult<bool>;
+	async fn claim(&self, filter: Option<model::job::JobType>, busy: &[String]) -> JobResult<Option<model::job::Model>>;
+	async fn heartbeat(&self, job_internal: i64) -> JobResult<()>;
+	async fn release(&self, job_internal: i64) -> JobResult<()>;
 	async fn run(self, concurrency: usize, poll_interval: u64, job_filter: Option<model::job::JobType>, stop: impl crate::StopToken, wake: impl crate::WakeToken);
 }
 
 impl JobDispatcher for Context {
-	async fn poll(&self, filter: Option<model::job::JobType>) -> JobResult<Option<model::job::Model>> {
+	async fn poll(&self, filter: Option<model::job::JobType>, busy: &[String]) -> JobResult<Option<model::job::Model>> {
 		let mut s = model::job::Entity::find()
-			.filter(model::job::Column::NotBefore.lte(chrono::Utc::now()));
+			.filter(model::job::Column::NotBefore.lte(chrono::Utc::now()))
+			.filter(not_busy(busy));
 
 		if let Some(t) = filter {
 			s = s.filter(model::job::Column::JobType.eq(t));
@@ -70,7 +79,94 @@ impl JobDispatcher for Context {
 		Ok(true)
 	}
 
+	async fn claim(&self, filter: Option<model::job::JobType>, busy: &[String]) -> JobResult<Option<model::job::Model>> {
+		if self.db().get_database_backend() != DbBackend::Postgres {
+			// no row locks on sqlite: grab the oldest job and "lock" it by deleting it, if another
+			// worker deleted it first just try with the next one
+			loop {
+				let Some(job) = self.poll(filter, busy).await? else { return Ok(None) };
+				if self.lock(job.internal).await? {
+					return Ok(Some(job));
+				}
+			}
+		}
+
+		// on postgres jobs stay in the table while being processed, leased to whoever claimed them.
+		// if a worker crashes its lease eventually expires and the job becomes available again
+		let now = chrono::Utc::now();
+		let tx = self.db().begin().await?;
+		let mut s = model::job::Entity::find()
+			.filter(model::job::Column::NotBefore.lte(now))
+			.filter(
+				Condition::any()
+					.add(model::job::Column::LockedUntil.is_null())
+					.add(model::job::Column::LockedUntil.lt(now))
+			)
+			.filter(not_busy(busy));
+
+		if let Some(t) = filter {
+			s = s.filter(model::job::Column::JobType.eq(t));
+		}
+
+		let Some(mut job) = s
+			.order_by(model::job::Column::NotBefore, Order::Asc)
+			.lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+			.one(&tx)
+			.await?
+		else {
+			tx.commit().await?;
+			return Ok(None);
+		};
+
+		let lease = now + std::time::Duration::from_secs(self.cfg().worker.job_lease_seconds);
+		model::job::Entity::update_many()
+			.col_expr(model::job::Column::LockedUntil, Expr::value(lease))
+			.filter(model::job::Column::Internal.eq(job.internal))
+			.exec(&tx)
+			.await?;
+
+		tx.commit().await?;
+
+		job.locked_until = Some(lease);
+		Ok(Some(job))
+	}
+
+	async fn heartbeat(&self, job_internal: i64) -> JobResult<()> {
+		let lease = chrono::Utc::now() + std::time::Duration::from_secs(self.cfg().worker.job_lease_seconds);
+		model::job::Entity::update_many()
+			.col_expr(model::job::Column::LockedUntil, Expr::value(lease))
+			.filter(model::job::Column::Internal.eq(job_internal))
+			.exec(self.db())
+			.await?;
+		Ok(())
+	}
+
+	async fn release(&self, job_internal: i64) -> JobResult<()> {
+		// on sqlite jobs got deleted when claimed, nothing left to do
+		if self.db().get_database_backend() == DbBackend::Postgres {
+			self.lock(job_internal).await?;
+		}
+		Ok(())
+	}
+
 	async fn run(self, concurrency: usize, poll_interval: u64, job_filter: Option<model::job::JobType>, stop: impl crate::StopToken, mut wake: impl crate::WakeToken) {
+		// on postgres other processes (api server, other workers) may enqueue jobs too: a trigger
+		// notifies every insertion, so listen for those to wake up without waiting for next poll
+		let mut listener = match self.db().get_database_backend() {
+			DbBackend::Postgres => {
+				match listen(self.db().get_postgres_connection_pool()).await {
+					Ok(l) => Some(l),
+					Err(e) => {
+						tracing::error!("could not listen for job notifications, relying on polling: {e}");
+						None
+					},
+				}
+			},
+			_ => None,
+		};
+
+		let scheduler = DomainScheduler::default();
+
 		macro_rules! restart {
 			(now) => { continue };
 			() => {
@@ -78,6 +174,8 @@ impl JobDispatcher for Context {
 					tokio::select! {
 						_ = tokio::time::sleep(std::time::Duration::from_secs(poll_interval)) => {},
 						_ = wake.wait() => {},
+						_ = notified(listener.as_mut()) => {},
+						_ = scheduler.released() => {},
 					}
 					continue;
 				}
@@ -85,30 +183,34 @@ impl JobDispatcher for Context {
 		}
 
 		let mut pool = tokio::task::JoinSet::new();
+		let mut last_depth_check = std::time::Instant::now() - std::time::Duration::from_secs(poll_interval);
 	
 		loop {
 			if stop.stop() { break }
 
-			let job = match self.poll(job_filter).await {
+			if last_depth_check.elapsed().as_secs() >= poll_interval {
+				last_depth_check = std::time::Instant::now();
+				record_queue_depth(self.db()).await;
+			}
+
+			// skip instances which already have enough deliveries in flight, or asked us to back off,
+			// and instances already served in this round, so that one huge backlog towards a slow
+			// instance doesn't starve every other delivery
+			let mut skip = scheduler.busy(&self.cfg().worker);
+			skip.extend(scheduler.served());
+			let job = match self.claim(job_filter, &skip).await {
 				Ok(Some(j)) => j,
+				Ok(None) if scheduler.next_round() => restart!(now),
 				Ok(None) => restart!(),
 				Err(e) => {
-					tracing::error!("error polling for jobs: {e}");
+					tracing::error!("error claiming job: {e}");
 					restart!()
 				},
 			};
 	
-			match self.lock(job.internal).await {
-				Ok(true) => {},
-				Ok(false) => restart!(now),
-				Err(e) => {
-					tracing::error!("error locking job: {e}");
-					restart!()
-				},
-			}
-	
 			if chrono::Utc::now() > job.published + chrono::Duration::days(self.cfg().security.job_expiration_days as i64) {
 				tracing::info!("dropping expired job {job:?}");
+				self.release(job.internal).await.err_failed("failed releasing expired job");
 				restart!(now);
 			}
 
@@ -120,18 +222,33 @@ impl JobDispatcher for Context {
 					.await
 				{
 					tracing::info!("dropping already processed job '{}'", job.activity);
+					self.release(job.internal).await.err_failed("failed releasing processed job");
 					restart!(now);
 				}
 			}
 
+			metrics::histogram!("upub_job_wait_seconds", "type" => job.job_type.as_str())
+				.record((chrono::Utc::now() - job.not_before).num_milliseconds().max(0) as f64 / 1000.);
+
+			let permit = job.domain.clone().map(|d| scheduler.acquire(d, &self.cfg().worker));
+			let _scheduler = scheduler.clone();
 			let _ctx = self.clone();
 			pool.spawn(async move {
+				let heartbeat = job.locked_until.map(|_| tokio::spawn(keep_alive(_ctx.clone(), job.internal)));
+				let start = std::time::Instant::now();
+
 				let res = match job.job_type {
 					model::job::JobType::Inbound => crate::inbound::process(_ctx.clone(), &job).await,
 					model::job::JobType::Outbound => crate::outbound::process(_ctx.clone(), &job).await,
 					model::job::JobType::Delivery => crate::delivery::process(_ctx.clone(), &job).await,
 				};
 
+				if let Some(heartbeat) = heartbeat {
+					heartbeat.abort();
+				}
+
+				record_outcome(&job, &res, start.elapsed());
+
 				match res {
 					Ok(()) => tracing::debug!("job {} completed", job.activity),
 					Err(JobError::Json(x)) =>
@@ -150,6 +267,17 @@ impl JobDispatcher for Context {
 						tracing::warn!("dropping job because requested resource is no longer available: {e}"),
 					Err(JobError::ProcessorError(ProcessorError::PullError(RequestError::Malformed(f)))) => 
 						tracing::warn!("dropping job because requested resource could not be verified (fetch is invalid AP object: {f})"),
+					Err(JobError::DeliveryError(RequestError::RateLimited(until))) |
+					Err(JobError::ProcessorError(ProcessorError::PullError(RequestError::RateLimited(until)))) => {
+						let until = until.unwrap_or_else(||
+							chrono::Utc::now() + std::time::Duration::from_secs(_ctx.cfg().worker.default_retry_after_seconds)
+						);
+						if let (model::job::JobType::Delivery, Some(domain)) = (job.job_type, &job.domain) {
+							_scheduler.throttle(domain, until);
+						}
+						tracing::warn!("remote is rate limiting, rescheduling job '{}' at {until}", job.activity);
+						reinsert(&_ctx, &job, job.clone().reschedule(until), poll_interval).await;
+					},
 					Err(e) => {
 						if let JobError::ProcessorError(ProcessorError::PullError(RequestError::Fetch(status, ref e))) = e {
 							// TODO maybe convert this in generic .is_client_error() check, but excluding 401s
@@ -157,26 +285,17 @@ impl JobDispatcher for Context {
 							//      just want to drop lemmy.cafe jobs
 							if status.as_u16() == 447 {
 								tracing::warn!("dropping job with non-standard error {status} because requested resource is not available: {e}");
+								_ctx.release(job.internal).await.err_failed("failed releasing dropped job");
 								return;
 							}
 						}
 						tracing::error!("failed processing job '{}': {e}", job.activity);
-						let active = job.clone().repeat(Some(e.to_string()));
-						let mut count = 0;
-						loop {
-							match model::job::Entity::insert(active.clone()).exec(_ctx.db()).await {
-								Err(e) => tracing::error!("could not insert back job '{}': {e}", job.activity),
-								Ok(_) => break,
-							}
-							count += 1;
-							if count > _ctx.cfg().security.reinsertion_attempt_limit {
-								tracing::error!("reached job reinsertion limit, dropping {job:#?}");
-								break;
-							}
-							tokio::time::sleep(std::time::Duration::from_secs(poll_interval)).await;
-						}
+						reinsert(&_ctx, &job, job.clone().repeat(Some(e.to_string())), poll_interval).await;
 					}
 				}
+
+				_ctx.release(job.internal).await.err_failed("failed releasing completed job");
+				drop(permit);
 			});
 
 			while pool.len() >= concurrency {
@@ -194,3 +313,83 @@ impl JobDispatcher for Context {
 
 	}
 }
+
+async fn reinsert(ctx: &Context, job: &model::job::Model, active: model::job::ActiveModel, poll_interval: u64) {
+	let mut count = 0;
+	loop {
+		match model::job::Entity::insert(active.clone()).exec(ctx.db()).await {
+			Err(e) => tracing::error!("could not insert back job '{}': {e}", job.activity),
+			Ok(_) => break,
+		}
+		count += 1;
+		if count > ctx.cfg().security.reinsertion_attempt_limit {
+			tracing::error!("reached job reinsertion limit, dropping {job:#?}");
+			break;
+		}
+		tokio::time::sleep(std::time::Duration::from_secs(poll_interval)).await;
+	}
+}
+
+fn record_outcome(job: &model::job::Model, res: &JobResult<()>, elapsed: std::time::Duration) {
+	let outcome = match res {
+		Ok(()) => "ok",
+		Err(JobError::DeliveryError(RequestError::RateLimited(_))) |
+		Err(JobError::ProcessorError(ProcessorError::PullError(RequestError::RateLimited(_)))) => "rate_limited",
+		Err(_) => "error",
+	};
+	metrics::histogram!("upub_job_duration_seconds", "type" => job.job_type.as_str()).record(elapsed.as_secs_f64());
+	metrics::counter!("upub_jobs_processed_total", "type" => job.job_type.as_str(), "outcome" => outcome).increment(1);
+	if let (model::job::JobType::Delivery, Err(_), Some(domain)) = (job.job_type, res, &job.domain) {
+		metrics::counter!("upub_delivery_failures_total", "domain" => upub::ext::metrics_domain(domain)).increment(1);
+	}
+}
+
+async fn record_queue_depth(db: &sea_orm::DatabaseConnection) {
+	use sea_orm::PaginatorTrait;
+	for job_type in [model::job::JobType::Inbound, model::job::JobType::Outbound, model::job::JobType::Delivery] {
+		match model::job::Entity::find()
+			.filter(model::job::Column::JobType.eq(job_type))
+			.count(db)
+			.await
+		{
+			Ok(count) => metrics::gauge!("upub_jobs_queued", "type" => job_type.as_str()).set(count as f64),
+			Err(e) => tracing::warn!("could not count queued jobs: {e}"),
+		}
+	}
+}
+
+fn not_busy(busy: &[String]) -> Condition {
+	if busy.is_empty() {
+		return Condition::all();
+	}
+	Condition::any()
+		.add(model::job::Column::Domain.is_null())
+		.add(model::job::Column::Domain.is_not_in(busy.iter().cloned()))
+}
+
+async fn keep_alive(ctx: Context, job_internal: i64) {
+	let interval = std::time::Duration::from_secs(ctx.cfg().worker.job_heartbeat_seconds);
+	loop {
+		tokio::time::sleep(interval).await;
+		if let Err(e) = ctx.heartbeat(job_internal).await {
+			tracing::warn!("failed extending lease of job {job_internal}: {e}");
+		}
+	}
+}
+
+async fn listen(pool: &sea_orm::sqlx::PgPool) -> Result<sea_orm::sqlx::postgres::PgListener, sea_orm::sqlx::Error> {
+	let mut listener = sea_orm::sqlx::postgres::PgListener::connect_with(pool).await?;
+	listener.listen(JOBS_NOTIFY_CHANNEL).await?;
+	Ok(listener)
+}
+
+async fn notified(listener: Option<&mut sea_orm::sqlx::postgres::PgListener>) {
+	match listener {
+		None => std::future::pending().await,
+		Some(l) => if let Err(e) = l.recv().await {
+			tracing::warn!("error receiving job notification: {e}");
+			// don't spin if connection dropped, listener will reconnect on next recv
+			tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+		},
+	}
+}
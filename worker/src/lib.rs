@@ -2,6 +2,8 @@ pub mod dispatcher;
 pub mod inbound;
 pub mod outbound;
 pub mod delivery;
+pub mod scheduler;
+pub mod maintenance;
 
 pub use dispatcher::{JobError, JobResult};
 
@@ -16,11 +18,21 @@ pub fn spawn(
 	use dispatcher::JobDispatcher;
 	tokio::spawn(async move {
 		tracing::info!("starting worker task");
-		ctx.run(concurrency, poll, filter, stop, wake).await
+		let maintenance = tokio::spawn(maintenance::run(ctx.clone(), stop.clone()));
+		ctx.run(concurrency, poll, filter, stop, wake).await;
+		maintenance.abort();
 	})
 }
 
-pub trait StopToken: Sync + Send + 'static {
+/// export prometheus metrics on a standalone http listener, for workers running without api routes
+pub fn serve_metrics(bind: std::net::SocketAddr) -> Result<(), metrics_exporter_prometheus::BuildError> {
+	tracing::info!("serving worker metrics on {bind}");
+	metrics_exporter_prometheus::PrometheusBuilder::new()
+		.with_http_listener(bind)
+		.install()
+}
+
+pub trait StopToken: Sync + Send + Clone + 'static {
 	fn stop(&self) -> bool;
 }
 
@@ -0,0 +1,125 @@
+use sea_orm::{sea_query::{Expr, OnConflict}, ColumnTrait, Condition, DbErr, EntityTrait, QueryFilter};
+use upub::{model, Context};
+
+use crate::StopToken;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaintenanceTask {
+	UpdateActors,
+	Count,
+	Thread,
+	ExpiredSessions,
+}
+
+impl MaintenanceTask {
+	pub const ALL: [MaintenanceTask; 4] = [
+		MaintenanceTask::UpdateActors,
+		MaintenanceTask::Count,
+		MaintenanceTask::Thread,
+		MaintenanceTask::ExpiredSessions,
+	];
+
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			MaintenanceTask::UpdateActors => "update-actors",
+			MaintenanceTask::Count => "count",
+			MaintenanceTask::Thread => "thread",
+			MaintenanceTask::ExpiredSessions => "expired-sessions",
+		}
+	}
+
+	pub fn interval(&self, cfg: &upub::config::MaintenanceConfig) -> Option<chrono::Duration> {
+		let hours = match self {
+			MaintenanceTask::UpdateActors => cfg.update_actors_hours,
+			MaintenanceTask::Count => cfg.count_hours,
+			MaintenanceTask::Thread => cfg.thread_hours,
+			MaintenanceTask::ExpiredSessions => cfg.expired_sessions_hours,
+		};
+		if hours == 0 { return None }
+		Some(chrono::Duration::hours(hours as i64))
+	}
+
+	pub async fn run(&self, ctx: &Context) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+		let cfg = &ctx.cfg().worker.maintenance;
+		match self {
+			MaintenanceTask::UpdateActors =>
+				upub::maintenance::update_actors(ctx, cfg.update_actors_stale_days, Some(cfg.update_actors_limit)).await?,
+			MaintenanceTask::Count =>
+				upub::maintenance::count(ctx, true, true, true).await?,
+			MaintenanceTask::Thread =>
+				upub::maintenance::thread(ctx).await?,
+			MaintenanceTask::ExpiredSessions => {
+				// oauth sessions can still be refreshed for a while after expiring
+				let refreshable = chrono::Utc::now() - chrono::Duration::days(ctx.cfg().security.refresh_duration_days);
+				let res = model::session::Entity::delete_many()
+					.filter(model::session::Column::Expires.lt(chrono::Utc::now()))
+					.filter(Condition::any()
+						.add(model::session::Column::Refresh.is_null())
+						.add(model::session::Column::Expires.lt(refreshable))
+					)
+					.exec(ctx.db())
+					.await?;
+				tracing::info!("removed {} expired sessions", res.rows_affected);
+				let res = model::oauth_code::Entity::delete_many()
+					.filter(model::oauth_code::Column::Expires.lt(chrono::Utc::now()))
+					.exec(ctx.db())
+					.await?;
+				tracing::info!("removed {} expired oauth codes", res.rows_affected);
+			},
+		}
+		Ok(())
+	}
+}
+
+/// atomically mark task as ran now, if it's due. returns false if it's not due yet, or if another
+/// worker got to it first
+pub async fn claim(ctx: &Context, task: MaintenanceTask, interval: chrono::Duration) -> Result<bool, DbErr> {
+	let now = chrono::Utc::now();
+
+	// make sure there's a row to compete on: first run ever is always due
+	model::maintenance::Entity::insert(model::maintenance::ActiveModel {
+		internal: sea_orm::ActiveValue::NotSet,
+		task: sea_orm::ActiveValue::Set(task.as_str().to_string()),
+		last_run: sea_orm::ActiveValue::Set(chrono::DateTime::UNIX_EPOCH),
+	})
+		.on_conflict(OnConflict::column(model::maintenance::Column::Task).do_nothing().to_owned())
+		.exec_without_returning(ctx.db())
+		.await?;
+
+	let res = model::maintenance::Entity::update_many()
+		.col_expr(model::maintenance::Column::LastRun, Expr::value(now))
+		.filter(model::maintenance::Column::Task.eq(task.as_str()))
+		.filter(model::maintenance::Column::LastRun.lte(now - interval))
+		.exec(ctx.db())
+		.await?;
+
+	Ok(res.rows_affected > 0)
+}
+
+pub async fn run(ctx: Context, stop: impl StopToken) {
+	let check = std::time::Duration::from_secs(ctx.cfg().worker.maintenance.check_interval_seconds);
+
+	loop {
+		if stop.stop() { break }
+
+		for task in MaintenanceTask::ALL {
+			let Some(interval) = task.interval(&ctx.cfg().worker.maintenance) else { continue };
+
+			match claim(&ctx, task, interval).await {
+				Ok(false) => continue,
+				Err(e) => {
+					tracing::error!("could not check maintenance task {}: {e}", task.as_str());
+					continue;
+				},
+				Ok(true) => {
+					tracing::info!("running maintenance task {}", task.as_str());
+					if let Err(e) = task.run(&ctx).await {
+						tracing::error!("failed running maintenance task {}: {e}", task.as_str());
+					}
+				},
+			}
+		}
+
+		tokio::time::sleep(check).await;
+	}
+}
@@ -0,0 +1,106 @@
+use std::{collections::{HashMap, HashSet}, sync::{Arc, Mutex}};
+
+use upub::config::WorkerConfig;
+
+/// keeps track of deliveries in flight towards each remote domain, so that the dispatcher can
+/// skip domains which are saturated or which asked us to slow down, and of domains already served
+/// in current round, so that jobs get claimed round-robin across domains rather than just oldest
+/// first. this is per-worker: multiple worker processes each get their own allowance and rounds
+#[derive(Clone, Default)]
+pub struct DomainScheduler(Arc<SchedulerInner>);
+
+#[derive(Default)]
+struct SchedulerInner {
+	state: Mutex<SchedulerState>,
+	released: tokio::sync::Notify,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+	in_flight: HashMap<String, usize>,
+	not_before: HashMap<String, chrono::DateTime<chrono::Utc>>,
+	served: HashSet<String>,
+}
+
+/// start a new round anyway after serving this many domains, to keep claim queries small
+const ROUND_MAX_DOMAINS: usize = 500;
+
+impl DomainScheduler {
+	/// domains which shouldn't receive more deliveries right now
+	pub fn busy(&self, cfg: &WorkerConfig) -> Vec<String> {
+		let now = chrono::Utc::now();
+		let mut state = self.0.state.lock().expect("domain scheduler lock poisoned");
+		state.not_before.retain(|_, until| *until > now);
+		let mut out : Vec<String> = state.not_before.keys().cloned().collect();
+		for (domain, count) in state.in_flight.iter() {
+			if *count >= cfg.max_concurrent(domain) && !state.not_before.contains_key(domain) {
+				out.push(domain.clone());
+			}
+		}
+		out
+	}
+
+	/// domains which got a delivery in current round, and should wait until every other domain with
+	/// pending deliveries got one too
+	pub fn served(&self) -> Vec<String> {
+		let state = self.0.state.lock().expect("domain scheduler lock poisoned");
+		state.served.iter().cloned().collect()
+	}
+
+	/// every domain with pending deliveries got its turn, start over. returns false if nothing was
+	/// served in this round, meaning there's nothing else to claim
+	pub fn next_round(&self) -> bool {
+		let mut state = self.0.state.lock().expect("domain scheduler lock poisoned");
+		let served = !state.served.is_empty();
+		state.served.clear();
+		served
+	}
+
+	/// mark a delivery towards this domain as started, it's considered finished when the returned
+	/// permit gets dropped
+	pub fn acquire(&self, domain: String, cfg: &WorkerConfig) -> DomainPermit {
+		let interval = cfg.min_interval(&domain);
+		let mut state = self.0.state.lock().expect("domain scheduler lock poisoned");
+		*state.in_flight.entry(domain.clone()).or_default() += 1;
+		if state.served.len() >= ROUND_MAX_DOMAINS {
+			state.served.clear();
+		}
+		state.served.insert(domain.clone());
+		if !interval.is_zero() {
+			let until = chrono::Utc::now() + interval;
+			let prev = state.not_before.entry(domain.clone()).or_insert(until);
+			*prev = until.max(*prev);
+		}
+		DomainPermit { scheduler: self.clone(), domain }
+	}
+
+	/// don't deliver anything to this domain before given time
+	pub fn throttle(&self, domain: &str, until: chrono::DateTime<chrono::Utc>) {
+		let mut state = self.0.state.lock().expect("domain scheduler lock poisoned");
+		let prev = state.not_before.entry(domain.to_string()).or_insert(until);
+		*prev = until.max(*prev);
+	}
+
+	/// resolves after some delivery finished, freeing a slot for its domain
+	pub async fn released(&self) {
+		self.0.released.notified().await
+	}
+}
+
+pub struct DomainPermit {
+	scheduler: DomainScheduler,
+	domain: String,
+}
+
+impl Drop for DomainPermit {
+	fn drop(&mut self) {
+		let mut state = self.scheduler.0.state.lock().expect("domain scheduler lock poisoned");
+		if let Some(count) = state.in_flight.get_mut(&self.domain) {
+			*count = count.saturating_sub(1);
+			if *count == 0 {
+				state.in_flight.remove(&self.domain);
+			}
+		}
+		self.scheduler.0.released.notify_one();
+	}
+}